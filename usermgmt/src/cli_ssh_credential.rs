@@ -1,30 +1,36 @@
+use std::sync::Arc;
+
 use log::info;
 use once_cell::sync::OnceCell;
-use usermgmt_lib::cli::OptFilePath;
+use usermgmt_lib::cli::{CredentialArgs, OptFilePath};
 use usermgmt_lib::prelude::*;
 
-use usermgmt_lib::ssh::SshKeyPair;
+use usermgmt_lib::ssh::{SshKeyPair, SshPublicKeySuggestion};
 use usermgmt_lib::{
     config::MgmtConfig,
     prelude::{anyhow, AppResult},
     ssh::SshCredentials,
 };
 
-use crate::user_input;
+use crate::{cli_user_input, user_input};
 
 #[derive(Debug, Clone)]
-/// Username are retrieved by a terminal prompt or by a default one if provided in the
-/// configuration.
-/// Password are retrieved by a terminal prompt.
+/// Username and password are taken from the sources given via CLI.
+/// Without a source the username is retrieved by a terminal prompt or by a default one
+/// if provided in the configuration.
+/// Without a source the password is retrieved by a terminal prompt.
+/// Clones share the retrieved username and password.
+/// This way every source is read and every prompt is shown only once.
 pub struct CliSshCredential {
     default_ssh_user: String,
-    username: OnceCell<String>,
-    password: OnceCell<String>,
+    username: Arc<OnceCell<String>>,
+    password: Arc<OnceCell<String>>,
     ssh_key_path: Option<SshKeyPair>,
+    sources: CredentialArgs,
 }
 
 impl CliSshCredential {
-    pub fn new(config: &MgmtConfig, on_which_sys: &OptFilePath) -> Self {
+    pub fn new(config: &MgmtConfig, on_which_sys: &OptFilePath, sources: &CredentialArgs) -> Self {
         let ssh_key_path = on_which_sys
             .as_ref()
             .cloned()
@@ -35,36 +41,67 @@ impl CliSshCredential {
             password: Default::default(),
             default_ssh_user: config.default_ssh_user.clone(),
             ssh_key_path,
+            sources: sources.clone(),
         }
     }
 }
 
 impl SshCredentials for CliSshCredential {
-    /// Returns given username of user or the default user name if the user has given no username
+    /// Returns given username of user or the default user name if the user has given no username.
+    /// Without a source the default user name is taken right away if asking is not allowed.
     ///
     /// # Errors
     ///
+    /// - If fetching from the source fails. See [`cli_user_input::from_source_or_prompt`].
     /// - If the terminal prompt fails. See [`user_input::ask_for_line_from_user_over_term`].
     fn username(&self) -> AppResult<&str> {
         let username = self.username.get_or_try_init(|| {
-            user_input::ask_for_line_from_user_over_term(
-                "Enter your SSH username",
-                Some(self.default_ssh_user.as_str()),
+            if self.sources.non_interactive && self.sources.ssh_user_from.is_none() {
+                return Ok(self.default_ssh_user.clone());
+            }
+            cli_user_input::from_source_or_prompt(
+                self.sources.ssh_user_from.as_ref(),
+                self.sources.non_interactive,
+                "SSH username",
+                "--ssh-user-from",
+                || {
+                    user_input::ask_for_line_from_user_over_term(
+                        "Enter your SSH username",
+                        Some(self.default_ssh_user.as_str()),
+                    )
+                },
             )
         })?;
 
         Ok(username)
     }
 
-    /// Password is provided by a terminal prompt.
+    /// Password is provided by a terminal prompt without a source.
+    /// A key pair without a source is used without a passphrase if asking is not allowed.
     ///
     /// # Errors
     ///
+    /// - If fetching from the source fails. See [`cli_user_input::from_source_or_prompt`].
     /// - If the terminal prompt fails. See [`user_input::cli_ask_for_password`].
     fn password(&self) -> AppResult<&str> {
         let password = self.password.get_or_try_init(|| {
-            let from_prompt = user_input::cli_ask_for_password("Enter your SSH password: ")?;
-            Ok::<String, AppError>(from_prompt.unwrap_or_default())
+            let without_prompt = self.sources.non_interactive
+                && self.sources.ssh_password_from.is_none()
+                && self.ssh_key_path.is_some();
+            if without_prompt {
+                return Ok(String::new());
+            }
+            cli_user_input::from_source_or_prompt(
+                self.sources.ssh_password_from.as_ref(),
+                self.sources.non_interactive,
+                "SSH password",
+                "--ssh-password-from",
+                || {
+                    let from_prompt =
+                        user_input::cli_ask_for_password("Enter your SSH password: ")?;
+                    Ok::<String, AppError>(from_prompt.unwrap_or_default())
+                },
+            )
         })?;
 
         Ok(password)
@@ -72,12 +109,16 @@ impl SshCredentials for CliSshCredential {
 
     /// # Errors
     ///
+    /// - If asking is not allowed. The key must then be chosen via path to its key pair.
     /// - If reading the user choice from the terminals fails. See [`user_input::line_input_from_user`].
     /// - If the user enters a selection index greater than the greatest selection index.
-    fn auth_agent_resolve(
-        &self,
-        many_keys: Vec<usermgmt_lib::ssh::SshPublicKeySuggestion>,
-    ) -> AppResult<usize> {
+    fn auth_agent_resolve(&self, many_keys: Vec<SshPublicKeySuggestion>) -> AppResult<usize> {
+        if self.sources.non_interactive {
+            bail!(
+                "Found more than one key in ssh agent but choosing one is not allowed via --non-interactive. \
+                Provide the key via --ssh-path or the field ssh_key_path in the configuration"
+            );
+        }
         let length = many_keys.len();
         let last_index = length.saturating_sub(1);
         println!("Found more than one key in ssh agent !");
@@ -97,7 +138,7 @@ impl SshCredentials for CliSshCredential {
             Err(anyhow!("Choice should between {} and {}", 0, last_index))
         } else {
             info!("{}. ssh key is chosen", user_choice);
-            Ok(last_index)
+            Ok(user_choice)
         }
     }
 
@@ -105,3 +146,45 @@ impl SshCredentials for CliSshCredential {
         self.ssh_key_path.as_ref()
     }
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn read_source_only_once_for_all_clones() {
+        let runs = std::env::temp_dir().join(format!("usermgmt_ssh_runs_{}", std::process::id()));
+        let _ = std::fs::remove_file(&runs);
+        let sources = CredentialArgs {
+            non_interactive: true,
+            ssh_password_from: Some(
+                format!("cmd:echo run >> {}; echo secret", runs.display())
+                    .parse()
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        let credential = CliSshCredential::new(&MgmtConfig::default(), &None, &sources);
+        let cloned = credential.clone();
+
+        assert_eq!("secret", cloned.password().unwrap());
+        assert_eq!("secret", credential.password().unwrap());
+        assert_eq!("run\n", std::fs::read_to_string(&runs).unwrap());
+        std::fs::remove_file(&runs).unwrap();
+    }
+
+    #[test]
+    fn refuse_choosing_agent_key_without_prompt() {
+        let sources = CredentialArgs {
+            non_interactive: true,
+            ..Default::default()
+        };
+        let credential = CliSshCredential::new(&MgmtConfig::default(), &None, &sources);
+        let many_keys = vec![
+            SshPublicKeySuggestion::new("first"),
+            SshPublicKeySuggestion::new("second"),
+        ];
+
+        assert!(credential.auth_agent_resolve(many_keys).is_err());
+    }
+}
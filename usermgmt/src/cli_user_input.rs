@@ -1,4 +1,7 @@
-use usermgmt_lib::prelude::{anyhow, AppResult, Context};
+use usermgmt_lib::{
+    cli::CredentialSource,
+    prelude::{anyhow, bail, AppResult, Context},
+};
 
 use crate::user_input;
 
@@ -14,3 +17,30 @@ pub fn ask_cli_password() -> AppResult<String> {
         .ok_or_else(|| anyhow!("No password provided"))?;
     Ok(password)
 }
+
+/// Takes a credential from the given source or asks for it in the terminal without a source.
+///
+/// # Errors
+///
+/// - If fetching from the source fails. See [`CredentialSource::fetch`].
+/// - If the credential would be asked for but parameter `non_interactive` is true.
+/// - If asking in the terminal fails.
+pub fn from_source_or_prompt(
+    source: Option<&CredentialSource>,
+    non_interactive: bool,
+    credential: &str,
+    option: &str,
+    prompt: impl FnOnce() -> AppResult<String>,
+) -> AppResult<String> {
+    if let Some(fetched) = source.map(CredentialSource::fetch).transpose()?.flatten() {
+        return Ok(fetched);
+    }
+    if non_interactive {
+        bail!(
+            "{} is needed but asking for it is not allowed via --non-interactive. Provide it via {}",
+            credential,
+            option
+        );
+    }
+    prompt()
+}
@@ -1,31 +1,87 @@
-use once_cell::unsync::OnceCell;
-use usermgmt_lib::{ldap::LdapCredential, prelude::AppResult};
+use std::sync::Arc;
+
+use once_cell::sync::OnceCell;
+use usermgmt_lib::{cli::CredentialArgs, ldap::LdapCredential, prelude::AppResult};
 
 use crate::cli_user_input;
 
+/// Username and password are taken from the sources given via CLI
+/// or are retrieved by a terminal prompt.
+/// Clones share the retrieved username and password.
+/// This way every source is read and every prompt is shown only once.
 #[derive(Debug, Default, Clone)]
 pub struct LdapCliCredential {
-    username: OnceCell<String>,
-    password: OnceCell<String>,
+    username: Arc<OnceCell<String>>,
+    password: Arc<OnceCell<String>>,
+    sources: CredentialArgs,
+}
+
+impl LdapCliCredential {
+    pub fn new(sources: CredentialArgs) -> Self {
+        Self {
+            sources,
+            ..Default::default()
+        }
+    }
 }
 
 impl LdapCredential for LdapCliCredential {
     fn username(&self) -> AppResult<&str> {
         self.username
-            .get_or_try_init(cli_user_input::ask_cli_username)
+            .get_or_try_init(|| {
+                cli_user_input::from_source_or_prompt(
+                    self.sources.ldap_user_from.as_ref(),
+                    self.sources.non_interactive,
+                    "LDAP username",
+                    "--ldap-user-from",
+                    cli_user_input::ask_cli_username,
+                )
+            })
             .map(|string| string.as_str())
     }
 
     fn password(&self) -> AppResult<&str> {
         self.password
-            .get_or_try_init(cli_user_input::ask_cli_password)
+            .get_or_try_init(|| {
+                cli_user_input::from_source_or_prompt(
+                    self.sources.ldap_password_from.as_ref(),
+                    self.sources.non_interactive,
+                    "LDAP password",
+                    "--ldap-password-from",
+                    cli_user_input::ask_cli_password,
+                )
+            })
             .map(|string| string.as_str())
     }
 
     fn set_password(&mut self, new: String) {
-        self.password = OnceCell::new();
-        self.password
-            .set(new)
-            .expect("Once cell is cleared the line above");
+        self.password = Arc::new(OnceCell::with_value(new));
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn read_source_only_once_for_all_clones() {
+        let runs = std::env::temp_dir().join(format!("usermgmt_ldap_runs_{}", std::process::id()));
+        let _ = std::fs::remove_file(&runs);
+        let sources = CredentialArgs {
+            non_interactive: true,
+            ldap_user_from: Some(
+                format!("cmd:echo run >> {}; echo admin", runs.display())
+                    .parse()
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        let credential = LdapCliCredential::new(sources);
+        let cloned = credential.clone();
+
+        assert_eq!("admin", cloned.username().unwrap());
+        assert_eq!("admin", credential.username().unwrap());
+        assert_eq!("run\n", std::fs::read_to_string(&runs).unwrap());
+        std::fs::remove_file(&runs).unwrap();
     }
 }
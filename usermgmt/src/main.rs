@@ -1,14 +1,19 @@
 #![deny(clippy::unwrap_used)]
+#![cfg_attr(test, allow(clippy::unwrap_used))]
 #![forbid(unsafe_code)]
 
 use clap::Parser;
 use cli_ssh_credential::CliSshCredential;
 use ldap_cli_credential::LdapCliCredential;
-use log::error;
-use std::process::ExitCode;
-use usermgmt_lib::cli::{self, Commands, GeneralArgs, OnWhichSystem};
+use log::{error, info, warn};
+use std::{path::PathBuf, process::ExitCode};
+use usermgmt_lib::cli::{
+    self, AccountCommands, Commands, ConfigCommands, CoordinatorCommands, GeneralArgs,
+    OnWhichSystem, QosCommands,
+};
 use usermgmt_lib::config::{self};
-use usermgmt_lib::{operations, prelude::*, ChangesToUser, Entity};
+use usermgmt_lib::output::{self, OutputFormat, UserChange};
+use usermgmt_lib::{doctor, operations, prelude::*, ChangesToUser, Entity};
 
 mod cli_ssh_credential;
 mod cli_user_input;
@@ -41,72 +46,283 @@ fn execute_command() -> AppResult {
 /// # Errors
 ///
 /// - If the LDAP or SSH session could not be established because of connection problems or invalid
-/// credentials.
+///   credentials.
 /// - If some arguments in CLI, parameter `args`, for action are not valid.
 pub fn run_mgmt(args: cli::GeneralArgs) -> AppResult {
-    let ldap_credential = LdapCliCredential::default();
-    match args.command {
+    let credentials = args.credentials;
+    let config_path = args.config;
+    let profile = args.profile;
+    let ldap_credential = LdapCliCredential::new(credentials.clone());
+    let output = args.output;
+    let command = match args.command {
         Commands::GenerateConfig => {
+            output::ensure_table_output(output)?;
             // To StdOut, user can then pipe this default configuration wherever they please.
-            println!("{}", config::config_for_save())
+            println!("{}", config::config_for_save());
+            return Ok(());
+        }
+        Commands::Config { command } => {
+            return run_config_command(command, config_path, profile, output)
+        }
+        command => command,
+    };
+    let loaded = config::load_effective_config(config_path.clone(), profile.clone())?;
+    let config = &loaded.config;
+    match command {
+        Commands::GenerateConfig | Commands::Config { .. } => {
+            unreachable!("Commands without the effective configuration are handled above")
         }
         Commands::Add {
             to_add,
             on_which_sys,
         } => {
-            let config = config::load_config(None)?.config;
-            let on_which_sys = &OnWhichSystem::from_config_for_all(&config, &on_which_sys);
-            let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
-            operations::add_user(
+            let on_which_sys = &OnWhichSystem::from_config_for_all(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
+            let added = operations::add_user(
                 to_add,
                 on_which_sys,
-                &config,
+                config,
                 ldap_credential,
                 cli_ssh_credential,
-            )?
+            )?;
+            print_user_change(added, output)?
         }
         Commands::Modify { data, on_which_sys } => {
-            let config = config::load_config(None)?.config;
-            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
-            let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
-            let data = Entity::new_modifieble_conf(data, &config)?;
+            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
+            let data = Entity::new_modifieble_conf(data, config)?;
             let data = ChangesToUser::try_new(data)?;
-            operations::modify_user(
+            let modified = operations::modify_user(
                 data,
                 on_which_sys,
-                &config,
+                config,
                 ldap_credential,
                 cli_ssh_credential,
-            )?
+            )?;
+            print_user_change(modified, output)?
         }
-        Commands::Delete { user, on_which_sys } => {
-            let config = config::load_config(None)?.config;
-            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
-            let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
-            operations::delete_user(
+        Commands::Delete {
+            user,
+            on_which_sys,
+            clusters,
+        } => {
+            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
+            let clusters: Vec<String> = clusters.into_iter().map(Into::into).collect();
+            let deleted = operations::delete_user(
                 user.as_ref(),
+                &clusters,
                 on_which_sys,
-                &config,
+                config,
                 ldap_credential,
                 cli_ssh_credential,
             )?;
+            print_user_change(deleted, output)?
         }
         Commands::List {
             on_which_sys,
             simple_output_for_ldap,
+            query,
         } => {
-            let config = config::load_config(None)?.config;
-            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(&config, &on_which_sys);
-            let cli_ssh_credential = CliSshCredential::new(&config, on_which_sys.ssh_path());
+            let on_which_sys = &OnWhichSystem::from_config_for_slurm_ldap(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
             operations::print_list_of_users_to_stdout(
-                &config,
+                config,
                 on_which_sys,
                 simple_output_for_ldap.unwrap_or(false),
+                &query,
+                output,
+                ldap_credential,
+                cli_ssh_credential,
+            )?
+        }
+        Commands::Show { user, on_which_sys } => {
+            let on_which_sys = &OnWhichSystem::from_config_for_all(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
+            operations::print_user_details_to_stdout(
+                user.as_ref(),
+                on_which_sys,
+                output,
+                config,
+                ldap_credential,
+                cli_ssh_credential,
+            )?
+        }
+        Commands::Doctor { on_which_sys } => {
+            let on_which_sys = &OnWhichSystem::from_config_for_all(config, &on_which_sys);
+            let cli_ssh_credential =
+                CliSshCredential::new(config, on_which_sys.ssh_path(), &credentials);
+            let results = operations::check_connections(
+                config,
+                on_which_sys,
+                ldap_credential,
+                cli_ssh_credential,
+            );
+            println!("{}", output::render(&results, output.unwrap_or_default())?);
+            if !doctor::all_passed(&results) {
+                let failed = results.iter().filter(|result| !result.passed).count();
+                bail!("{} of {} checks failed", failed, results.len());
+            }
+        }
+        Commands::Inactive(args) => {
+            let cli_ssh_credential = CliSshCredential::new(config, &args.ssh_path, &credentials);
+            operations::print_inactive_users_to_stdout(
+                &args,
+                output.unwrap_or_default(),
+                config,
+                ldap_credential,
+                cli_ssh_credential,
+            )?
+        }
+        Commands::Usage(args) => {
+            let cli_ssh_credential = CliSshCredential::new(config, &args.ssh_path, &credentials);
+            operations::print_usage_to_stdout(
+                &args,
+                output.unwrap_or_default(),
+                config,
                 ldap_credential,
                 cli_ssh_credential,
             )?
         }
+        Commands::Account { command, ssh_path } => {
+            output::ensure_table_output(output)?;
+            let cli_ssh_credential = CliSshCredential::new(config, &ssh_path, &credentials);
+            match command {
+                AccountCommands::Create(account) => {
+                    operations::add_account(account, config, cli_ssh_credential)?
+                }
+                AccountCommands::Modify(account) => {
+                    operations::modify_account(account, config, cli_ssh_credential)?
+                }
+                AccountCommands::Delete { name } => {
+                    operations::delete_account(name.as_ref(), config, cli_ssh_credential)?
+                }
+                AccountCommands::List => {
+                    operations::print_list_of_accounts_to_stdout(config, cli_ssh_credential)?
+                }
+                AccountCommands::Coordinator(command) => match command {
+                    CoordinatorCommands::Add(coordinators) => {
+                        operations::add_coordinators(coordinators, config, cli_ssh_credential)?
+                    }
+                    CoordinatorCommands::Remove(coordinators) => {
+                        operations::remove_coordinators(coordinators, config, cli_ssh_credential)?
+                    }
+                    CoordinatorCommands::List => operations::print_list_of_coordinators_to_stdout(
+                        config,
+                        cli_ssh_credential,
+                    )?,
+                },
+            }
+        }
+        Commands::Qos { command, ssh_path } => {
+            output::ensure_table_output(output)?;
+            let cli_ssh_credential = CliSshCredential::new(config, &ssh_path, &credentials);
+            match command {
+                QosCommands::Create {
+                    qos,
+                    add_to_valid_qos,
+                } => {
+                    let name = qos.name.to_string();
+                    operations::add_qos(qos, config, cli_ssh_credential)?;
+                    if add_to_valid_qos {
+                        match config::add_valid_qos_to_file(config_path, profile, &name)? {
+                            Some(path) => info!(
+                                "Added QOS {} to valid_qos in configuration at {:?}",
+                                name, path
+                            ),
+                            None => info!("QOS {} is already in valid_qos", name),
+                        }
+                    }
+                }
+                QosCommands::Modify(qos) => {
+                    operations::modify_qos(qos, config, cli_ssh_credential)?
+                }
+                QosCommands::Delete { name } => {
+                    operations::delete_qos(name.as_ref(), config, cli_ssh_credential)?
+                }
+                QosCommands::List => {
+                    operations::print_list_of_qos_to_stdout(config, cli_ssh_credential)?
+                }
+            }
+        }
     };
 
     Ok(())
 }
+
+/// Runs the subcommands for the configuration file itself.
+/// They need the raw file or the sources of the fields instead of the effective configuration.
+fn run_config_command(
+    command: ConfigCommands,
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    output: Option<OutputFormat>,
+) -> AppResult {
+    match command {
+        ConfigCommands::Show => {
+            let (loaded, entries) = config::load_config_with_sources(config_path, profile)?;
+            info!("Effective configuration of the folder {:?}", loaded.path);
+            println!("{}", output::render(&entries, output.unwrap_or_default())?);
+        }
+        ConfigCommands::Validate => {
+            let loaded = config::load_effective_config(config_path, profile)?;
+            let problems = loaded.config.validate();
+            if problems.is_empty() && output.is_none() {
+                info!("Configuration in the folder {:?} is valid", loaded.path);
+            } else {
+                println!("{}", output::render(&problems, output.unwrap_or_default())?);
+            }
+            if !problems.is_empty() {
+                bail!(
+                    "Configuration in the folder {:?} has {} problem(s)",
+                    loaded.path,
+                    problems.len()
+                );
+            }
+        }
+        ConfigCommands::Upgrade { dry_run } => {
+            output::ensure_table_output(output)?;
+            let (file, migrated) = config::upgrade_config_file(config_path, dry_run)?;
+            for unknown in migrated.unknown_keys.iter() {
+                warn!("{}", unknown);
+            }
+            if !migrated.is_outdated() {
+                info!(
+                    "Configuration file at {:?} has the current version {} already",
+                    file,
+                    config::CONFIG_VERSION
+                );
+                return Ok(());
+            }
+            for change in migrated.changes.iter() {
+                info!("Changed {}", change);
+            }
+            if dry_run {
+                print!("{}", migrated.content);
+            } else {
+                info!(
+                    "Upgraded configuration file at {:?} from version {} to {}. \
+                    The original file is kept at {:?}",
+                    file,
+                    migrated.from_version,
+                    config::CONFIG_VERSION,
+                    config::backup_path(&file)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the added, modified or deleted user if an output format is chosen.
+fn print_user_change(change: UserChange, output: Option<OutputFormat>) -> AppResult {
+    if let Some(format) = output {
+        println!("{}", output::render(&[change], format)?);
+    }
+    Ok(())
+}
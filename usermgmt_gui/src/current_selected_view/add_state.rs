@@ -47,14 +47,15 @@ impl AddState {
             firstname,
             lastname,
             CommonUserFields {
-                username,
                 group: some_if_not_blank_str(&self.group),
                 mail: some_if_not_blank_str(&self.mail),
                 default_qos: some_if_not_blank_str(&self.default_qos),
                 publickey: some_if_not_blank_str(&self.publickey),
                 qos,
+                limits: Default::default(),
             },
-        );
+        )
+        .with_username(username);
 
         return Ok(user);
         fn some_if_not_blank_str(input: &str) -> Option<TrimmedNonEmptyText> {
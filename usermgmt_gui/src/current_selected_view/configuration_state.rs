@@ -1,12 +1,86 @@
 use std::path::PathBuf;
 
-use usermgmt_lib::config::LoadedMgmtConfig;
+use usermgmt_lib::{
+    config::{ConfigProblem, LoadedMgmtConfig, MgmtConfig},
+    doctor::CheckResult,
+};
 
-use crate::{drawing::configuration::CacheForConfFields, io_resource_manager::IoResourceManager};
+use crate::{
+    drawing::configuration::CacheForConfFields,
+    io_resource_manager::{IoResourceManager, IoTaskStatus},
+    prelude::AppResult,
+};
 
 #[derive(Debug, Default)]
 pub struct ConfigurationState {
     pub gui_field_cache: CacheForConfFields,
+    /// Configuration which is shown and used for all actions.
+    /// It is the effective configuration of the selected profile if a profile is selected.
     pub io_conf: IoResourceManager<LoadedMgmtConfig>,
     pub io_save_conf: IoResourceManager<PathBuf>,
+    /// Results of testing the connections with the shown configuration.
+    pub io_check_connections: IoResourceManager<Vec<CheckResult>>,
+    selected_profile: Option<String>,
+    /// Shared configuration with all profiles while a profile is selected.
+    shared_conf: Option<MgmtConfig>,
+    /// Error of the last selection of a profile.
+    pub profile_error: Option<String>,
+    /// Problems of the shown configuration found after loading it or selecting a profile.
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl ConfigurationState {
+    pub fn selected_profile(&self) -> Option<&str> {
+        self.selected_profile.as_deref()
+    }
+
+    /// Shows the effective configuration of the profile `profile`
+    /// or the shared configuration if `profile` is `None`.
+    /// Edits of the formerly shown configuration are kept.
+    pub fn select_profile(&mut self, profile: Option<String>) -> AppResult {
+        let IoTaskStatus::Successful(loaded) = self.io_conf.status() else {
+            return Ok(());
+        };
+        let shared = self.shared_with_edits(&loaded.config)?;
+        let shown = match profile.as_deref() {
+            Some(name) => shared.with_profile(name)?,
+            None => shared.clone(),
+        };
+        if let IoTaskStatus::Successful(loaded) = self.io_conf.status_mut() {
+            loaded.config = shown;
+        }
+        self.shared_conf = profile.is_some().then_some(shared);
+        self.selected_profile = profile;
+        self.validate();
+        Ok(())
+    }
+
+    /// Checks the shown configuration for inconsistencies. See [`MgmtConfig::validate`].
+    pub fn validate(&mut self) {
+        self.problems = match self.io_conf.status() {
+            IoTaskStatus::Successful(loaded) => loaded.config.validate(),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Shared configuration with all profiles as it is saved to file.
+    /// Edits of the shown configuration `shown` are stored in the selected profile if any.
+    pub fn shared_with_edits(&self, shown: &MgmtConfig) -> AppResult<MgmtConfig> {
+        match (self.selected_profile.as_deref(), &self.shared_conf) {
+            (Some(name), Some(shared)) => {
+                let mut shared = shared.clone();
+                shared.store_as_profile(name, shown)?;
+                Ok(shared)
+            }
+            _ => Ok(shown.clone()),
+        }
+    }
+
+    /// Forgets the selected profile, e.g. if another configuration is loaded.
+    pub fn reset_profile(&mut self) {
+        self.selected_profile = None;
+        self.shared_conf = None;
+        self.profile_error = None;
+        self.problems.clear();
+    }
 }
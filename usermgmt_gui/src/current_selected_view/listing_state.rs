@@ -1,4 +1,4 @@
-use usermgmt_lib::{ldap::LdapSearchResult, slurm::ListedUser};
+use usermgmt_lib::{ldap::LdapSearchResult, slurm::SlurmAssociation};
 
 use crate::io_resource_manager::IoResourceManager;
 
@@ -7,5 +7,5 @@ pub struct ListingState {
     pub rw_user_name: Option<String>,
     pub rw_pw: Option<String>,
     pub list_ldap_res: IoResourceManager<LdapSearchResult>,
-    pub list_slurm_user_res: IoResourceManager<ListedUser>,
+    pub list_slurm_user_res: IoResourceManager<Vec<SlurmAssociation>>,
 }
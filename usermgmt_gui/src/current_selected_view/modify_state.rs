@@ -24,15 +24,16 @@ impl ModifyState {
             general_utils::some_if_not_blank_str(&self.firstname),
             general_utils::some_if_not_blank_str(&self.lastname),
         );
+        let username = TrimmedNonEmptyText::try_from(self.username.clone())?;
         let common_fields = CommonUserFields {
-            username: TrimmedNonEmptyText::try_from(self.username.clone())?,
             group: general_utils::some_if_not_blank_str(&self.group),
             mail: general_utils::some_if_not_blank_str(&self.mail),
             default_qos: general_utils::some_if_not_blank_str(&self.default_qos),
             publickey: general_utils::some_if_not_blank_str(&self.publickey),
             qos: self.qos.clone(),
+            limits: Default::default(),
         };
-        let entity = Entity::new(firstname, lastname, common_fields, config)?;
+        let entity = Entity::new(username, firstname, lastname, common_fields, config)?;
         let changes = ChangesToUser::try_new(entity)?;
         Ok(changes)
     }
@@ -6,7 +6,12 @@ type LabelTyp = Rc<str>;
 use super::draw_utils::{GroupDrawing, TextFieldEntry};
 use crate::{current_selected_view::ConfigurationState, prelude::*};
 use std::{cell::RefCell, collections::HashMap, path::PathBuf, rc::Rc};
-use usermgmt_lib::config::{LoadedMgmtConfig, MgmtConfig};
+use usermgmt_lib::{
+    config::{LoadedMgmtConfig, MgmtConfig},
+    doctor::CheckResult,
+    operations,
+    output::TableRow,
+};
 
 pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
     let mut can_reload = true;
@@ -47,10 +52,13 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
         ),
     );
 
+    draw_problems(window, ui);
     draw_utils::draw_file_path(ui, window);
     ui.separator();
     draw_buttons(window, ui, can_reload);
     ui.separator();
+    draw_profile_selection(window, ui);
+    draw_connection_checks(window, ui);
     ui.add_space(20.0);
     draw_fields(&mut window.conf_state, &window.settings, ui);
 }
@@ -83,14 +91,164 @@ fn draw_buttons(window: &mut UsermgmtWindow, ui: &mut egui::Ui, can_reload: bool
                 path: Default::default(),
                 config: default,
             };
+            window.conf_state.reset_profile();
             window.conf_state.io_conf.set_success(loaded_conf);
         }
     });
 }
 
+fn draw_problems(window: &UsermgmtWindow, ui: &mut egui::Ui) {
+    let problems = &window.conf_state.problems;
+    if problems.is_empty() {
+        return;
+    }
+    let settings = &window.settings;
+    draw_utils::draw_box_group(
+        ui,
+        settings,
+        &GroupDrawing::new(settings.texts().conf_problems_group()),
+        |ui| {
+            for problem in problems {
+                ui.colored_label(settings.colors().err_msg(), problem.to_string());
+            }
+        },
+    );
+}
+
+fn draw_connection_checks(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
+    if !window.conf_state.io_conf.is_there() {
+        return;
+    }
+    let title = window.settings.texts().conf_check_connections().clone();
+    ui.collapsing(&*title, |ui| {
+        draw_utils::draw_credentials(ui, window, true);
+        let is_checking = window.conf_state.io_check_connections.is_loading();
+        if ui
+            .add_enabled(
+                !is_checking,
+                egui::Button::new(window.settings.texts().btn_check_connections()),
+            )
+            .clicked()
+        {
+            check_connections(window);
+        }
+
+        let settings = &window.settings;
+        let texts = settings.texts();
+        let status = window.conf_state.io_check_connections.status();
+        draw_utils::draw_status_msg(
+            ui,
+            settings,
+            status,
+            (
+                || texts.conf_check_init_msg().to_owned(),
+                || texts.conf_check_loading_msg().to_owned(),
+                |results: &Vec<CheckResult>| {
+                    let failed = results.iter().filter(|result| !result.passed).count();
+                    format!(
+                        "{}: {} of {} failed",
+                        texts.conf_check_success_msg(),
+                        failed,
+                        results.len()
+                    )
+                },
+                || texts.conf_check_err_msg().to_owned(),
+            ),
+        );
+        if let IoTaskStatus::Successful(results) = status {
+            egui::Grid::new("connection_checks")
+                .striped(true)
+                .show(ui, |ui| {
+                    for header in CheckResult::headers() {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for result in results {
+                        let color = if result.passed {
+                            settings.colors().success_msg()
+                        } else {
+                            settings.colors().err_msg()
+                        };
+                        for cell in result.cells() {
+                            ui.colored_label(color, cell);
+                        }
+                        ui.end_row();
+                    }
+                });
+        }
+    });
+    ui.separator();
+}
+
+fn check_connections(window: &mut UsermgmtWindow) {
+    if let Ok(prep) =
+        general_utils::prep_conf_creds(window, |app| &mut app.conf_state.io_check_connections, true)
+    {
+        let _ = window.conf_state.io_check_connections.spawn_task(
+            move || {
+                Ok(operations::check_connections(
+                    &prep.config,
+                    &prep.on_which_sys,
+                    prep.ldap_cred,
+                    prep.ssh_cred,
+                ))
+            },
+            String::from("Testing connections"),
+        );
+    }
+}
+
+fn draw_profile_selection(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
+    let conf_state = &mut window.conf_state;
+    let profiles: Vec<String> = match conf_state.io_conf.status() {
+        IoTaskStatus::Successful(loaded) => conf_state
+            .shared_with_edits(&loaded.config)
+            .map(|shared| {
+                shared
+                    .profile_names()
+                    .into_iter()
+                    .map(ToOwned::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => return,
+    };
+    if profiles.is_empty() {
+        return;
+    }
+
+    let texts = window.settings.texts();
+    let mut selected = conf_state.selected_profile().map(ToOwned::to_owned);
+    let before = selected.clone();
+    egui::ComboBox::from_label(texts.conf_profile())
+        .selected_text(selected.as_deref().unwrap_or(texts.conf_no_profile()))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut selected, None, texts.conf_no_profile());
+            for profile in profiles {
+                ui.selectable_value(&mut selected, Some(profile.clone()), profile);
+            }
+        });
+    if selected != before {
+        conf_state.profile_error = conf_state
+            .select_profile(selected)
+            .err()
+            .map(|error| general_utils::error_status(texts.conf_profile_err_msg(), error));
+    }
+    if let Some(error) = &conf_state.profile_error {
+        ui.colored_label(window.settings.colors().err_msg(), error);
+    }
+    ui.separator();
+}
+
 fn save_config(config_state: &mut ConfigurationState, conf_path: PathBuf) {
     if let IoTaskStatus::Successful(loaded) = config_state.io_conf.status() {
-        let config = loaded.config.clone();
+        let config = match config_state.shared_with_edits(&loaded.config) {
+            Ok(shared) => shared,
+            Err(error) => {
+                config_state.io_save_conf.set_error(error);
+                return;
+            }
+        };
         config_state.io_save_conf.spawn_task(
             move || config.save(&conf_path),
             String::from("Saving configuration"),
@@ -225,7 +383,7 @@ fn snake_to_label(input: &'static str, repos: CacheForConfFields) -> Rc<str> {
     }))
 }
 
-fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<ConfiField> {
+fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<ConfiField<'_>> {
     macro_rules! create_conf_field {
         ($field:ident, $too_tip:expr) => {
             (
@@ -255,6 +413,19 @@ fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<Con
         create_conf_field!(valid_qos),
         create_conf_field!(student_qos),
         create_conf_field!(staff_qos),
+        create_conf_field!(validate_qos_against_slurm),
+        create_conf_field!(use_slurmrestd),
+        create_conf_field!(slurmrestd_url),
+        create_conf_field!(slurmrestd_api_version),
+        create_conf_field!(slurmrestd_user),
+        create_conf_field!(slurmrestd_token),
+        create_conf_field!(clusters),
+        create_conf_field!(student_clusters),
+        create_conf_field!(staff_clusters),
+        create_conf_field!(sacct_path),
+        create_conf_field!(sreport_path),
+        create_conf_field!(ldap_search_filter),
+        create_conf_field!(ldap_page_size),
         create_conf_field!(valid_slurm_groups),
         create_conf_field!(compute_nodes),
         create_conf_field!(ldap_domain_components),
@@ -289,6 +460,11 @@ fn construct_fields(config: &mut MgmtConfig, map: CacheForConfFields) -> Vec<Con
         create_conf_field!(faculty_gid),
         create_conf_field!(sacctmgr_path),
         create_conf_field!(ssh_key_path),
+        create_conf_field!(username_regex),
+        create_conf_field!(username_reserved),
+        create_conf_field!(username_reserve_system_accounts),
+        create_conf_field!(username_lowercase_only),
+        create_conf_field!(username_template),
     ];
     fields.sort();
     fields
@@ -363,7 +539,7 @@ impl Ord for ConfiField<'_> {
 }
 impl PartialOrd for ConfiField<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.label().cmp(other.label()))
+        Some(self.cmp(other))
     }
 }
 
@@ -97,17 +97,16 @@ pub fn draw(ui: &mut egui::Ui, window: &mut UsermgmtWindow) {
         {
             let adding_state = &mut window.adding_state;
             let to_add = adding_state.create_user_to_add()?;
-            let username = to_add.common_user_fields().username.to_string();
             let _ = adding_state.adding_res_io.spawn_task(
                 move || {
-                    operations::add_user(
+                    let added = operations::add_user(
                         to_add,
                         &prep.on_which_sys,
                         &prep.config,
                         prep.ldap_cred,
                         prep.ssh_cred,
                     )?;
-                    Ok(username)
+                    Ok(added.username)
                 },
                 String::from("Adding user"),
             );
@@ -49,14 +49,15 @@ fn delete_user(window: &mut UsermgmtWindow) {
         let username = window.remove_state.username.clone();
         let _ = window.remove_state.remove_res_io.spawn_task(
             move || {
-                operations::delete_user(
+                let deleted = operations::delete_user(
                     &username,
+                    &[],
                     &prep.on_which_sys,
                     &prep.config,
                     prep.ldap_cred,
                     prep.ssh_cred,
                 )?;
-                Ok(username)
+                Ok(deleted.username)
             },
             String::from("Deleting user"),
         );
@@ -5,7 +5,7 @@ use drawing::draw_utils::GroupDrawing;
 use egui_extras::{Size, StripBuilder};
 use usermgmt_lib::{
     ldap::{list_ldap_users, LDAPConfig, LdapSearchResult, LdapSimpleCredential},
-    slurm::{self, ListedUser},
+    slurm::{self, SlurmAssociation},
     ssh::{SshConnection, SshGivenCredential},
 };
 
@@ -54,18 +54,15 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
             (
                 || texts.listing_slurm_init().to_string(),
                 || texts.listing_slurm_loading().to_string(),
-                |_: &ListedUser| texts.listing_slurm_success().to_string(),
+                |_: &Vec<SlurmAssociation>| texts.listing_slurm_success().to_string(),
                 || texts.listing_slurm_failure().to_string(),
             ),
         );
 
-        listing_state
-            .list_slurm_user_res
-            .success()
-            .inspect(|slurm_users| {
-                ui.separator();
-                draw_slurm_table(ui, setting, slurm_users)
-            });
+        if let Some(slurm_users) = listing_state.list_slurm_user_res.success() {
+            ui.separator();
+            draw_slurm_table(ui, setting, slurm_users)
+        }
     }
 
     fn draw_listed_ldap_users(
@@ -93,19 +90,18 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
         }
     }
 
-    fn draw_slurm_table(ui: &mut egui::Ui, settings: &Settings, slurm_users: &ListedUser) {
+    fn draw_slurm_table(ui: &mut egui::Ui, settings: &Settings, slurm_users: &[SlurmAssociation]) {
         use egui_extras::{Column, TableBuilder};
         draw_table(ui, settings, slurm_users);
 
-        fn draw_table(ui: &mut egui::Ui, settings: &Settings, raw: &ListedUser) {
+        fn draw_table(ui: &mut egui::Ui, settings: &Settings, associations: &[SlurmAssociation]) {
             let mut table = TableBuilder::new(ui)
                 .striped(true)
                 .resizable(true)
                 .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
                 .min_scrolled_height(0.);
 
-            let headers = raw.headers();
-            let rows = raw.fields();
+            let headers = SlurmAssociation::HEADERS;
             table = table
                 .columns(Column::auto(), headers.len().saturating_sub(1))
                 .column(Column::remainder());
@@ -118,9 +114,9 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
                     }
                 })
                 .body(|mut body| {
-                    for single_row in rows {
+                    for association in associations {
                         body.row(10., |mut row| {
-                            for column in single_row {
+                            for column in association.to_row() {
                                 row.col(|ui| _ = ui.label(column));
                             }
                         });
@@ -171,12 +167,13 @@ pub fn draw(window: &mut UsermgmtWindow, ui: &mut egui::Ui) {
                 ),
             );
             let mgmt_conf = mgmt_conf.config.clone();
-            let failed_parsing_slurm = text.failed_parsing_slurm().clone();
             _ = window.listin_state.list_slurm_user_res.spawn_task(
                 move || {
                     let connection = SshConnection::from_head_node(&mgmt_conf, ssh_credentials);
-                    let slurm_users_raw = slurm::list_users(&mgmt_conf, &connection, true)?;
-                    ListedUser::new(&slurm_users_raw).ok_or(anyhow!(failed_parsing_slurm))
+                    slurm::list_associations(
+                        &mgmt_conf,
+                        &slurm::executor_from_config(&mgmt_conf, &connection),
+                    )
                 },
                 String::from("Getting slurm user"),
             );
@@ -41,15 +41,14 @@ fn handle_modify_req(window: &mut UsermgmtWindow) {
             Ok(changes) => {
                 window.modify_state.res_io.spawn_task(
                     move || {
-                        let username = changes.username.to_string();
-                        operations::modify_user(
+                        let modified = operations::modify_user(
                             changes,
                             &on_which_sys,
                             &config,
                             ldap_cred,
                             ssh_cred,
                         )?;
-                        Ok(username)
+                        Ok(modified.username)
                     },
                     String::from("Modifying User"),
                 );
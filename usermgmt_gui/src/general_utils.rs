@@ -31,6 +31,7 @@ pub fn is_some_and_not_empty(input: Option<&str>) -> bool {
         .unwrap_or(false)
 }
 pub fn start_load_config(conf_state: &mut ConfigurationState, path: Option<PathBuf>) {
+    conf_state.reset_profile();
     conf_state.io_conf.spawn_task(
         || config::load_config(path),
         "Loading configuration".to_string(),
@@ -1,7 +1,8 @@
 use usermgmt_lib::prelude::AppError;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum IoTaskStatus<T> {
+    #[default]
     NotStarted,
     Loading,
     Successful(T),
@@ -16,9 +17,3 @@ impl<T> IoTaskStatus<T> {
         matches!(self, IoTaskStatus::Successful(_))
     }
 }
-
-impl<T> Default for IoTaskStatus<T> {
-    fn default() -> Self {
-        Self::NotStarted
-    }
-}
@@ -3,7 +3,9 @@ use crate::prelude::*;
 /// Checks every frame if some background IO Task as a Os thread is finished and applies its result
 /// to the global state of this application.
 pub fn query(window: &mut UsermgmtWindow) {
+    let mut conf_loaded = false;
     if let Some(conf) = window.conf_state.io_conf.query_task() {
+        conf_loaded = true;
         let listing_state = &mut window.listin_state;
         let ssh_state = &mut window.ssh_state;
         let path = &mut window.conf_path;
@@ -24,6 +26,9 @@ pub fn query(window: &mut UsermgmtWindow) {
             ssh_state.username = Some(config.default_ssh_user.to_owned());
         }
     }
+    if conf_loaded {
+        window.conf_state.validate();
+    }
     if let Some(path) = window.conf_state.io_save_conf.query_task() {
         window.conf_path = path.to_path_buf();
     }
@@ -32,6 +37,7 @@ pub fn query(window: &mut UsermgmtWindow) {
     let _ = window.adding_state.adding_res_io.query_task();
     let _ = window.remove_state.remove_res_io.query_task();
     let _ = window.modify_state.res_io.query_task();
+    let _ = window.conf_state.io_check_connections.query_task();
 
     #[cfg(debug_assertions)]
     {
@@ -63,6 +63,15 @@ pub struct Texts {
     conf_save_loading_msg: ReadonlyText,
     conf_load_group: ReadonlyText,
     conf_save_group: ReadonlyText,
+    conf_profile: ReadonlyText,
+    conf_no_profile: ReadonlyText,
+    conf_profile_err_msg: ReadonlyText,
+    conf_problems_group: ReadonlyText,
+    conf_check_connections: ReadonlyText,
+    conf_check_init_msg: ReadonlyText,
+    conf_check_loading_msg: ReadonlyText,
+    conf_check_success_msg: ReadonlyText,
+    conf_check_err_msg: ReadonlyText,
     ssh_cred: ReadonlyText,
     ldap_cred: ReadonlyText,
     dir_conf_path: ReadonlyText,
@@ -84,6 +93,7 @@ pub struct Texts {
     btn_action_conf_load: ReadonlyText,
     btn_action_conf_save: ReadonlyText,
     btn_action_conf_default: ReadonlyText,
+    btn_check_connections: ReadonlyText,
     btn_action_add: ReadonlyText,
     btn_action_remove: ReadonlyText,
     btn_action_modify: ReadonlyText,
@@ -108,7 +118,6 @@ pub struct Texts {
     mode_ldap: ReadonlyText,
     mode_slurm: ReadonlyText,
     mode_directory: ReadonlyText,
-    failed_parsing_slurm: ReadonlyText,
     ldap_cred_missing: ReadonlyText,
     ssh_cred_missing: ReadonlyText,
 }
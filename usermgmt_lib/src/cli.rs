@@ -1,13 +1,21 @@
+pub use credential_source::{CredentialArgs, CredentialSource};
 pub use on_which_system::{OnSlurmLdapOnlyCli, OnWhichSystem, OnWhichSystemCli, OptFilePath};
 
+mod credential_source;
 mod on_which_system;
 
+use chrono::NaiveDate;
 use clap::{Args, Parser, Subcommand};
 use const_format::concatcp;
 use derive_more::Into;
 
+use std::{num::NonZeroUsize, path::PathBuf};
+
+use crate::output::{ListColumn, OutputFormat};
 use crate::prelude::*;
+use crate::slurm::{AccountAttributes, AssociationLimits, QosAttributes};
 use crate::util::TrimmedNonEmptyText;
+use crate::Group;
 
 pub const fn short_about() -> &'static str {
     "Simultaneous user management for Slurm and LDAP"
@@ -35,6 +43,21 @@ pub struct GeneralArgs {
     /// Operation to conduct on the user. Either add, delete or modify.
     #[clap(subcommand)]
     pub command: Commands,
+    /// Prints the result in this format, e.g. json for automation.
+    /// Listed users of LDAP and Slurm are merged per user with this option.
+    /// Added, modified or deleted users are only printed with this option.
+    #[clap(long, global = true, value_enum, verbatim_doc_comment)]
+    pub output: Option<OutputFormat>,
+    /// Path to the configuration file or its folder.
+    /// Has priority over the environment variable USERMGMT_CONFIG and the usual locations.
+    #[clap(long, global = true, verbatim_doc_comment)]
+    pub config: Option<PathBuf>,
+    /// Name of the profile in the configuration file whose fields override the shared fields.
+    /// Has priority over the environment variable USERMGMT_PROFILE.
+    #[clap(long, global = true, verbatim_doc_comment)]
+    pub profile: Option<String>,
+    #[command(flatten)]
+    pub credentials: CredentialArgs,
 }
 
 #[derive(Subcommand, Debug)]
@@ -64,6 +87,11 @@ pub enum Commands {
         user: TrimmedNonEmptyText,
         #[command(flatten)]
         on_which_sys: OnSlurmLdapOnlyCli,
+        /// Slurm clusters the user is removed from. Without this option the user is removed from all clusters.
+        /// The user stays in LDAP if clusters are given.
+        /// Clusters need to be provided as a whitespace separated list (e.g. alpha beta).
+        #[clap(long = "cluster", value_parser = trimmed_non_empty, num_args(1..=20), verbatim_doc_comment)]
+        clusters: Vec<TrimmedNonEmptyText>,
     },
     /// List users in Slurm and/or LDAP
     #[clap(visible_alias = "l")]
@@ -74,16 +102,328 @@ pub enum Commands {
         /// Is meant to be used for automation
         #[clap(long, verbatim_doc_comment)]
         simple_output_for_ldap: Option<bool>,
+        #[command(flatten)]
+        query: ListArgs,
+    },
+    /// Show a single user in LDAP, Slurm and optionally its directories with inconsistencies between them
+    Show {
+        /// A valid username e.g. wagnerdo.
+        #[clap(value_parser = trimmed_non_empty)]
+        user: TrimmedNonEmptyText,
+        #[command(flatten)]
+        on_which_sys: OnWhichSystemCli,
+    },
+    /// List users in LDAP without a job submission in Slurm for a number of days
+    Inactive(InactiveUsersArgs),
+    /// Show CPU and GPU hours per user or group in Slurm within a time window
+    Usage(UsageArgs),
+    /// Create, modify, delete or list accounts in Slurm
+    #[clap(visible_alias = "acc")]
+    Account {
+        #[clap(subcommand)]
+        command: AccountCommands,
+        /// Path where to find key pair to be used for ssh connection.
+        /// Has priority over the path from the configuration file.
+        #[arg(long, global = true, verbatim_doc_comment)]
+        ssh_path: Option<PathBuf>,
+    },
+    /// Create, modify, delete or list QOS in Slurm
+    Qos {
+        #[clap(subcommand)]
+        command: QosCommands,
+        /// Path where to find key pair to be used for ssh connection.
+        /// Has priority over the path from the configuration file.
+        #[arg(long, global = true, verbatim_doc_comment)]
+        ssh_path: Option<PathBuf>,
     },
     #[clap(visible_alias = "gc")]
     /// Outputs a default configuration, aka conf.toml, to stdout.
     /// Pipe it to a path for a file to generate a permanent configuration somewhere.
     GenerateConfig,
+    /// Check the connections to LDAP, Slurm and the hosts of the cluster with timings,
+    /// e.g. after changing the configuration or the credentials.
+    /// The exit code is non-zero if any check fails.
+    #[clap(verbatim_doc_comment)]
+    Doctor {
+        #[command(flatten)]
+        on_which_sys: OnWhichSystemCli,
+    },
+    /// Inspect the configuration
+    Config {
+        #[clap(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+/// CLI sub commands for the configuration file
+pub enum ConfigCommands {
+    /// Show the effective configuration with the source of every field:
+    /// default, configuration file, profile or environment variable like USERMGMT_LDAP_SERVER.
+    #[clap(verbatim_doc_comment)]
+    Show,
+    /// Check the effective configuration for inconsistencies, e.g. a default QOS
+    /// which is not among the QOS or soft limits of quotas above their hard limits.
+    /// All problems are listed and the exit code is non-zero if there is any.
+    #[clap(verbatim_doc_comment)]
+    Validate,
+    /// Upgrade the configuration file written for an older version of this application.
+    /// Comments and formatting are kept and the original file is kept with the extension .bak.
+    /// The applied changes and unknown keys, e.g. misspelled fields, are reported.
+    #[clap(verbatim_doc_comment)]
+    Upgrade {
+        /// Print the upgraded configuration file instead of writing it.
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+/// CLI sub commands for operations on accounts in the Slurm database
+pub enum AccountCommands {
+    /// Create an account in Slurm
+    Create(AccountFields),
+    /// Change the parent, description or organization of an account in Slurm
+    Modify(AccountFields),
+    /// Delete an account in Slurm
+    Delete {
+        /// Name of the account e.g. project_x.
+        #[clap(value_parser = trimmed_non_empty)]
+        name: TrimmedNonEmptyText,
+    },
+    /// List all accounts in Slurm
+    List,
+    /// Add, remove or list coordinators of accounts in Slurm
+    #[clap(subcommand)]
+    Coordinator(CoordinatorCommands),
+}
+
+/// Options for the report of inactive users
+#[derive(Args, Debug, Clone)]
+pub struct InactiveUsersArgs {
+    /// Users without a job submission within this number of days are listed.
+    #[clap(long, default_value_t = 180)]
+    pub days: u32,
+    /// Number of days in the past in which sacct searches for job submissions.
+    /// Users whose last job is older are listed without a last submission.
+    #[clap(long, default_value_t = 365, verbatim_doc_comment)]
+    pub lookback_days: u32,
+    /// Users who logged in on the head node within the number of days are not listed.
+    /// The logins are taken from lastlog over ssh.
+    #[clap(long, verbatim_doc_comment)]
+    pub lastlog: bool,
+    /// Prints only the usernames, one per line.
+    /// Is meant as input for a bulk delete, e.g. via xargs.
+    #[clap(long, verbatim_doc_comment)]
+    pub names_only: bool,
+    /// Path where to find key pair to be used for ssh connection.
+    /// Has priority over the path from the configuration file.
+    #[arg(long, verbatim_doc_comment)]
+    pub ssh_path: Option<PathBuf>,
+}
+
+/// Filters, sorting, columns and paging for listing users.
+/// Filters are applied in the LDAP search and on the associations of Slurm.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ListArgs {
+    /// Only users of this group, e.g. staff.
+    /// In Slurm all associations of the users with this group in LDAP are listed.
+    #[clap(long, verbatim_doc_comment)]
+    pub group: Option<Group>,
+    /// Only users with this QOS.
+    #[clap(long)]
+    pub qos: Option<String>,
+    /// Only users with this default QOS.
+    #[clap(long)]
+    pub default_qos: Option<String>,
+    /// Only users whose username, first name, last name or mail contains this text.
+    /// In Slurm only the username is searched.
+    #[clap(long, verbatim_doc_comment)]
+    pub search: Option<String>,
+    /// Only users in LDAP with at least this uid.
+    #[clap(long)]
+    pub uid_min: Option<u32>,
+    /// Only users in LDAP with at most this uid.
+    #[clap(long)]
+    pub uid_max: Option<u32>,
+    /// Raw LDAP filter which is combined with the other filters, e.g. "(loginShell=/bin/zsh)".
+    #[clap(long)]
+    pub ldap_filter: Option<String>,
+    /// Sorts the users by this column. The users of LDAP and Slurm are merged per user with this option.
+    #[clap(long, value_enum)]
+    pub sort_by: Option<ListColumn>,
+    /// Sorts in descending instead of ascending order.
+    #[clap(long, requires = "sort_by")]
+    pub descending: bool,
+    /// Prints only these columns in this order, e.g. username,mail.
+    /// The users of LDAP and Slurm are merged per user with this option.
+    #[clap(long, value_enum, value_delimiter = ',', verbatim_doc_comment)]
+    pub columns: Vec<ListColumn>,
+    /// Number of users per page. The users of LDAP and Slurm are merged per user with this option.
+    #[clap(long)]
+    pub page_size: Option<NonZeroUsize>,
+    /// Page to print, starting with 1.
+    #[clap(long, requires = "page_size")]
+    pub page: Option<NonZeroUsize>,
+}
+
+impl ListArgs {
+    /// True if any filter is given.
+    pub fn has_filters(&self) -> bool {
+        self.group.is_some()
+            || self.qos.is_some()
+            || self.default_qos.is_some()
+            || self.search.is_some()
+            || self.uid_min.is_some()
+            || self.uid_max.is_some()
+            || self.ldap_filter.is_some()
+    }
+
+    /// True if the users of LDAP and Slurm need to be merged per user to sort, select columns or page.
+    pub fn needs_merged_users(&self) -> bool {
+        self.sort_by.is_some() || !self.columns.is_empty() || self.page_size.is_some()
+    }
+}
+
+/// Options for the report of CPU and GPU hours
+#[derive(Args, Debug, Clone)]
+pub struct UsageArgs {
+    /// First day of the time window, e.g. 2024-02-01.
+    /// Without start and end the previous month is shown.
+    #[clap(long, requires = "end", verbatim_doc_comment)]
+    pub start: Option<NaiveDate>,
+    /// Day after the time window, e.g. 2024-03-01.
+    #[clap(long, requires = "start")]
+    pub end: Option<NaiveDate>,
+    /// Sums up the hours per group instead of listing every user.
+    #[clap(long)]
+    pub by_group: bool,
+    /// Path where to find key pair to be used for ssh connection.
+    /// Has priority over the path from the configuration file.
+    #[arg(long, verbatim_doc_comment)]
+    pub ssh_path: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+/// CLI sub commands for coordinators of accounts in the Slurm database.
+/// A coordinator can manage an account and its users, e.g. a professor for a project account.
+pub enum CoordinatorCommands {
+    /// Make users coordinators of an account in Slurm
+    Add(CoordinatorFields),
+    /// Remove users as coordinators of an account in Slurm.
+    /// Not supported via slurmrestd
+    Remove(CoordinatorFields),
+    /// List all accounts with their coordinators in Slurm
+    List,
+}
+
+/// Defines the account and users for adding or removing coordinators in Slurm
+#[derive(Args, Debug, Clone)]
+pub struct CoordinatorFields {
+    /// Name of the account e.g. project_x.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub account: TrimmedNonEmptyText,
+    /// Usernames of the coordinators. Need to be provided as a whitespace separated list (e.g. wagnerdo musterma).
+    #[clap(value_parser = trimmed_non_empty, num_args(1..=20), required = true)]
+    pub users: Vec<TrimmedNonEmptyText>,
+}
+
+/// Defines options for creating or modifying an account in Slurm
+#[derive(Args, Debug, Clone)]
+pub struct AccountFields {
+    /// Name of the account e.g. project_x.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub name: TrimmedNonEmptyText,
+    /// Parent account in the account hierarchy e.g. faculty_cs.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub parent: Option<TrimmedNonEmptyText>,
+    /// Description of the account.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub description: Option<TrimmedNonEmptyText>,
+    /// Organization the account belongs to.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub organization: Option<TrimmedNonEmptyText>,
+}
+
+impl From<AccountFields> for AccountAttributes {
+    fn from(value: AccountFields) -> Self {
+        Self {
+            parent: value.parent.map(Into::into),
+            description: value.description.map(Into::into),
+            organization: value.organization.map(Into::into),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+/// CLI sub commands for operations on QOS in the Slurm database
+pub enum QosCommands {
+    /// Create a QOS in Slurm
+    Create {
+        #[command(flatten)]
+        qos: QosFields,
+        /// Adds the new QOS to valid_qos in the conf.toml.
+        /// Only valid_qos is changed, comments within the conf.toml are kept.
+        /// With a profile which overrides valid_qos, the QOS is added to the profile.
+        #[arg(long, verbatim_doc_comment)]
+        add_to_valid_qos: bool,
+    },
+    /// Change priority, limits or preemption settings of a QOS in Slurm
+    Modify(QosFields),
+    /// Delete a QOS in Slurm
+    Delete {
+        /// Name of the QOS e.g. gpu.
+        #[clap(value_parser = trimmed_non_empty)]
+        name: TrimmedNonEmptyText,
+    },
+    /// List all QOS in Slurm
+    List,
+}
+
+/// Defines options for creating or modifying a QOS in Slurm
+#[derive(Args, Debug, Clone)]
+pub struct QosFields {
+    /// Name of the QOS e.g. gpu.
+    #[clap(value_parser = trimmed_non_empty)]
+    pub name: TrimmedNonEmptyText,
+    /// Priority of jobs within this QOS e.g. 100.
+    #[clap(long)]
+    pub priority: Option<u32>,
+    /// Limit of trackable resources per user e.g. cpu=64,gres/gpu=2.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub max_tres_per_user: Option<TrimmedNonEmptyText>,
+    /// Maximum wall time of each job e.g. 2-00:00:00.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub max_wall: Option<TrimmedNonEmptyText>,
+    /// QOS which can be preempted by jobs of this QOS.
+    /// QOS need to be provided as a whitespace separated list (e.g. basic interactive).
+    #[clap(long, value_parser = trimmed_non_empty, num_args(1..=20), verbatim_doc_comment)]
+    pub preempt: Option<Vec<TrimmedNonEmptyText>>,
+    /// How jobs of this QOS are preempted e.g. cancel, requeue or suspend.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub preempt_mode: Option<TrimmedNonEmptyText>,
+}
+
+impl From<QosFields> for QosAttributes {
+    fn from(value: QosFields) -> Self {
+        Self {
+            priority: value.priority,
+            max_tres_per_user: value.max_tres_per_user.map(Into::into),
+            max_wall: value.max_wall.map(Into::into),
+            preempt: value
+                .preempt
+                .map(|preempt| preempt.into_iter().map(Into::into).collect()),
+            preempt_mode: value.preempt_mode.map(Into::into),
+        }
+    }
 }
 
 /// Defines options for modifying an user
 #[derive(Args, Debug, Clone, Into)]
 pub struct Modifiable {
+    /// Username e.g. wagnerdo.
+    #[clap(value_parser = trimmed_non_empty)]
+    username: TrimmedNonEmptyText,
     /// Firstname of the user.
     #[clap(short, long, value_parser = trimmed_non_empty)]
     firstname: Option<TrimmedNonEmptyText>,
@@ -97,24 +437,49 @@ pub struct Modifiable {
 impl Modifiable {
     pub fn new(username: TrimmedNonEmptyText) -> Self {
         Self {
+            username,
             firstname: Default::default(),
             lastname: Default::default(),
-            common_user_fields: CommonUserFields::new(username),
+            common_user_fields: Default::default(),
         }
     }
 }
 
 /// Defines options for adding an user
-#[derive(Args, Debug, Clone, Into)]
+#[derive(Args, Debug, Clone)]
 pub struct UserToAdd {
+    /// Username e.g. wagnerdo.
+    /// Can be omitted if `username_template` is set in the conf.toml.
+    /// The username is then derived from the first and last name.
+    #[clap(value_parser = trimmed_non_empty, verbatim_doc_comment)]
+    pub username: Option<TrimmedNonEmptyText>,
     /// Firstname of the user.
     #[clap(short, long, value_parser = trimmed_non_empty)]
-    firstname: TrimmedNonEmptyText,
+    pub firstname: TrimmedNonEmptyText,
     /// Lastname of the user.
     #[clap(short, long, value_parser = trimmed_non_empty)]
-    lastname: TrimmedNonEmptyText,
+    pub lastname: TrimmedNonEmptyText,
     #[command(flatten)]
-    common_user_fields: CommonUserFields,
+    pub common_user_fields: CommonUserFields,
+    /// Slurm accounts the user is added to. Defaults to the account named after the group of the user.
+    /// Accounts need to be provided as a whitespace separated list (e.g. project_a project_b).
+    #[clap(long = "account", value_parser = trimmed_non_empty, num_args(1..=20), verbatim_doc_comment)]
+    pub accounts: Vec<TrimmedNonEmptyText>,
+    /// Default Slurm account of the user. Must be one of the accounts given via --account.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub default_account: Option<TrimmedNonEmptyText>,
+    /// Slurm partitions the user is restricted to. Defaults to the partitions of the group in the conf.toml.
+    /// Partitions need to be provided as a whitespace separated list (e.g. cpu gpu).
+    #[clap(long = "partition", value_parser = trimmed_non_empty, num_args(1..=20), verbatim_doc_comment)]
+    pub partitions: Vec<TrimmedNonEmptyText>,
+    /// Slurm clusters the user is added to. Defaults to the clusters of the group in the conf.toml.
+    /// Clusters need to be provided as a whitespace separated list (e.g. alpha beta).
+    #[clap(long = "cluster", value_parser = trimmed_non_empty, num_args(1..=20), verbatim_doc_comment)]
+    pub clusters: Vec<TrimmedNonEmptyText>,
+    /// Makes the user a coordinator of its default Slurm account.
+    /// A coordinator can manage the account and its users, e.g. a professor for a project account.
+    #[clap(long, verbatim_doc_comment)]
+    pub coordinator: bool,
 }
 
 impl UserToAdd {
@@ -124,23 +489,39 @@ impl UserToAdd {
         common_user_fields: CommonUserFields,
     ) -> Self {
         Self {
+            username: None,
             firstname,
             lastname,
             common_user_fields,
+            accounts: Default::default(),
+            default_account: Default::default(),
+            partitions: Default::default(),
+            clusters: Default::default(),
+            coordinator: false,
         }
     }
 
     pub fn common_user_fields(&self) -> &CommonUserFields {
         &self.common_user_fields
     }
+
+    pub fn firstname(&self) -> &TrimmedNonEmptyText {
+        &self.firstname
+    }
+
+    pub fn lastname(&self) -> &TrimmedNonEmptyText {
+        &self.lastname
+    }
+
+    pub fn with_username(mut self, username: TrimmedNonEmptyText) -> Self {
+        self.username = Some(username);
+        self
+    }
 }
 
-#[derive(Args, Debug, Clone, Into)]
+#[derive(Args, Debug, Clone, Default, Into)]
 /// Attributes which are used  on structs for operations on users (adding, deleting or modifying).
 pub struct CommonUserFields {
-    /// Username e.g. wagnerdo.
-    #[clap(value_parser = trimmed_non_empty)]
-    pub username: TrimmedNonEmptyText,
     /// Unix group the user belongs to e.g. staff.
     #[clap(short, long, value_parser = trimmed_non_empty)]
     pub group: Option<TrimmedNonEmptyText>,
@@ -156,17 +537,40 @@ pub struct CommonUserFields {
     /// List of QOS assigned to the user (must be valid QOS i.e. they must exist in valid_qos of conf.toml). QOS need to be provided as a whitespace separated list (e.g. interactive basic).
     #[clap(short, long, num_args(0..=20))]
     pub qos: Vec<String>,
+    #[command(flatten)]
+    pub limits: LimitFields,
 }
 
-impl CommonUserFields {
-    pub fn new(username: TrimmedNonEmptyText) -> Self {
+/// Slurm limits of an user.
+/// While adding an user, every limit not given is taken from the limits of the group in the conf.toml.
+#[derive(Args, Debug, Clone, Default)]
+pub struct LimitFields {
+    /// Limit of trackable resources for all running jobs of the user e.g. cpu=64,gres/gpu=2.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub grp_tres: Option<TrimmedNonEmptyText>,
+    /// Maximum number of running jobs of the user.
+    #[clap(long)]
+    pub max_jobs: Option<u32>,
+    /// Maximum number of running and pending jobs of the user.
+    #[clap(long)]
+    pub max_submit_jobs: Option<u32>,
+    /// Maximum wall time of each job of the user e.g. 2-00:00:00.
+    #[clap(long, value_parser = trimmed_non_empty)]
+    pub max_wall: Option<TrimmedNonEmptyText>,
+    /// Fairshare value of the user e.g. 10.
+    #[clap(long)]
+    pub fairshare: Option<u32>,
+}
+
+impl From<LimitFields> for AssociationLimits {
+    fn from(value: LimitFields) -> Self {
         Self {
-            username,
-            group: Default::default(),
-            mail: Default::default(),
-            default_qos: Default::default(),
-            publickey: Default::default(),
-            qos: Default::default(),
+            grp_tres: value.grp_tres.map(Into::into),
+            max_jobs: value.max_jobs,
+            max_submit_jobs: value.max_submit_jobs,
+            max_wall: value.max_wall.map(Into::into),
+            fairshare: value.fairshare,
+            partitions: Default::default(),
         }
     }
 }
@@ -0,0 +1,224 @@
+use std::{
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Context};
+use clap::Args;
+
+use crate::prelude::{AppError, AppResult};
+
+/// Where a credential is taken from.
+/// Every source except [`CredentialSource::Prompt`] works without a terminal, e.g. in cron jobs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CredentialSource {
+    /// Asks for the credential in the terminal.
+    #[default]
+    Prompt,
+    /// Value of the environment variable with this name.
+    Env(String),
+    /// First line of the file at this path. The file must only be accessible by its owner.
+    File(PathBuf),
+    /// Next line from stdin.
+    Stdin,
+    /// First line printed by this shell command, e.g. `pass show ldap/admin`.
+    Command(String),
+}
+
+impl FromStr for CredentialSource {
+    type Err = AppError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let source = match input.split_once(':') {
+            Some(("env", name)) if !name.is_empty() => Self::Env(name.to_owned()),
+            Some(("file", path)) if !path.is_empty() => Self::File(PathBuf::from(path)),
+            Some(("cmd", command)) if !command.trim().is_empty() => {
+                Self::Command(command.to_owned())
+            }
+            _ => match input {
+                "prompt" => Self::Prompt,
+                "stdin" => Self::Stdin,
+                _ => bail!(
+                    "Credential source ({}) is not valid. \
+                    Valid are prompt, stdin, env:<variable>, file:<path> or cmd:<command>",
+                    input
+                ),
+            },
+        };
+        Ok(source)
+    }
+}
+
+impl CredentialSource {
+    /// Fetches the credential from this source.
+    /// Returns `None` for [`CredentialSource::Prompt`] since prompting is up to the caller.
+    ///
+    /// # Errors
+    ///
+    /// - If the source yields no or an empty credential.
+    /// - If the environment variable is not set.
+    /// - If the file can not be read or is accessible by others than its owner.
+    /// - If reading from stdin fails.
+    /// - If the command can not be started or fails.
+    pub fn fetch(&self) -> AppResult<Option<String>> {
+        let fetched = match self {
+            Self::Prompt => return Ok(None),
+            Self::Env(name) => std::env::var(name)
+                .with_context(|| format!("Environment variable {} is not set", name))?,
+            Self::File(path) => read_restricted_file(path)?,
+            Self::Stdin => {
+                let mut line = String::new();
+                io::stdin()
+                    .lock()
+                    .read_line(&mut line)
+                    .context("Could not read credential from stdin")?;
+                line
+            }
+            Self::Command(command) => run_password_command(command)?,
+        };
+        let first_line = fetched.lines().next().unwrap_or_default();
+        if first_line.is_empty() {
+            bail!("Credential from {} is empty", self);
+        }
+        Ok(Some(first_line.to_owned()))
+    }
+}
+
+impl std::fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Prompt => write!(f, "prompt"),
+            Self::Env(name) => write!(f, "env:{}", name),
+            Self::File(path) => write!(f, "file:{}", path.display()),
+            Self::Stdin => write!(f, "stdin"),
+            Self::Command(command) => write!(f, "cmd:{}", command),
+        }
+    }
+}
+
+/// Sources of the credentials for LDAP and SSH.
+/// Without a source the credential is asked for in the terminal.
+#[derive(Args, Debug, Clone, Default)]
+pub struct CredentialArgs {
+    /// Fails instead of asking for a missing credential in the terminal.
+    #[clap(long, global = true)]
+    pub non_interactive: bool,
+    /// Source of the LDAP username: prompt, stdin, env:<variable>, file:<path> or cmd:<command>.
+    #[clap(long, global = true, value_name = "SOURCE")]
+    pub ldap_user_from: Option<CredentialSource>,
+    /// Source of the LDAP password: prompt, stdin, env:<variable>, file:<path> or cmd:<command>.
+    #[clap(long, global = true, value_name = "SOURCE")]
+    pub ldap_password_from: Option<CredentialSource>,
+    /// Source of the SSH username: prompt, stdin, env:<variable>, file:<path> or cmd:<command>.
+    #[clap(long, global = true, value_name = "SOURCE")]
+    pub ssh_user_from: Option<CredentialSource>,
+    /// Source of the SSH password or passphrase of the key pair:
+    /// prompt, stdin, env:<variable>, file:<path> or cmd:<command>.
+    #[clap(long, global = true, value_name = "SOURCE", verbatim_doc_comment)]
+    pub ssh_password_from: Option<CredentialSource>,
+}
+
+fn read_restricted_file(path: &Path) -> AppResult<String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)
+            .with_context(|| format!("Could not read metadata of credential file {:?}", path))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            bail!(
+                "Credential file {:?} is accessible by others than its owner (mode {:o}). \
+                Restrict it via chmod 600",
+                path,
+                mode & 0o777
+            );
+        }
+    }
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read credential file {:?}", path))
+}
+
+fn run_password_command(command: &str) -> AppResult<String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .with_context(|| format!("Could not start command ({}) for a credential", command))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Command ({}) for a credential failed with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("Output of command ({}) is not valid UTF-8", command))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parse_sources() {
+        assert_eq!(
+            CredentialSource::Env("LDAP_PASSWORD".to_owned()),
+            "env:LDAP_PASSWORD".parse().unwrap()
+        );
+        assert_eq!(
+            CredentialSource::Command("pass show ldap/admin".to_owned()),
+            "cmd:pass show ldap/admin".parse().unwrap()
+        );
+        assert_eq!(
+            CredentialSource::File(PathBuf::from("/etc/usermgmt/ldap:pw")),
+            "file:/etc/usermgmt/ldap:pw".parse().unwrap()
+        );
+        assert_eq!(CredentialSource::Stdin, "stdin".parse().unwrap());
+        assert_eq!(CredentialSource::Prompt, "prompt".parse().unwrap());
+        assert!("env:".parse::<CredentialSource>().is_err());
+        assert!("password".parse::<CredentialSource>().is_err());
+    }
+
+    #[test]
+    fn fetch_first_line_from_env_and_command() {
+        std::env::set_var("USERMGMT_TEST_CREDENTIAL_SOURCE", "secret\nignored");
+        assert_eq!(
+            Some("secret".to_owned()),
+            CredentialSource::Env("USERMGMT_TEST_CREDENTIAL_SOURCE".to_owned())
+                .fetch()
+                .unwrap()
+        );
+        assert_eq!(
+            Some("from command".to_owned()),
+            CredentialSource::Command("printf 'from command\\nsecond'".to_owned())
+                .fetch()
+                .unwrap()
+        );
+        assert!(CredentialSource::Command("exit 3".to_owned())
+            .fetch()
+            .is_err());
+        assert_eq!(None, CredentialSource::Prompt.fetch().unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuse_credential_file_readable_by_others() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path =
+            std::env::temp_dir().join(format!("usermgmt_credential_source_{}", std::process::id()));
+        std::fs::write(&path, "secret\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let source = CredentialSource::File(path.clone());
+        assert!(source.fetch().is_err());
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let fetched = source.fetch();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Some("secret".to_owned()), fetched.unwrap());
+    }
+}
@@ -1,19 +1,34 @@
-pub use path_sources::get_path_to_conf;
+pub use migration::{Migrated, UnknownKey, CONFIG_VERSION};
+pub use path_sources::{get_explicit_path_to_conf, get_path_to_conf, CONFIG_PATH_ENV};
+pub use profiles::PROFILE_ENV;
+pub use provenance::{ConfigEntry, ValueSource, ENV_OVERRIDE_PREFIX};
+pub use validation::ConfigProblem;
 
+mod file_edits;
+mod migration;
 mod path_sources;
+mod profiles;
+mod provenance;
+mod validation;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
-use log::info;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::{config, prelude::*};
+use crate::{ldap::LdapSearchScope, prelude::*, slurm::AssociationLimits, Group};
 
 /// This configuration is read from a configuration file in production.
 /// It contains many options to control this application performs actions the various systems
 /// on the cluster.
+///
+/// Fields missing in a configuration file take their value from [`MgmtConfig::default`].
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct MgmtConfig {
     pub student_default_qos: String,
     pub staff_default_qos: String,
@@ -60,8 +75,73 @@ pub struct MgmtConfig {
     pub ssh_port: u32,
     pub ssh_agent: bool,
     pub ssh_key_path: Option<PathBuf>,
+    pub username_regex: Option<String>,
+    pub username_min_length: Option<usize>,
+    pub username_max_length: Option<usize>,
+    pub username_reserved: Vec<String>,
+    pub username_reserve_system_accounts: bool,
+    pub username_lowercase_only: bool,
+    pub username_template: Option<String>,
+    pub validate_qos_against_slurm: bool,
+    pub use_slurmrestd: bool,
+    pub slurmrestd_url: String,
+    pub slurmrestd_api_version: String,
+    pub slurmrestd_user: Option<String>,
+    pub slurmrestd_token: Option<String>,
+    pub student_limits: AssociationLimits,
+    pub staff_limits: AssociationLimits,
+    pub clusters: Vec<String>,
+    pub student_clusters: Vec<String>,
+    pub staff_clusters: Vec<String>,
+    pub sacct_path: String,
+    pub sreport_path: String,
+    pub ldap_search_scope: LdapSearchScope,
+    pub ldap_search_filter: String,
+    pub ldap_page_size: u32,
+    /// Version of the layout of the configuration file. See [`CONFIG_VERSION`].
+    pub config_version: u32,
+    /// Named profiles, e.g. one per cluster. Every profile overrides some of the fields above.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, toml::Table>,
 }
 impl MgmtConfig {
+    /// Clusters a new user of the given group is added to.
+    /// Falls back to the field `clusters` if no clusters are configured for the group.
+    /// An empty list means the default cluster of slurm.
+    pub fn clusters_of_group(&self, group: Group) -> Vec<String> {
+        let of_group = match group {
+            Group::Staff | Group::Faculty => &self.staff_clusters,
+            Group::Student => &self.student_clusters,
+        };
+        if of_group.is_empty() {
+            self.clusters.clone()
+        } else {
+            of_group.clone()
+        }
+    }
+
+    /// # Errors
+    ///
+    /// - If the field `clusters` is not empty and does not contain all of the given clusters.
+    pub fn ensure_known_clusters(&self, clusters: &[String]) -> AppResult {
+        if self.clusters.is_empty() {
+            return Ok(());
+        }
+        let unknown: Vec<&str> = clusters
+            .iter()
+            .filter(|next| !self.clusters.contains(next))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            bail!(
+                "Clusters ({}) are not part of the configured clusters ({})",
+                unknown.join(","),
+                self.clusters.join(",")
+            );
+        }
+        Ok(())
+    }
+
     /// # Errors
     ///
     /// - If the parameter `path` can not be converted into an absolute path.
@@ -140,30 +220,218 @@ impl Default for MgmtConfig {
             ssh_port: 22,
             ssh_agent: false,
             ssh_key_path: None,
+            username_regex: None,
+            username_min_length: None,
+            username_max_length: None,
+            username_reserved: vec!["root".to_string()],
+            username_reserve_system_accounts: false,
+            username_lowercase_only: false,
+            username_template: None,
+            validate_qos_against_slurm: false,
+            use_slurmrestd: false,
+            slurmrestd_url: "http://localhost:6820".to_string(),
+            slurmrestd_api_version: "v0.0.40".to_string(),
+            slurmrestd_user: None,
+            slurmrestd_token: None,
+            student_limits: Default::default(),
+            staff_limits: Default::default(),
+            clusters: Default::default(),
+            student_clusters: Default::default(),
+            staff_clusters: Default::default(),
+            sacct_path: "/usr/local/bin/sacct".to_string(),
+            sreport_path: "/usr/local/bin/sreport".to_string(),
+            ldap_search_scope: Default::default(),
+            ldap_search_filter: crate::ldap::ALL_ENTRIES_FILTER.to_string(),
+            ldap_page_size: 200,
+            config_version: CONFIG_VERSION,
+            profiles: Default::default(),
         }
     }
 }
 
-/// Tries to load  config.toml for application.
+/// Loads the configuration file given via parameter `explicit_path`, via the environment variable
+/// `USERMGMT_CONFIG` or found at the usual locations. See [`get_path_to_conf`].
+/// Unlike [`load_effective_config`] no profile and no environment variable for a field is applied.
+/// A missing configuration file is not created. It is only created by [`MgmtConfig::save`].
 ///
-/// # Error
+/// # Errors
 ///
-/// - Can not ensure if folder exits where conf.toml file exits
-/// - Can not read or create a configuration file
-pub fn load_config(manual_path: Option<PathBuf>) -> AppResult<LoadedMgmtConfig> {
-    let path = config::get_path_to_conf(manual_path)?;
-
-    info!("Loading configuration file from path at {:?}", path);
-    // Load (or create if nonexistent) configuration file conf.toml
-    let config = confy::load_path(&path)
-        .with_context(|| format!("Error in loading or creating config file at {:?}", &path))?;
-    let path = path
+/// - If an explicitly given configuration file does not exist. See [`get_explicit_path_to_conf`].
+/// - If no configuration file could be found at the usual locations.
+/// - If the configuration file can not be read or is not valid.
+pub fn load_config(explicit_path: Option<PathBuf>) -> AppResult<LoadedMgmtConfig> {
+    let file = resolve_config_file(explicit_path)?;
+
+    info!("Loading configuration file from path at {:?}", file);
+    let content = migrate_for_loading(&read_config_file(&file)?, &file)?;
+    let config = toml::from_str(&content)
+        .with_context(|| format!("Configuration file at {:?} is not valid", file))?;
+    let path = file
         .parent()
-        .ok_or_else(|| anyhow!("{:?} needs to have a parent folder", &path))?
+        .ok_or_else(|| anyhow!("{:?} needs to have a parent folder", &file))?
         .to_path_buf();
     Ok(LoadedMgmtConfig { path, config })
 }
 
+/// Loads the configuration file given via parameter `explicit_path`, via the environment variable
+/// `USERMGMT_CONFIG` or found at the usual locations. See [`get_path_to_conf`].
+/// Afterwards the profile given via parameter `profile` or via the environment variable
+/// `USERMGMT_PROFILE` is applied
+/// and fields are overridden by environment variables like `USERMGMT_LDAP_SERVER`.
+///
+/// # Errors
+///
+/// - If an explicitly given configuration file does not exist. See [`get_explicit_path_to_conf`].
+/// - If no configuration file could be found at the usual locations.
+/// - If the configuration file can not be read or is not valid.
+/// - If the profile is not defined or not valid. See [`MgmtConfig::with_profile`].
+/// - If an environment variable does not fit to the type of its field.
+pub fn load_effective_config(
+    explicit_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> AppResult<LoadedMgmtConfig> {
+    load_config_with_sources(explicit_path, profile).map(|(loaded, _)| loaded)
+}
+
+/// Same as [`load_effective_config`] but also returns every field with its effective value
+/// and where this value comes from.
+///
+/// # Errors
+///
+/// See [`load_effective_config`].
+pub fn load_config_with_sources(
+    explicit_path: Option<PathBuf>,
+    profile: Option<String>,
+) -> AppResult<(LoadedMgmtConfig, Vec<ConfigEntry>)> {
+    let file = resolve_config_file(explicit_path)?;
+
+    info!("Loading configuration file from path at {:?}", file);
+    let content = migrate_for_loading(&read_config_file(&file)?, &file)?;
+    let profile = resolve_profile(profile);
+    if let Some(profile) = profile.as_deref() {
+        info!("Using profile {}", profile);
+    }
+    let (config, entries) =
+        provenance::read_with_overrides(&content, &file, profile.as_deref(), |variable| {
+            std::env::var(variable).ok()
+        })?;
+    for entry in entries.iter() {
+        if let ValueSource::Env(variable) = &entry.source {
+            info!("Field {} is overridden by {}", entry.field, variable);
+        }
+    }
+    let path = file
+        .parent()
+        .ok_or_else(|| anyhow!("{:?} needs to have a parent folder", &file))?
+        .to_path_buf();
+    Ok((LoadedMgmtConfig { path, config }, entries))
+}
+
+/// Migrates the configuration file given via parameter `explicit_path`, via the environment
+/// variable `USERMGMT_CONFIG` or found at the usual locations to the current version.
+/// Comments and formatting of the file are kept.
+/// Unless `dry_run` is true, the original file is kept with the extension `.bak`
+/// and the migrated content is written to the file.
+/// Nothing is written if the file has the current version already.
+///
+/// Returns the path to the file and the outcome of the migration.
+///
+/// # Errors
+///
+/// - If the configuration file can not be found or read.
+/// - If the file can not be migrated. See [`Migrated`].
+/// - If writing the backup or the migrated file fails.
+pub fn upgrade_config_file(
+    explicit_path: Option<PathBuf>,
+    dry_run: bool,
+) -> AppResult<(PathBuf, Migrated)> {
+    let file = resolve_config_file(explicit_path)?;
+    let migrated = migration::migrate(&read_config_file(&file)?, &file)?;
+    if migrated.is_outdated() && !dry_run {
+        let backup = backup_path(&file);
+        std::fs::copy(&file, &backup)
+            .with_context(|| format!("Could not back up configuration file to {:?}", backup))?;
+        std::fs::write(&file, &migrated.content)
+            .with_context(|| format!("Could not write upgraded configuration to {:?}", file))?;
+    }
+    Ok((file, migrated))
+}
+
+/// Path of the copy of the configuration file at `file` which is kept while upgrading it.
+pub fn backup_path(file: &Path) -> PathBuf {
+    let mut backup = file.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Adds `qos` to the field `valid_qos` of the configuration file given via parameter
+/// `explicit_path`, via the environment variable `USERMGMT_CONFIG` or found at the usual locations.
+/// If the profile given via parameter `profile` or via the environment variable `USERMGMT_PROFILE`
+/// overrides `valid_qos`, the QOS is added to the list of this profile.
+/// Otherwise it is added to the shared list. See [`MgmtConfig::with_profile`].
+/// Only this field is changed within the file itself.
+/// Values from environment variables like `USERMGMT_LDAP_SERVER` are never written.
+///
+/// Returns the path to the changed file or None if `valid_qos` contains `qos` already.
+///
+/// # Errors
+///
+/// - If the configuration file can not be found, read or written.
+/// - If the configuration file is no valid toml or its field `valid_qos` is no list.
+pub fn add_valid_qos_to_file(
+    explicit_path: Option<PathBuf>,
+    profile: Option<String>,
+    qos: &str,
+) -> AppResult<Option<PathBuf>> {
+    let file = resolve_config_file(explicit_path)?;
+    let profile = resolve_profile(profile);
+    let changed = file_edits::add_valid_qos(&read_config_file(&file)?, profile.as_deref(), qos)
+        .with_context(|| format!("Could not add QOS to configuration file at {:?}", file))?;
+    match changed {
+        None => Ok(None),
+        Some(content) => {
+            std::fs::write(&file, content)
+                .with_context(|| format!("Could not save to path: {:?}", file))?;
+            Ok(Some(file))
+        }
+    }
+}
+
+fn resolve_config_file(explicit_path: Option<PathBuf>) -> AppResult<PathBuf> {
+    let explicit_path =
+        explicit_path.or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from));
+    match explicit_path {
+        Some(explicit) => get_explicit_path_to_conf(&explicit),
+        None => get_path_to_conf(None),
+    }
+}
+
+fn resolve_profile(profile: Option<String>) -> Option<String> {
+    profile.or_else(|| std::env::var(PROFILE_ENV).ok())
+}
+
+fn read_config_file(file: &Path) -> AppResult<String> {
+    std::fs::read_to_string(file)
+        .with_context(|| format!("Could not read configuration file at {:?}", file))
+}
+
+/// Migrates the content of a configuration file in memory
+/// and warns about unknown keys and an outdated version.
+fn migrate_for_loading(content: &str, file: &Path) -> AppResult<String> {
+    let migrated = migration::migrate(content, file)?;
+    for unknown in migrated.unknown_keys.iter() {
+        warn!("Configuration file at {:?}: {}", file, unknown);
+    }
+    if migrated.is_outdated() {
+        warn!(
+            "Configuration file at {:?} has the outdated version {}. \
+            Run \"usermgmt config upgrade\" to upgrade it to version {}",
+            file, migrated.from_version, CONFIG_VERSION
+        );
+    }
+    Ok(migrated.content)
+}
+
 #[derive(Debug, Default)]
 pub struct LoadedMgmtConfig {
     pub path: PathBuf,
@@ -176,3 +444,22 @@ pub fn config_for_save() -> String {
     toml::to_string_pretty(&MgmtConfig::default())
         .expect("Could not turn default configuration into the toml format")
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn refuse_missing_config_file_without_creating_it() {
+        let folder =
+            std::env::temp_dir().join(format!("usermgmt_missing_config_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let actual = load_config(Some(folder.clone()));
+        let created = folder.join(constants::NAME_CONFIG_FILE).exists();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        assert!(actual.is_err());
+        assert!(!created);
+    }
+}
@@ -0,0 +1,139 @@
+//! Edits of single fields within the content of a configuration file.
+//!
+//! Only the content of the file is changed.
+//! Fields overridden by environment variables are not written back this way.
+
+use toml_edit::{Array, DocumentMut, Item, TableLike};
+
+use super::{profiles::PROFILES_FIELD, MgmtConfig};
+use crate::prelude::*;
+
+const VALID_QOS_FIELD: &str = "valid_qos";
+
+/// Adds `qos` to the field `valid_qos` within the content of a configuration file.
+/// The list of the profile `profile` is changed if this profile overrides `valid_qos`.
+/// Otherwise the shared list at the top level is changed.
+/// If the file does not set `valid_qos`, the default QOS together with `qos` are added.
+/// Comments and formatting of the content are kept.
+/// Returns the changed content or None if `valid_qos` contains `qos` already.
+///
+/// # Errors
+///
+/// - If the content is no valid toml.
+/// - If `valid_qos` is no list.
+pub fn add_valid_qos(content: &str, profile: Option<&str>, qos: &str) -> AppResult<Option<String>> {
+    let mut document: DocumentMut = content.parse().context("Configuration is no valid toml")?;
+    let overridden_by_profile = profile.filter(|name| {
+        document
+            .get(PROFILES_FIELD)
+            .and_then(|profiles| profiles.get(name))
+            .and_then(|profile| profile.get(VALID_QOS_FIELD))
+            .is_some()
+    });
+    let table: &mut dyn TableLike = match overridden_by_profile {
+        Some(name) => document
+            .get_mut(PROFILES_FIELD)
+            .and_then(|profiles| profiles.get_mut(name))
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| anyhow!("Profile {} is no table", name))?,
+        None => document.as_table_mut(),
+    };
+    let valid_qos = table
+        .entry(VALID_QOS_FIELD)
+        .or_insert_with(|| toml_edit::value(Array::from_iter(MgmtConfig::default().valid_qos)));
+    let valid_qos = valid_qos
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("Field {} is no list", VALID_QOS_FIELD))?;
+    if valid_qos.iter().any(|next| next.as_str() == Some(qos)) {
+        return Ok(None);
+    }
+    // Puts the new entry on its own line with the same indentation if the last one is.
+    let indentation = valid_qos
+        .iter()
+        .last()
+        .and_then(|last| last.decor().prefix()?.as_str())
+        .and_then(|prefix| prefix.rsplit_once('\n'))
+        .map(|(_, indentation)| format!("\n{}", indentation));
+    valid_qos.push(qos);
+    if let (Some(indentation), Some(added)) = (indentation, valid_qos.iter_mut().last()) {
+        added.decor_mut().set_prefix(indentation);
+    }
+    Ok(Some(document.to_string()))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn keep_comments_and_formatting() {
+        let content = r#"# Shared QOS
+valid_qos = [
+    "interactive", # for tests
+    "basic",
+]
+head_node = "head" # login node
+"#;
+
+        let added = add_valid_qos(content, None, "gpu").unwrap().unwrap();
+
+        insta::assert_snapshot!(added);
+    }
+
+    #[test]
+    fn add_qos_only_once() {
+        let content = "valid_qos = [\"basic\"]\nhead_node = \"head\"\n";
+
+        let added = add_valid_qos(content, None, "gpu").unwrap().unwrap();
+
+        let added: MgmtConfig = toml::from_str(&added).unwrap();
+        assert_eq!(vec!["basic", "gpu"], added.valid_qos);
+        assert_eq!(None, add_valid_qos(content, None, "basic").unwrap());
+    }
+
+    #[test]
+    fn add_qos_to_default_qos_if_not_set() {
+        let added = add_valid_qos("head_node = \"head\"\n", None, "gpu")
+            .unwrap()
+            .unwrap();
+
+        let added: MgmtConfig = toml::from_str(&added).unwrap();
+        assert_eq!(
+            vec!["interactive", "basic", "advanced", "gpu"],
+            added.valid_qos
+        );
+    }
+
+    #[test]
+    fn add_qos_to_profile_only_if_it_overrides_valid_qos() {
+        let content = r#"
+valid_qos = ["basic"]
+
+[profiles.teaching]
+valid_qos = ["interactive"]
+
+[profiles.research]
+head_node = "research-head"
+"#;
+
+        let added = add_valid_qos(content, Some("teaching"), "gpu")
+            .unwrap()
+            .unwrap();
+        let added: MgmtConfig = toml::from_str(&added).unwrap();
+        assert_eq!(vec!["basic"], added.valid_qos);
+        assert_eq!(
+            vec!["interactive", "gpu"],
+            added.with_profile("teaching").unwrap().valid_qos
+        );
+
+        let added = add_valid_qos(content, Some("research"), "gpu")
+            .unwrap()
+            .unwrap();
+        let added: MgmtConfig = toml::from_str(&added).unwrap();
+        assert_eq!(vec!["basic", "gpu"], added.valid_qos);
+        assert_eq!(
+            vec!["interactive"],
+            added.with_profile("teaching").unwrap().valid_qos
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Version of the layout of the configuration file, migrations from older layouts
+//! and warnings about keys which are no fields of the configuration.
+//!
+//! Migrations work on the toml document itself.
+//! This way comments and formatting of a file are kept while it is upgraded.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use super::{profiles::PROFILES_FIELD, provenance};
+use crate::{prelude::*, slurm::AssociationLimits};
+
+/// Version of the layout of the configuration file understood by this application.
+/// A file without the field `config_version` has the version 0.
+pub const CONFIG_VERSION: u32 = 1;
+
+pub(super) const VERSION_FIELD: &str = "config_version";
+
+const LIMITS_FIELDS: [&str; 2] = ["student_limits", "staff_limits"];
+
+/// Migrates a table at the top level or of a profile to the next version.
+/// Every change is described in the given list. Its entries start with the given prefix.
+type Migration = fn(&mut dyn TableLike, &str, &mut Vec<String>);
+
+/// The migration at index i migrates the version i to the version i + 1.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [org_units_as_dn_parts];
+
+/// Content of a configuration file after all migrations to the current version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migrated {
+    /// Version of the file before the migrations.
+    pub from_version: u32,
+    /// Migrated content with the comments and formatting of the original content.
+    pub content: String,
+    /// Description of every change done by the migrations.
+    pub changes: Vec<String>,
+    pub unknown_keys: Vec<UnknownKey>,
+}
+
+impl Migrated {
+    /// True if the file was written for an older version and should be upgraded.
+    pub fn is_outdated(&self) -> bool {
+        self.from_version < CONFIG_VERSION
+    }
+}
+
+/// Key of a configuration file which is ignored since there is no field with this name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnknownKey {
+    /// Dotted path of the key, e.g. `profiles.teaching.head_nod`.
+    pub key: String,
+    /// Field with the most similar name if any is similar enough.
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown key {} is ignored.", self.key)?;
+        match &self.suggestion {
+            Some(suggestion) => write!(f, " Did you mean {}?", suggestion),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Migrates the content of the configuration file at `path` to the current version
+/// and finds all keys which are no fields of the configuration.
+///
+/// # Errors
+///
+/// - If the content is no valid toml.
+/// - If the field `config_version` is no number or is newer than [`CONFIG_VERSION`].
+pub fn migrate(content: &str, path: &Path) -> AppResult<Migrated> {
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("Configuration file at {:?} is no valid toml", path))?;
+    let from_version = version_of(&document)
+        .with_context(|| format!("Configuration file at {:?} has no valid version", path))?;
+    if from_version > CONFIG_VERSION {
+        bail!(
+            "Configuration file at {:?} has the version {}, but this application only understands \
+            versions up to {}. Please update this application",
+            path,
+            from_version,
+            CONFIG_VERSION
+        );
+    }
+
+    let mut changes = Vec::new();
+    for migration in &MIGRATIONS[from_version as usize..] {
+        migration(document.as_table_mut(), "", &mut changes);
+        if let Some(profiles) = document
+            .get_mut(PROFILES_FIELD)
+            .and_then(Item::as_table_like_mut)
+        {
+            for (name, profile) in profiles.iter_mut() {
+                if let Some(profile) = profile.as_table_like_mut() {
+                    let prefix = format!("{}.{}.", PROFILES_FIELD, name.get());
+                    migration(profile, &prefix, &mut changes);
+                }
+            }
+        }
+    }
+    if from_version < CONFIG_VERSION {
+        set_version(&mut document);
+        changes.push(format!(
+            "{}: {} => {}",
+            VERSION_FIELD, from_version, CONFIG_VERSION
+        ));
+    }
+
+    let unknown_keys = unknown_keys(&document);
+    Ok(Migrated {
+        from_version,
+        content: document.to_string(),
+        changes,
+        unknown_keys,
+    })
+}
+
+fn version_of(document: &DocumentMut) -> AppResult<u32> {
+    match document.get(VERSION_FIELD) {
+        None => Ok(0),
+        Some(item) => item
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Value ({}) of field {} is no version number",
+                    item.to_string().trim(),
+                    VERSION_FIELD
+                )
+            }),
+    }
+}
+
+fn set_version(document: &mut DocumentMut) {
+    let version = i64::from(CONFIG_VERSION);
+    match document.get_mut(VERSION_FIELD) {
+        Some(item) => replace_keeping_decor(item, version.into()),
+        None => {
+            document.insert(VERSION_FIELD, toml_edit::value(version));
+            if let Some(mut key) = document.key_mut(VERSION_FIELD) {
+                key.leaf_decor_mut().set_prefix(
+                    "# Version of the layout of this file. \
+                    Older files are upgraded by \"usermgmt config upgrade\".\n",
+                );
+            }
+        }
+    }
+}
+
+/// Replaces the value of `item` but keeps the comment behind the old value.
+fn replace_keeping_decor(item: &mut Item, mut new: Value) {
+    if let Some(old) = item.as_value() {
+        *new.decor_mut() = old.decor().clone();
+    }
+    *item = Item::Value(new);
+}
+
+/// Until version 0.5.0 the fields `ldap_org_unit` and `ldap_bind_org_unit` only contained
+/// the value of one ou part, like people for ou=people.
+/// Since then these fields contain whole dn parts.
+fn org_units_as_dn_parts(table: &mut dyn TableLike, prefix: &str, changes: &mut Vec<String>) {
+    for field in ["ldap_org_unit", "ldap_bind_org_unit"] {
+        if let Some(item) = table.get_mut(field) {
+            let old = match item.as_str() {
+                Some(old) if !old.trim().is_empty() && !old.contains('=') => old.to_owned(),
+                _ => continue,
+            };
+            let new = format!("ou={}", old);
+            changes.push(format!("{}{}: {} => {}", prefix, field, old, new));
+            replace_keeping_decor(item, new.into());
+        }
+    }
+}
+
+fn unknown_keys(document: &DocumentMut) -> Vec<UnknownKey> {
+    let mut unknown = Vec::new();
+    collect_unknown_keys(document.as_table(), "", &mut unknown);
+    if let Some(profiles) = document.get(PROFILES_FIELD).and_then(Item::as_table_like) {
+        for (name, profile) in profiles.iter() {
+            if let Some(profile) = profile.as_table_like() {
+                let prefix = format!("{}.{}.", PROFILES_FIELD, name);
+                collect_unknown_keys(profile, &prefix, &mut unknown);
+            }
+        }
+    }
+    unknown
+}
+
+fn collect_unknown_keys(table: &dyn TableLike, prefix: &str, unknown: &mut Vec<UnknownKey>) {
+    let fields = provenance::field_names();
+    for (key, item) in table.iter() {
+        if !fields.contains(&key) {
+            unknown.push(UnknownKey {
+                key: format!("{}{}", prefix, key),
+                suggestion: suggest(key, fields),
+            });
+        } else if let (true, Some(limits)) = (LIMITS_FIELDS.contains(&key), item.as_table_like()) {
+            let limit_fields = provenance::struct_field_names::<AssociationLimits>();
+            for (limit, _) in limits.iter() {
+                if !limit_fields.contains(&limit) {
+                    unknown.push(UnknownKey {
+                        key: format!("{}{}.{}", prefix, key, limit),
+                        suggestion: suggest(limit, limit_fields),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Name among `known` which is the most similar to `unknown`
+/// if it is similar enough to assume a typo.
+pub(super) fn suggest(unknown: &str, known: &[&str]) -> Option<String> {
+    let allowed_distance = (unknown.chars().count() / 3).max(2);
+    known
+        .iter()
+        .map(|name| (edit_distance(unknown, name), name))
+        .filter(|(distance, _)| *distance <= allowed_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name.to_string())
+}
+
+/// Levenshtein distance: minimal number of inserted, removed or replaced characters
+/// to turn `left` into `right`.
+fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (row, left_char) in left.chars().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, right_char) in right.iter().enumerate() {
+            let replaced = previous[column] + usize::from(left_char != *right_char);
+            let removed = previous[column + 1] + 1;
+            let inserted = current[column] + 1;
+            current.push(replaced.min(removed).min(inserted));
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const OLD_FILE: &str = r#"# Configuration of the cluster
+student_default_qos = "basic"
+# Only one ou part was allowed back then
+ldap_org_unit = "people" # users are here
+ldap_bind_org_unit = "ou=special"
+head_nod = "head"
+
+[student_limits]
+max_job = 10
+
+[profiles.teaching]
+ldap_bind_org_unit = "teachers"
+"#;
+
+    #[test]
+    fn migrate_old_file_and_keep_comments() {
+        let migrated = migrate(OLD_FILE, Path::new("conf.toml")).unwrap();
+
+        assert_eq!(0, migrated.from_version);
+        assert!(migrated.is_outdated());
+        insta::assert_snapshot!(migrated.content);
+        insta::assert_yaml_snapshot!(migrated.changes);
+    }
+
+    #[test]
+    fn suggest_fields_for_unknown_keys() {
+        let migrated = migrate(OLD_FILE, Path::new("conf.toml")).unwrap();
+        insta::assert_yaml_snapshot!(migrated.unknown_keys);
+        assert_eq!(None, suggest("completely_different", &["head_node"]));
+    }
+
+    #[test]
+    fn keep_current_file_unchanged() {
+        let content = super::super::config_for_save();
+        let migrated = migrate(&content, Path::new("conf.toml")).unwrap();
+
+        assert!(!migrated.is_outdated());
+        assert_eq!(content, migrated.content);
+        assert!(migrated.changes.is_empty());
+        assert!(migrated.unknown_keys.is_empty());
+    }
+
+    #[test]
+    fn error_for_newer_or_invalid_version() {
+        let newer = format!("{} = {}", VERSION_FIELD, CONFIG_VERSION + 1);
+        assert!(migrate(&newer, Path::new("conf.toml")).is_err());
+        let invalid = format!("{} = \"one\"", VERSION_FIELD);
+        assert!(migrate(&invalid, Path::new("conf.toml")).is_err());
+    }
+
+    #[test]
+    fn count_edits_between_names() {
+        assert_eq!(0, edit_distance("head_node", "head_node"));
+        assert_eq!(1, edit_distance("head_nod", "head_node"));
+        assert_eq!(2, edit_distance("ldap_sever", "ldap_server_"));
+        assert_eq!(3, edit_distance("", "abc"));
+    }
+}
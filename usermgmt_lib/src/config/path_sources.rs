@@ -9,6 +9,9 @@ use once_cell::sync::Lazy;
 
 use crate::prelude::*;
 
+/// Environment variable with the path of the configuration file or its folder.
+pub const CONFIG_PATH_ENV: &str = "USERMGMT_CONFIG";
+
 static HOME_LOCATIONS: Lazy<Vec<PathBuf>> = Lazy::new(|| {
     let os_config = dirs::config_dir();
     let mut paths: Vec<PathBuf> = Vec::with_capacity(2);
@@ -40,7 +43,7 @@ static SYSTEM_LOCATIONS: Lazy<Vec<PathBuf>> = Lazy::new(|| {
 ///
 /// - If no configuration file could be found anywhere.
 /// - If CWD can not be determined after the configuration file could not be found under the home and
-/// system paths.
+///   system paths.
 ///
 pub fn get_path_to_conf(manual_path: Option<PathBuf>) -> AppResult<PathBuf> {
     get_path_to_conf_with_dep(
@@ -52,6 +55,25 @@ pub fn get_path_to_conf(manual_path: Option<PathBuf>) -> AppResult<PathBuf> {
     )
 }
 
+/// Path of a configuration file which was given explicitly, e.g. via CLI.
+/// A folder is taken as the folder containing the file named `NAME_CONFIG_FILE`.
+/// Unlike [`get_path_to_conf`] no other location is tried.
+///
+/// # Errors
+///
+/// - If no configuration file exists at the given path.
+pub fn get_explicit_path_to_conf(path: &Path) -> AppResult<PathBuf> {
+    let file = if path.is_dir() {
+        path.join(constants::NAME_CONFIG_FILE)
+    } else {
+        path.to_path_buf()
+    };
+    if !io_try_exists(&file).unwrap_or(false) {
+        bail!("No configuration file exists at {:?}", file);
+    }
+    Ok(file)
+}
+
 fn get_path_to_conf_with_dep(
     manual_path: Option<PathBuf>,
     on_resolve_paths_with_home: impl Fn(Option<PathBuf>, &[PathBuf]) -> Vec<PathBuf>,
@@ -232,7 +254,7 @@ mod testing {
             None,
             |_, _| Vec::new(),
             |to_match| {
-                if to_match == &expected {
+                if to_match == expected {
                     Some(to_match.to_owned())
                 } else {
                     None
@@ -251,7 +273,7 @@ mod testing {
             None,
             |_, _| vec![expected.clone()],
             |to_match| {
-                if to_match == &expected {
+                if to_match == expected {
                     Some(to_match.to_owned())
                 } else {
                     None
@@ -0,0 +1,198 @@
+//! Named profiles within one configuration file, e.g. one per cluster.
+//!
+//! The fields at the top level of the configuration file are shared by all profiles.
+//! A profile in the table `profiles` overrides some of these shared fields.
+
+use anyhow::Context;
+use serde::Deserialize;
+use toml::Table;
+
+use super::{migration, provenance, MgmtConfig};
+use crate::prelude::*;
+
+/// Environment variable which selects a profile if no profile is given explicitly.
+pub const PROFILE_ENV: &str = "USERMGMT_PROFILE";
+
+impl MgmtConfig {
+    /// Names of all profiles in alphabetical order.
+    pub fn profile_names(&self) -> Vec<&str> {
+        self.profiles.keys().map(String::as_str).collect()
+    }
+
+    /// Shared fields overridden by the fields of the profile `name`.
+    /// The returned configuration still contains all profiles.
+    ///
+    /// # Errors
+    ///
+    /// - If there is no profile called `name`.
+    /// - If the profile sets a field which does not exist or whose value does not fit to its type.
+    pub fn with_profile(&self, name: &str) -> AppResult<MgmtConfig> {
+        let overrides = self.profile(name)?;
+        let mut merged = provenance::to_table(self)?;
+        for (field, value) in overrides {
+            merged.insert(field.clone(), value.clone());
+        }
+        MgmtConfig::deserialize(merged).with_context(|| format!("Profile {} is not valid", name))
+    }
+
+    /// Stores every field of `edited` which differs from the shared fields as override
+    /// in the profile `name`. Overrides of fields which equal the shared fields are removed.
+    ///
+    /// # Errors
+    ///
+    /// - If the configurations could not be turned into the toml format.
+    pub fn store_as_profile(&mut self, name: &str, edited: &MgmtConfig) -> AppResult {
+        let shared = provenance::to_table(self)?;
+        let edited = provenance::to_table(edited)?;
+        let overrides: Table = edited
+            .into_iter()
+            .filter(|(field, value)| field != PROFILES_FIELD && shared.get(field) != Some(value))
+            .collect();
+        self.profiles.insert(name.to_owned(), overrides);
+        Ok(())
+    }
+
+    fn profile(&self, name: &str) -> AppResult<&Table> {
+        let overrides = self.profiles.get(name).ok_or_else(|| {
+            anyhow!(
+                "Profile {} is not defined in the configuration. Defined profiles: {}",
+                name,
+                self.profile_names().join(", ")
+            )
+        })?;
+        let fields = provenance::field_names();
+        if let Some(unknown) = overrides
+            .keys()
+            .find(|field| *field == PROFILES_FIELD || !fields.contains(&field.as_str()))
+        {
+            let suggestion = migration::suggest(unknown, fields)
+                .map(|field| format!(". Did you mean {}?", field))
+                .unwrap_or_default();
+            bail!(
+                "Profile {} sets the unknown field {}{}",
+                name,
+                unknown,
+                suggestion
+            );
+        }
+        Ok(overrides)
+    }
+}
+
+/// Field with the profiles which is neither overridden by a profile nor by environment variables.
+pub(super) const PROFILES_FIELD: &str = "profiles";
+
+/// Applies the profile `name` if given to the shared fields and returns the fields
+/// set by the profile.
+///
+/// # Errors
+///
+/// See [`MgmtConfig::with_profile`].
+pub fn apply_profile(
+    config: MgmtConfig,
+    name: Option<&str>,
+) -> AppResult<(MgmtConfig, Vec<String>)> {
+    match name {
+        None => Ok((config, Vec::new())),
+        Some(name) => {
+            let set_fields = config.profile(name)?.keys().cloned().collect();
+            Ok((config.with_profile(name)?, set_fields))
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    const PROFILES: &str = r#"
+[profiles.teaching]
+ldap_org_unit = "teaching"
+head_node = "teaching-head"
+valid_qos = ["basic"]
+
+[profiles.research]
+head_node = "research-head"
+"#;
+
+    fn content_with_profiles() -> String {
+        format!(
+            "{}\n{}",
+            super::super::config_for_save().replace("head_node = \"\"", "head_node = \"shared\""),
+            PROFILES
+        )
+    }
+
+    fn with_profiles() -> MgmtConfig {
+        toml::from_str(&content_with_profiles()).unwrap()
+    }
+
+    #[test]
+    fn override_shared_fields_by_profile() {
+        let config = with_profiles();
+        assert_eq!(vec!["research", "teaching"], config.profile_names());
+
+        let teaching = config.with_profile("teaching").unwrap();
+        assert_eq!("teaching-head", teaching.head_node);
+        assert_eq!(Some("teaching".to_owned()), teaching.ldap_org_unit);
+        assert_eq!(vec!["basic"], teaching.valid_qos);
+        assert_eq!(config.student_qos, teaching.student_qos);
+
+        let research = config.with_profile("research").unwrap();
+        assert_eq!("research-head", research.head_node);
+        assert_eq!(config.valid_qos, research.valid_qos);
+    }
+
+    #[test]
+    fn apply_profile_before_env_overrides() {
+        let (config, entries) = provenance::read_with_overrides(
+            &content_with_profiles(),
+            std::path::Path::new("/etc/conf.toml"),
+            Some("teaching"),
+            |key| (key == "USERMGMT_HEAD_NODE").then(|| "env-head".to_owned()),
+        )
+        .unwrap();
+
+        assert_eq!("env-head", config.head_node);
+        let source_of = |field: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.field == field)
+                .map(|entry| entry.source.to_string())
+                .unwrap()
+        };
+        assert_eq!("env USERMGMT_HEAD_NODE", source_of("head_node"));
+        assert_eq!("profile teaching", source_of("ldap_org_unit"));
+        assert_eq!("file /etc/conf.toml", source_of("student_qos"));
+    }
+
+    #[test]
+    fn error_for_unknown_profile_or_field() {
+        let mut config = with_profiles();
+        let error = config.with_profile("other").unwrap_err().to_string();
+        assert!(error.contains("research, teaching"));
+
+        config
+            .profiles
+            .get_mut("research")
+            .unwrap()
+            .insert("head_nod".to_owned(), "typo".into());
+        let error = config.with_profile("research").unwrap_err().to_string();
+        assert!(error.ends_with("Did you mean head_node?"));
+    }
+
+    #[test]
+    fn store_only_differing_fields_in_profile() {
+        let mut config = with_profiles();
+        let mut edited = config.with_profile("teaching").unwrap();
+        edited.ldap_org_unit = None;
+        edited.ssh_port = 2222;
+
+        config.store_as_profile("teaching", &edited).unwrap();
+
+        insta::assert_snapshot!(toml::to_string(&config.profiles["teaching"]).unwrap());
+        assert_eq!(2222, config.with_profile("teaching").unwrap().ssh_port);
+        let saved: MgmtConfig = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(config.profiles, saved.profiles);
+    }
+}
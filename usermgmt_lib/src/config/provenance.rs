@@ -0,0 +1,285 @@
+//! Overrides of configuration fields by environment variables and the source of every field.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde::{
+    de::{self, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+use toml::{Table, Value};
+
+use super::{profiles, MgmtConfig};
+use crate::{output::TableRow, prelude::AppResult};
+
+/// Prefix of environment variables which override a field of the configuration,
+/// e.g. `USERMGMT_LDAP_SERVER` for the field `ldap_server`.
+pub const ENV_OVERRIDE_PREFIX: &str = "USERMGMT_";
+
+/// Where the effective value of a configuration field comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueSource {
+    Default,
+    File(PathBuf),
+    Profile(String),
+    Env(String),
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Profile(name) => write!(f, "profile {}", name),
+            Self::Env(variable) => write!(f, "env {}", variable),
+        }
+    }
+}
+
+/// Effective value of a configuration field together with its source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigEntry {
+    pub field: String,
+    /// Value in the toml format. Empty if the field is not set. Secrets are masked.
+    pub value: String,
+    pub source: ValueSource,
+}
+
+impl TableRow for ConfigEntry {
+    fn headers() -> Vec<&'static str> {
+        vec!["Field", "Value", "Source"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.field.clone(),
+            self.value.clone(),
+            self.source.to_string(),
+        ]
+    }
+}
+
+/// Names of all fields of [`MgmtConfig`] in the order of their declaration.
+pub fn field_names() -> &'static [&'static str] {
+    struct_field_names::<MgmtConfig>()
+}
+
+/// Names of all fields of the struct `T` in the order of their declaration.
+pub fn struct_field_names<'de, T>() -> &'static [&'static str]
+where
+    T: Deserialize<'de>,
+{
+    let mut fields: &'static [&'static str] = &[];
+    let _captured_by_error = T::deserialize(StructFields(&mut fields));
+    fields
+}
+
+/// Name of the environment variable which overrides the given field.
+pub fn env_variable_of(field: &str) -> String {
+    format!("{}{}", ENV_OVERRIDE_PREFIX, field.to_uppercase())
+}
+
+/// Reads the configuration from the content of a configuration file at `path`,
+/// applies the given profile and overrides fields by the environment variables returned from `env`.
+/// Returns the configuration and every field with its effective value and source.
+///
+/// A value of an environment variable is taken as toml value, as text or as comma separated list,
+/// whatever fits to the type of the field.
+///
+/// # Errors
+///
+/// - If the content is no valid configuration.
+/// - If the profile is not defined or not valid. See [`MgmtConfig::with_profile`].
+/// - If the value of an environment variable does not fit to the type of its field.
+pub fn read_with_overrides(
+    content: &str,
+    path: &Path,
+    profile: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+) -> AppResult<(MgmtConfig, Vec<ConfigEntry>)> {
+    let in_file: Table = toml::from_str(content)
+        .with_context(|| format!("Configuration file at {:?} is no valid toml", path))?;
+    let from_file: MgmtConfig = toml::from_str(content)
+        .with_context(|| format!("Configuration file at {:?} is not valid", path))?;
+    let (from_profile, set_by_profile) = profiles::apply_profile(from_file, profile)?;
+    let mut merged = to_table(&from_profile)?;
+
+    let mut sources = Vec::new();
+    for &field in field_names()
+        .iter()
+        .filter(|&&field| field != profiles::PROFILES_FIELD)
+    {
+        let variable = env_variable_of(field);
+        let source = match (env(&variable), profile) {
+            (Some(raw), _) => {
+                override_field(&mut merged, field, &raw)
+                    .with_context(|| format!("Environment variable {} is not valid", variable))?;
+                ValueSource::Env(variable)
+            }
+            (None, Some(name)) if set_by_profile.iter().any(|set| set == field) => {
+                ValueSource::Profile(name.to_owned())
+            }
+            (None, _) if in_file.contains_key(field) => ValueSource::File(path.to_path_buf()),
+            (None, _) => ValueSource::Default,
+        };
+        sources.push((field, source));
+    }
+
+    let config = MgmtConfig::deserialize(merged.clone())
+        .context("Configuration with overrides of environment variables is not valid")?;
+    let entries = sources
+        .into_iter()
+        .map(|(field, source)| ConfigEntry {
+            field: field.to_owned(),
+            value: shown_value(field, merged.get(field)),
+            source,
+        })
+        .collect();
+    Ok((config, entries))
+}
+
+pub(super) fn to_table(config: &MgmtConfig) -> AppResult<Table> {
+    match Value::try_from(config).context("Could not turn configuration into toml")? {
+        Value::Table(table) => Ok(table),
+        _ => unreachable!("A struct is always turned into a toml table"),
+    }
+}
+
+/// Sets the first interpretation of `raw` which makes the configuration valid.
+fn override_field(merged: &mut Table, field: &str, raw: &str) -> AppResult {
+    let as_toml = toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"));
+    let as_list = Value::Array(
+        raw.split(',')
+            .map(|item| Value::String(item.trim().to_owned()))
+            .collect(),
+    );
+    let candidates = as_toml
+        .into_iter()
+        .chain([Value::String(raw.to_owned()), as_list]);
+    for candidate in candidates {
+        let mut tried = merged.clone();
+        tried.insert(field.to_owned(), candidate);
+        if MgmtConfig::deserialize(tried.clone()).is_ok() {
+            *merged = tried;
+            return Ok(());
+        }
+    }
+    Err(anyhow!(
+        "Value ({}) does not fit to the type of field {}",
+        raw,
+        field
+    ))
+}
+
+fn shown_value(field: &str, value: Option<&Value>) -> String {
+    const SECRETS: [&str; 3] = ["_pw", "password", "token"];
+    match value {
+        None => String::new(),
+        Some(_) if SECRETS.iter().any(|secret| field.contains(secret)) => "***".to_owned(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Deserializer which only captures the names of the fields of a struct.
+struct StructFields<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for StructFields<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom("Only the fields of structs are captured"))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        *self.0 = fields;
+        Err(de::Error::custom("Fields are captured"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn capture_all_field_names() {
+        let fields = field_names();
+        assert_eq!(Some(&"student_default_qos"), fields.first());
+        assert!(fields.contains(&"ldap_readonly_pw"));
+        assert!(fields.contains(&"ldap_page_size"));
+    }
+
+    #[test]
+    fn override_fields_by_env_and_track_sources() {
+        let content = super::super::config_for_save().replace(
+            "ldap_server = \"ldap://localhost:389\"",
+            "ldap_server = \"ldap://file:389\"",
+        );
+        let env: HashMap<String, String> = [
+            ("USERMGMT_LDAP_SERVER", "ldap://env:389"),
+            ("USERMGMT_SSH_PORT", "2222"),
+            ("USERMGMT_INCLUDE_SLURM", "false"),
+            ("USERMGMT_VALID_QOS", "basic, gpu"),
+            ("USERMGMT_LDAP_READONLY_PW", "1234"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+
+        let (config, entries) =
+            read_with_overrides(&content, Path::new("/etc/conf.toml"), None, |key| {
+                env.get(key).cloned()
+            })
+            .unwrap();
+
+        assert_eq!("ldap://env:389", config.ldap_server);
+        assert_eq!(2222, config.ssh_port);
+        assert!(!config.include_slurm);
+        assert_eq!(vec!["basic", "gpu"], config.valid_qos);
+        assert_eq!(Some("1234".to_owned()), config.ldap_readonly_pw);
+        let shown: Vec<&ConfigEntry> = entries
+            .iter()
+            .filter(|entry| {
+                [
+                    "ldap_server",
+                    "ldap_readonly_pw",
+                    "head_node",
+                    "username_regex",
+                ]
+                .contains(&entry.field.as_str())
+            })
+            .collect();
+        insta::assert_yaml_snapshot!(shown);
+    }
+
+    #[test]
+    fn error_for_env_value_not_fitting_to_field() {
+        let content = super::super::config_for_save();
+        let actual = read_with_overrides(&content, Path::new("conf.toml"), None, |key| {
+            (key == "USERMGMT_SSH_PORT").then(|| "twenty-two".to_owned())
+        });
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Checks of the semantic consistency of a configuration, beyond the types of its fields.
+
+use serde::{Deserialize, Serialize};
+
+use super::MgmtConfig;
+use crate::output::TableRow;
+
+const LDAP_SCHEMES: [&str; 3] = ["ldap", "ldaps", "ldapi"];
+
+/// Problem found in a configuration together with the field it is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigProblem {
+    pub field: String,
+    pub problem: String,
+}
+
+impl ConfigProblem {
+    fn new(field: &str, problem: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            problem: problem.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.problem)
+    }
+}
+
+impl TableRow for ConfigProblem {
+    fn headers() -> Vec<&'static str> {
+        vec!["Field", "Problem"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.field.clone(), self.problem.clone()]
+    }
+}
+
+impl MgmtConfig {
+    /// Finds all inconsistencies within this configuration.
+    /// An empty list means the configuration is valid.
+    pub fn validate(&self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+        self.validate_qos(&mut problems);
+        self.validate_gids(&mut problems);
+        self.validate_ldap_server(&mut problems);
+        self.validate_quotas(&mut problems);
+        self.validate_slurm(&mut problems);
+        self.validate_directories(&mut problems);
+        problems
+    }
+
+    fn validate_qos(&self, problems: &mut Vec<ConfigProblem>) {
+        let groups = [
+            (
+                "student_default_qos",
+                &self.student_default_qos,
+                "student_qos",
+                &self.student_qos,
+            ),
+            (
+                "staff_default_qos",
+                &self.staff_default_qos,
+                "staff_qos",
+                &self.staff_qos,
+            ),
+        ];
+        for (default_field, default_qos, qos_field, qos) in groups {
+            if !qos.contains(default_qos) {
+                problems.push(ConfigProblem::new(
+                    default_field,
+                    format!("Default QOS ({}) is not in {}", default_qos, qos_field),
+                ));
+            }
+            if !self.valid_qos.contains(default_qos) {
+                problems.push(ConfigProblem::new(
+                    default_field,
+                    format!("Default QOS ({}) is not in valid_qos", default_qos),
+                ));
+            }
+            for invalid in qos.iter().filter(|next| !self.valid_qos.contains(next)) {
+                problems.push(ConfigProblem::new(
+                    qos_field,
+                    format!("QOS ({}) is not in valid_qos", invalid),
+                ));
+            }
+        }
+    }
+
+    fn validate_gids(&self, problems: &mut Vec<ConfigProblem>) {
+        let gids = [
+            ("student_gid", self.student_gid),
+            ("staff_gid", self.staff_gid),
+            ("faculty_gid", self.faculty_gid),
+        ];
+        for (index, (field, gid)) in gids.iter().enumerate() {
+            if let Some((same_field, _)) = gids[..index].iter().find(|(_, other)| other == gid) {
+                problems.push(ConfigProblem::new(
+                    field,
+                    format!("Gid ({}) is the same as of {}", gid, same_field),
+                ));
+            }
+        }
+    }
+
+    fn validate_ldap_server(&self, problems: &mut Vec<ConfigProblem>) {
+        if !self.include_ldap {
+            return;
+        }
+        match url::Url::parse(&self.ldap_server) {
+            Err(error) => problems.push(ConfigProblem::new(
+                "ldap_server",
+                format!("Not a valid URL ({}): {}", self.ldap_server, error),
+            )),
+            Ok(url) if !LDAP_SCHEMES.contains(&url.scheme()) => problems.push(ConfigProblem::new(
+                "ldap_server",
+                format!(
+                    "Scheme ({}) is none of {}",
+                    url.scheme(),
+                    LDAP_SCHEMES.join(", ")
+                ),
+            )),
+            Ok(_) => (),
+        }
+    }
+
+    fn validate_quotas(&self, problems: &mut Vec<ConfigProblem>) {
+        let limits = [
+            (
+                "quota_softlimit",
+                &self.quota_softlimit,
+                "quota_hardlimit",
+                &self.quota_hardlimit,
+            ),
+            (
+                "quota_nfs_softlimit",
+                &self.quota_nfs_softlimit,
+                "quota_nfs_hardlimit",
+                &self.quota_nfs_hardlimit,
+            ),
+            (
+                "quota_home_softlimit",
+                &self.quota_home_softlimit,
+                "quota_home_hardlimit",
+                &self.quota_home_hardlimit,
+            ),
+        ];
+        for (soft_field, soft, hard_field, hard) in limits {
+            // An empty limit is allowed and means that no quota is set.
+            let mut parse = |field: &str, limit: &str| {
+                if limit.is_empty() {
+                    return None;
+                }
+                let parsed = parse_quota_size(limit);
+                if parsed.is_none() {
+                    problems.push(ConfigProblem::new(
+                        field,
+                        format!(
+                            "Limit ({}) is no size like 200G. Allowed units are K, M, G and T",
+                            limit
+                        ),
+                    ));
+                }
+                parsed
+            };
+            let (soft_size, hard_size) = (parse(soft_field, soft), parse(hard_field, hard));
+            if let (Some(soft_size), Some(hard_size)) = (soft_size, hard_size) {
+                if soft_size > hard_size {
+                    problems.push(ConfigProblem::new(
+                        soft_field,
+                        format!(
+                            "Soft limit ({}) is greater than the hard limit ({}) of {}",
+                            soft, hard, hard_field
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_slurm(&self, problems: &mut Vec<ConfigProblem>) {
+        if !self.include_slurm || self.use_slurmrestd {
+            return;
+        }
+        if self.run_slurm_remote {
+            if self.head_node.trim().is_empty() {
+                problems.push(ConfigProblem::new(
+                    "head_node",
+                    "Must be set if run_slurm_remote is true",
+                ));
+            }
+        } else if self.sacctmgr_path.trim().is_empty() {
+            problems.push(ConfigProblem::new(
+                "sacctmgr_path",
+                "Must be set if run_slurm_remote is false",
+            ));
+        }
+    }
+
+    fn validate_directories(&self, problems: &mut Vec<ConfigProblem>) {
+        if !self.include_dir_mgmt {
+            return;
+        }
+        if self.compute_nodes.is_empty() {
+            problems.push(ConfigProblem::new(
+                "compute_nodes",
+                "Must not be empty if include_dir_mgmt is true",
+            ));
+        }
+        let required = [
+            ("compute_node_root_dir", &self.compute_node_root_dir),
+            ("filesystem", &self.filesystem),
+            ("nfs_host", &self.nfs_host),
+            ("nfs_root_dir", &self.nfs_root_dir),
+            ("nfs_filesystem", &self.nfs_filesystem),
+            ("home_host", &self.home_host),
+            ("home_filesystem", &self.home_filesystem),
+        ];
+        for (field, _) in required
+            .into_iter()
+            .filter(|(_, value)| value.trim().is_empty())
+        {
+            problems.push(ConfigProblem::new(
+                field,
+                "Must be set if include_dir_mgmt is true",
+            ));
+        }
+    }
+}
+
+/// Size of a quota limit in KiB as understood by setquota,
+/// e.g. 200G. A number without unit is taken as KiB.
+fn parse_quota_size(limit: &str) -> Option<u64> {
+    let limit = limit.trim();
+    let (number, factor) = match limit.char_indices().last()? {
+        (index, 'K' | 'k') => (&limit[..index], 1),
+        (index, 'M' | 'm') => (&limit[..index], 1 << 10),
+        (index, 'G' | 'g') => (&limit[..index], 1 << 20),
+        (index, 'T' | 't') => (&limit[..index], 1 << 30),
+        _ => (limit, 1),
+    };
+    if !number.chars().all(|digit| digit.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(factor)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(
+            Vec::<ConfigProblem>::new(),
+            MgmtConfig::default().validate()
+        );
+    }
+
+    #[test]
+    fn parse_quota_sizes() {
+        assert_eq!(Some(200 << 20), parse_quota_size("200G"));
+        assert_eq!(Some(3 << 30), parse_quota_size("3T"));
+        assert_eq!(Some(512), parse_quota_size("512"));
+        assert_eq!(None, parse_quota_size("2GB"));
+        assert_eq!(None, parse_quota_size("-2G"));
+        assert_eq!(None, parse_quota_size("G"));
+    }
+
+    #[test]
+    fn report_all_problems_with_their_fields() {
+        let config = MgmtConfig {
+            student_default_qos: "gpu".to_owned(),
+            staff_qos: vec!["advanced".to_owned(), "unknown".to_owned()],
+            faculty_gid: 1001,
+            ldap_server: "localhost:389".to_owned(),
+            quota_softlimit: "300G".to_owned(),
+            quota_nfs_hardlimit: "lots".to_owned(),
+            sacctmgr_path: "".to_owned(),
+            include_dir_mgmt: true,
+            compute_nodes: Vec::new(),
+            nfs_root_dir: "/nfs".to_owned(),
+            compute_node_root_dir: "/mnt/md0".to_owned(),
+            filesystem: "/mnt/md0".to_owned(),
+            nfs_filesystem: "/nfs".to_owned(),
+            ..Default::default()
+        };
+
+        insta::assert_yaml_snapshot!(config.validate());
+    }
+}
@@ -1,12 +1,20 @@
 use crate::util::ResultAccumulator;
 /// Module for directory management
 use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
 
 use crate::config::MgmtConfig;
+use crate::executor::{CommandExecutor, ExecCommand};
+use crate::output::TableRow;
 use crate::prelude::AppResult;
-use crate::ssh::{self, SshConnection, SshCredentials};
+use crate::ssh::{SshConnection, SshCredentials};
 use crate::{Group, NewEntity};
 
+/// Creates the directories of a new user on all configured hosts over ssh.
+///
+/// # Errors
+///
+/// See [`add_user_directories_with`]
 pub fn add_user_directories<T>(
     entity: &NewEntity,
     config: &MgmtConfig,
@@ -15,19 +23,43 @@ pub fn add_user_directories<T>(
 where
     T: SshCredentials,
 {
-    handle_compute_nodes(entity, config, credentials)?;
+    add_user_directories_with(entity, config, |host| {
+        SshConnection::new(host, config, credentials.clone())
+    })
+}
+
+/// Creates the directories of a new user on the compute nodes, the NFS host and the home host.
+/// The parameter `on_host` returns the executor which runs the commands on the given host.
+///
+/// # Errors
+///
+/// - If a command could not be executed on a host.
+/// - If a command on a host returned with a non-zero exit code.
+pub fn add_user_directories_with<'a, E>(
+    entity: &NewEntity,
+    config: &'a MgmtConfig,
+    on_host: impl Fn(&'a str) -> E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    handle_compute_nodes(entity, config, &on_host)?;
 
-    handle_nfs(entity, config, credentials)?;
+    handle_nfs(entity, config, &on_host)?;
 
-    handle_home(entity, config, credentials)?;
+    handle_home(entity, config, &on_host)?;
 
     Ok(())
 }
 
 /// Establish SSH connection to each compute node, make user directory and set quota
-fn handle_compute_nodes<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+fn handle_compute_nodes<'a, E>(
+    entity: &NewEntity,
+    config: &'a MgmtConfig,
+    on_host: impl Fn(&'a str) -> E,
+) -> AppResult
 where
-    T: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Start handling directories on compute nodes");
 
@@ -59,9 +91,9 @@ where
     let mut quota_exit_codes = Vec::new();
     for server in config.compute_nodes.iter() {
         info!("Connecting to a compute node");
-        let sess = SshConnection::new(server, config, credentials.clone());
+        let sess = on_host(server);
         // Create directory
-        let directory = format!("{}/{}", config.compute_node_root_dir, entity.username);
+        let directory = compute_node_directory(config, entity.username.as_ref());
         let (dir_exit_code, _) = make_directory(&sess, &directory)?;
         mkdir_exit_codes.push(dir_exit_code);
 
@@ -124,9 +156,13 @@ where
 }
 
 /// Establish SSH connection to NFS host, make user directory and set quota
-fn handle_nfs<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+fn handle_nfs<'a, E>(
+    entity: &NewEntity,
+    config: &'a MgmtConfig,
+    on_host: impl Fn(&'a str) -> E,
+) -> AppResult
 where
-    T: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Start handling NFS user directory");
 
@@ -149,14 +185,10 @@ where
     }
 
     info!("Connecting to NFS host");
-    let sess = SshConnection::new(&config.nfs_host, config, credentials.clone());
+    let sess = on_host(&config.nfs_host);
 
     // Create directory
-    let mut group_dir = "staff";
-    if entity.group.id() == Group::Student {
-        group_dir = "students"
-    }
-    let directory = format!("{}/{}/{}", config.nfs_root_dir, group_dir, entity.username);
+    let directory = nfs_directory(config, entity.username.as_ref(), entity.group.id());
     let (dir_exit_code, _) = make_directory(&sess, &directory)?;
 
     let mut detected_errors =
@@ -205,9 +237,13 @@ where
 }
 
 /// Establish SSH connection to home host, make user directory and set quota
-fn handle_home<T>(entity: &NewEntity, config: &MgmtConfig, credentials: &T) -> AppResult
+fn handle_home<'a, E>(
+    entity: &NewEntity,
+    config: &'a MgmtConfig,
+    on_host: impl Fn(&'a str) -> E,
+) -> AppResult
 where
-    T: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Start handling home directory");
 
@@ -226,10 +262,10 @@ where
     }
 
     info!("Connecting to home host");
-    let sess = SshConnection::new(&config.home_host, config, credentials.clone());
+    let sess = on_host(&config.home_host);
 
     // Create directory
-    let directory = format!("/home/{}", entity.username);
+    let directory = home_directory(entity.username.as_ref());
 
     let (dir_exit_code, _) = if config.use_homedir_helper {
         make_home_directory(&sess, entity.username.as_ref())
@@ -271,7 +307,7 @@ where
             &config.home_filesystem,
         )?;
         detected_errors.add_err_if_false(
-            quota_exit_code != 0,
+            quota_exit_code == 0,
             "Home host did not return with exit code 0 during quota setup!".to_owned(),
         );
     }
@@ -281,57 +317,323 @@ where
     Ok(())
 }
 
-fn make_directory<C>(sess: &SshConnection<C>, directory: &str) -> AppResult<(i32, String)>
+/// State of a directory of an user on a host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryState {
+    pub host: String,
+    pub path: String,
+    pub exists: bool,
+    /// Owner and group like `somebody:staff`. `None` if the directory does not exist.
+    pub owner: Option<String>,
+    /// Line of quota for the configured filesystem with usage and limits.
+    /// `None` if no filesystem is configured or quota reports nothing for it.
+    pub quota: Option<String>,
+}
+
+impl TableRow for DirectoryState {
+    fn headers() -> Vec<&'static str> {
+        vec!["Host", "Path", "Exists", "Owner", "Quota"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.host.clone(),
+            self.path.clone(),
+            self.exists.to_string(),
+            self.owner.clone().unwrap_or_default(),
+            self.quota.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Inspects the directories of an user at the places where they are created while adding.
+/// The NFS directory is only inspected if the group of the user is known.
+/// Nothing is changed on the hosts.
+/// The parameter `on_host` returns the executor which runs the commands on the given host.
+///
+/// # Errors
+///
+/// - If a command could not be executed on a host.
+pub fn inspect_user_directories_with<'a, E>(
+    username: &str,
+    group: Option<Group>,
+    config: &'a MgmtConfig,
+    on_host: impl Fn(&'a str) -> E,
+) -> AppResult<Vec<DirectoryState>>
+where
+    E: CommandExecutor,
+{
+    let mut states = Vec::new();
+    if !config.compute_node_root_dir.is_empty() {
+        for server in config.compute_nodes.iter() {
+            states.push(inspect_directory(
+                &on_host(server),
+                server,
+                &compute_node_directory(config, username),
+                username,
+                &config.filesystem,
+            )?);
+        }
+    }
+    match group {
+        Some(group) if !config.nfs_host.is_empty() && !config.nfs_root_dir.is_empty() => {
+            states.push(inspect_directory(
+                &on_host(&config.nfs_host),
+                &config.nfs_host,
+                &nfs_directory(config, username, group),
+                username,
+                &config.nfs_filesystem,
+            )?);
+        }
+        None if !config.nfs_host.is_empty() => {
+            warn!(
+                "Group of user {} is unknown. NFS directory is not inspected.",
+                username
+            )
+        }
+        _ => (),
+    }
+    if !config.home_host.is_empty() {
+        states.push(inspect_directory(
+            &on_host(&config.home_host),
+            &config.home_host,
+            &home_directory(username),
+            username,
+            &config.home_filesystem,
+        )?);
+    }
+    Ok(states)
+}
+
+fn inspect_directory<E>(
+    sess: &E,
+    host: &str,
+    directory: &str,
+    username: &str,
+    filesystem: &str,
+) -> AppResult<DirectoryState>
+where
+    E: CommandExecutor,
+{
+    debug!("Inspecting directory {} on {}", directory, host);
+
+    let cmd = ExecCommand::new("sudo", ["stat", "-c", "%U:%G", directory]);
+    let (stat_exit_code, owner) = run_dir_command(sess, &cmd)?;
+    let exists = stat_exit_code == 0;
+
+    let quota = if filesystem.is_empty() {
+        None
+    } else {
+        let cmd = ExecCommand::new("sudo", ["quota", "-w", "-u", username]);
+        let (_, output) = run_dir_command(sess, &cmd)?;
+        output
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+            .find(|cells| cells.first() == Some(&filesystem))
+            .map(|cells| cells.join(" "))
+    };
+
+    Ok(DirectoryState {
+        host: host.to_owned(),
+        path: directory.to_owned(),
+        exists,
+        owner: exists.then(|| owner.trim().to_owned()),
+        quota,
+    })
+}
+
+fn compute_node_directory(config: &MgmtConfig, username: &str) -> String {
+    format!("{}/{}", config.compute_node_root_dir, username)
+}
+
+fn nfs_directory(config: &MgmtConfig, username: &str, group: Group) -> String {
+    let group_dir = if group == Group::Student {
+        "students"
+    } else {
+        "staff"
+    };
+    format!("{}/{}/{}", config.nfs_root_dir, group_dir, username)
+}
+
+fn home_directory(username: &str) -> String {
+    format!("/home/{}", username)
+}
+
+fn make_directory<E>(sess: &E, directory: &str) -> AppResult<(i32, String)>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Making directory {}", directory);
 
-    let cmd = format!("sudo mkdir -p {directory}");
-    ssh::run_remote_command(sess, &cmd)
+    let cmd = ExecCommand::new("sudo", ["mkdir", "-p", directory]);
+    run_dir_command(sess, &cmd)
 }
 
-fn make_home_directory<C>(sess: &SshConnection<C>, username: &str) -> AppResult<(i32, String)>
+fn make_home_directory<E>(sess: &E, username: &str) -> AppResult<(i32, String)>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Making home directory using the mkhomedir_helper");
 
-    let cmd = format!("sudo mkhomedir_helper {username}");
-    ssh::run_remote_command(sess, &cmd)
+    let cmd = ExecCommand::new("sudo", ["mkhomedir_helper", username]);
+    run_dir_command(sess, &cmd)
 }
 
-fn change_ownership<C>(
-    sess: &SshConnection<C>,
+fn change_ownership<E>(
+    sess: &E,
     directory: &str,
     username: &str,
     group: &str,
 ) -> AppResult<(i32, String)>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     debug!("Changing ownership for directory {}", directory);
 
-    let cmd = format!("sudo chown {username}:{group} {directory}");
-    ssh::run_remote_command(sess, &cmd)
+    let owner = format!("{username}:{group}");
+    let cmd = ExecCommand::new("sudo", ["chown", owner.as_str(), directory]);
+    run_dir_command(sess, &cmd)
 }
 
-fn set_quota<C>(
-    sess: &SshConnection<C>,
+fn set_quota<E>(
+    sess: &E,
     username: &str,
     softlimit: &str,
     hardlimit: &str,
     filesystem: &str,
 ) -> AppResult<(i32, String)>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     debug!(
         "Setting quota for user {} on filesystem {}",
         username, filesystem
     );
 
-    let cmd = format!("sudo setquota -u {username} {softlimit} {hardlimit} 0 0 {filesystem}");
+    let cmd = ExecCommand::new(
+        "sudo",
+        [
+            "setquota", "-u", username, softlimit, hardlimit, "0", "0", filesystem,
+        ],
+    );
+
+    run_dir_command(sess, &cmd)
+}
+
+/// Runs the command and returns its exit code with its stdout.
+fn run_dir_command<E>(sess: &E, cmd: &ExecCommand) -> AppResult<(i32, String)>
+where
+    E: CommandExecutor,
+{
+    let output = sess.run(cmd)?;
+    debug!("command exit status: {}", output.exit_code());
+    Ok((output.exit_code(), output.into_stdout()))
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::executor::{CommandOutput, RecordingExecutor};
+    use crate::util::{ResolvedGid, ValidGroupOfQos, ValidQos};
+
+    fn new_entity(config: &MgmtConfig) -> NewEntity {
+        let group = ResolvedGid::new(Group::Student, config);
+        NewEntity {
+            username: "somebody".try_into().unwrap(),
+            firstname: "Some".try_into().unwrap(),
+            lastname: "Body".try_into().unwrap(),
+            mail: None,
+            default_qos: ValidQos::default_qos_from_conf(group.id(), config),
+            qos: ValidGroupOfQos::from_group(group.id(), config).unwrap(),
+            publickey: None,
+            accounts: vec![group.id().to_string()],
+            default_account: None,
+            limits: Default::default(),
+            clusters: Default::default(),
+            coordinator: false,
+            group,
+        }
+    }
+
+    fn config_with_dirs() -> MgmtConfig {
+        MgmtConfig {
+            compute_nodes: vec!["node1".to_owned(), "node2".to_owned()],
+            compute_node_root_dir: "/mnt/md0/user".to_owned(),
+            filesystem: "/mnt/md0".to_owned(),
+            nfs_host: "nfs".to_owned(),
+            nfs_root_dir: "/mnt/md0/scratch".to_owned(),
+            nfs_filesystem: "/dev/sda1".to_owned(),
+            home_host: "home".to_owned(),
+            home_filesystem: "/dev/sdb4".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn add_directories_on_all_hosts() {
+        let config = config_with_dirs();
+        let entity = new_entity(&config);
+        let recorder = RecordingExecutor::new();
+
+        add_user_directories_with(&entity, &config, |host| recorder.for_host(host)).unwrap();
+
+        insta::assert_yaml_snapshot!(recorder.recorded_lines());
+    }
+
+    #[test]
+    fn report_error_for_failed_mkdir_on_compute_node() {
+        let config = config_with_dirs();
+        let entity = new_entity(&config);
+        let recorder =
+            RecordingExecutor::new().respond_with("sudo mkdir", CommandOutput::failure(1, ""));
+
+        let actual = add_user_directories_with(&entity, &config, |host| recorder.for_host(host));
+
+        assert!(actual.is_err());
+        insta::assert_yaml_snapshot!(recorder.recorded_lines());
+    }
+
+    #[test]
+    fn quote_hostile_config_values() {
+        let config = MgmtConfig {
+            compute_node_root_dir: "/mnt/md0/user; reboot".to_owned(),
+            quota_softlimit: "$(reboot)".to_owned(),
+            filesystem: "/mnt/md0 `reboot`".to_owned(),
+            nfs_host: String::new(),
+            home_host: String::new(),
+            ..config_with_dirs()
+        };
+        let entity = new_entity(&config);
+        let recorder = RecordingExecutor::new();
+
+        add_user_directories_with(&entity, &config, |host| recorder.for_host(host)).unwrap();
+
+        insta::assert_yaml_snapshot!(recorder.recorded_lines());
+    }
+
+    #[test]
+    fn inspect_directories_on_all_hosts() {
+        let config = config_with_dirs();
+        let recorder = RecordingExecutor::new()
+            .respond_with(
+                "sudo stat -c %U:%G /mnt/md0/scratch",
+                CommandOutput::failure(1, "No such file or directory"),
+            )
+            .respond_with("sudo stat", CommandOutput::success("somebody:student\n"))
+            .respond_with(
+                "sudo quota",
+                CommandOutput::success(
+                    "Disk quotas for user somebody (uid 1001):\n     Filesystem  blocks   quota   limit   grace   files   quota   limit   grace\n      /dev/sdb4      12    2000    3000               3       0       0\n",
+                ),
+            );
 
-    ssh::run_remote_command(sess, &cmd)
+        let actual =
+            inspect_user_directories_with("somebody", Some(Group::Student), &config, |host| {
+                recorder.for_host(host)
+            })
+            .unwrap();
+
+        insta::assert_yaml_snapshot!(actual);
+        insta::assert_yaml_snapshot!(recorder.recorded_lines());
+    }
 }
@@ -0,0 +1,319 @@
+//! Checks whether LDAP, Slurm and the hosts of the configuration are reachable
+//! with the given credentials before any user is changed.
+
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::OnWhichSystem,
+    config::MgmtConfig,
+    executor::{CommandExecutor, ExecCommand, LocalOrRemoteExecutor},
+    ldap::{self, LDAPConfig, LdapCredential},
+    output::TableRow,
+    prelude::AppResult,
+    slurm::SlurmRestClient,
+};
+
+const NO_ARGS: [&str; 0] = [];
+
+/// Outcome of a single check of a connection or command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckResult {
+    /// What was checked, e.g. SSH or the bind in LDAP.
+    pub check: String,
+    /// Host or server the check was performed against.
+    pub target: String,
+    pub passed: bool,
+    /// Time the check took in milliseconds.
+    pub millis: u128,
+    /// Short output on success or the error on failure.
+    pub details: String,
+}
+
+impl TableRow for CheckResult {
+    fn headers() -> Vec<&'static str> {
+        vec!["Check", "Target", "Result", "Time", "Details"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.check.clone(),
+            self.target.clone(),
+            if self.passed { "pass" } else { "fail" }.to_owned(),
+            format!("{} ms", self.millis),
+            self.details.clone(),
+        ]
+    }
+}
+
+/// Runs `action` as check and measures how long it takes.
+/// The check passes if `action` returns the details for the success.
+pub fn check(check: &str, target: &str, action: impl FnOnce() -> AppResult<String>) -> CheckResult {
+    let start = Instant::now();
+    let outcome = action();
+    let millis = start.elapsed().as_millis();
+    let (passed, details) = match outcome {
+        Ok(details) => (true, details),
+        Err(error) => (false, format!("{:#}", error)),
+    };
+    CheckResult {
+        check: check.to_owned(),
+        target: target.to_owned(),
+        passed,
+        millis,
+        details,
+    }
+}
+
+/// True if none of the checks failed.
+pub fn all_passed(results: &[CheckResult]) -> bool {
+    results.iter().all(|result| result.passed)
+}
+
+/// Checks the LDAP bind with the credentials for writing and for reading
+/// and a search under the base with the credentials for reading.
+/// The credentials are only asked for once. If reading uses the same bind as writing,
+/// the result of the bind for writing is taken for reading too.
+pub fn check_ldap<T>(config: &MgmtConfig, credentials: T) -> Vec<CheckResult>
+where
+    T: LdapCredential,
+{
+    let server = config.ldap_server.as_str();
+    let bind = |ldap_config: &LDAPConfig<T>| -> AppResult<String> {
+        if ldap_config.password()?.is_empty() {
+            bail!("No password given. An empty password would bind anonymously");
+        }
+        ldap::make_ldap_connection(ldap_config)?;
+        Ok(format!("Bound as {}", ldap_config.bind()))
+    };
+    let as_error = |error: &anyhow::Error| anyhow!("{:#}", error);
+
+    let write_config = LDAPConfig::new(config, credentials.clone());
+    let write_bind = check("LDAP bind", server, || {
+        bind(write_config.as_ref().map_err(as_error)?)
+    });
+    let readonly_config = LDAPConfig::new_readonly(config, credentials);
+    let readonly_check = "LDAP readonly bind";
+    let readonly_bind = match (&write_config, &readonly_config) {
+        (Ok(write), Ok(readonly)) if same_bind(write, readonly) => CheckResult {
+            check: readonly_check.to_owned(),
+            millis: 0,
+            ..write_bind.clone()
+        },
+        _ => check(readonly_check, server, || {
+            bind(readonly_config.as_ref().map_err(as_error)?)
+        }),
+    };
+    let search = check("LDAP search", server, || {
+        let ldap_config = readonly_config.as_ref().map_err(as_error)?;
+        ldap::search_base_entry(ldap_config)?;
+        Ok(format!("Search works under {}", ldap_config.base()))
+    });
+    vec![write_bind, readonly_bind, search]
+}
+
+fn same_bind<T>(left: &LDAPConfig<T>, right: &LDAPConfig<T>) -> bool
+where
+    T: LdapCredential,
+{
+    left.bind() == right.bind() && left.password().ok() == right.password().ok()
+}
+
+/// Checks slurmrestd or sacctmgr on the head node or locally, SSH to the hosts
+/// which commands are run on and whether sudo works without password
+/// for the commands of the directory management.
+/// The executor for a host is created by `on_host`. Every host is only checked once.
+///
+/// Hosts and commands of systems which are not chosen in `on_which_sys` are not checked.
+/// SSH to the head node is only checked if slurm is run remotely
+/// or the head node is one of the hosts of the directory management.
+pub fn check_hosts<'a, E>(
+    config: &'a MgmtConfig,
+    on_which_sys: &OnWhichSystem,
+    on_host: impl Fn(&'a str) -> E,
+) -> Vec<CheckResult>
+where
+    E: CommandExecutor,
+{
+    let mut results = Vec::new();
+    let mut connected: Vec<&str> = Vec::new();
+
+    if on_which_sys.slurm() && config.use_slurmrestd {
+        results.push(check("slurmrestd", &config.slurmrestd_url, || {
+            let qos = SlurmRestClient::from_config(config)?.check_access()?;
+            Ok(format!("Authenticated and found {} QOS", qos))
+        }));
+    } else if on_which_sys.slurm() {
+        let head_node = on_host(&config.head_node);
+        let target = if config.run_slurm_remote {
+            results.push(check_ssh(&config.head_node, &head_node));
+            connected.push(&config.head_node);
+            config.head_node.as_str()
+        } else {
+            "localhost"
+        };
+        let executor = LocalOrRemoteExecutor::new(config.run_slurm_remote, &head_node);
+        let version = ExecCommand::new(config.sacctmgr_path.clone(), ["--version"]);
+        results.push(check("sacctmgr --version", target, || {
+            run_for_first_line(&executor, &version)
+        }));
+    }
+
+    if on_which_sys.dirs() {
+        let mut hosts: Vec<&str> = vec![&config.nfs_host, &config.home_host];
+        hosts.extend(config.compute_nodes.iter().map(String::as_str));
+        let mut checked: Vec<&str> = Vec::new();
+        for host in hosts {
+            if host.is_empty() || checked.contains(&host) {
+                continue;
+            }
+            checked.push(host);
+            let executor = on_host(host);
+            if !connected.contains(&host) {
+                results.push(check_ssh(host, &executor));
+            }
+            let sudo = ExecCommand::new("sudo", ["-n", "true"]);
+            results.push(check("sudo -n true", host, || {
+                run_for_first_line(&executor, &sudo)
+                    .map(|_| "sudo works without password".to_owned())
+            }));
+        }
+    }
+    results
+}
+
+fn check_ssh<E>(host: &str, executor: &E) -> CheckResult
+where
+    E: CommandExecutor,
+{
+    check("SSH", host, || {
+        run_for_first_line(executor, &ExecCommand::new("true", NO_ARGS))
+            .map(|_| "Connected and ran a command".to_owned())
+    })
+}
+
+/// Runs the command and returns the first line of its stdout.
+fn run_for_first_line<E>(executor: &E, command: &ExecCommand) -> AppResult<String>
+where
+    E: CommandExecutor,
+{
+    let output = executor
+        .run(command)
+        .with_context(|| format!("Could not run command ({})", command))?;
+    if !output.is_success() {
+        bail!(
+            "Command ({}) failed with exit code {}: {}",
+            command,
+            output.exit_code(),
+            output.stderr().trim()
+        );
+    }
+    Ok(output
+        .into_stdout()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_owned())
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::executor::{CommandOutput, RecordingExecutor};
+
+    fn summary(results: &[CheckResult]) -> Vec<(String, String, bool, String)> {
+        results
+            .iter()
+            .map(|result| {
+                (
+                    result.check.clone(),
+                    result.target.clone(),
+                    result.passed,
+                    result.details.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn check_every_host_once() {
+        let config = MgmtConfig {
+            head_node: "head".to_owned(),
+            run_slurm_remote: true,
+            nfs_host: "nfs".to_owned(),
+            home_host: "head".to_owned(),
+            compute_nodes: vec!["node1".to_owned(), "nfs".to_owned()],
+            ..Default::default()
+        };
+        let executor = RecordingExecutor::new()
+            .respond_with(
+                "/usr/local/bin/sacctmgr --version",
+                CommandOutput::success("slurm 23.02.7\n"),
+            )
+            .respond_with(
+                "sudo -n true",
+                CommandOutput::failure(1, "sudo: a password is required\n"),
+            );
+
+        let actual = check_hosts(
+            &config,
+            &OnWhichSystem::new(true, true, true, None),
+            |host| executor.for_host(host),
+        );
+
+        insta::assert_yaml_snapshot!(executor.recorded_lines());
+        insta::assert_yaml_snapshot!(summary(&actual));
+        assert!(!all_passed(&actual));
+    }
+
+    #[test]
+    fn check_only_chosen_systems() {
+        let config = MgmtConfig {
+            head_node: "head".to_owned(),
+            run_slurm_remote: true,
+            ..Default::default()
+        };
+        let executor = RecordingExecutor::new();
+
+        let actual = check_hosts(
+            &config,
+            &OnWhichSystem::new(false, true, false, None),
+            |host| executor.for_host(host),
+        );
+
+        assert!(executor.recorded_lines().is_empty());
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn check_slurmrestd_instead_of_head_node() {
+        let config = MgmtConfig {
+            head_node: "head".to_owned(),
+            use_slurmrestd: true,
+            slurmrestd_url: "http://127.0.0.1:9".to_owned(),
+            slurmrestd_token: Some("secret.jwt".to_owned()),
+            ..Default::default()
+        };
+        let executor = RecordingExecutor::new();
+
+        let actual = check_hosts(
+            &config,
+            &OnWhichSystem::new(true, false, false, None),
+            |host| executor.for_host(host),
+        );
+
+        assert!(executor.recorded_lines().is_empty());
+        assert_eq!(1, actual.len());
+        assert_eq!(
+            ("slurmrestd", "http://127.0.0.1:9", false),
+            (
+                actual[0].check.as_str(),
+                actual[0].target.as_str(),
+                actual[0].passed
+            )
+        );
+    }
+}
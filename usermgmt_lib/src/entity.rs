@@ -1,7 +1,8 @@
 use crate::{
     cli::{CommonUserFields, Modifiable, UserToAdd},
     prelude::AppError,
-    util::{ResolvedGid, ValidGroupOfQos, ValidQos},
+    slurm::AssociationLimits,
+    util::{ResolvedGid, UsernamePolicy, ValidGroupOfQos, ValidQos},
 };
 use anyhow::{ensure, Context};
 use log::debug;
@@ -22,6 +23,8 @@ pub struct Entity {
     /// TODO: Add validation if a present publickey is in valid format, OpenSsh
     pub publickey: Option<TrimmedNonEmptyText>,
     pub qos: Option<ValidGroupOfQos>,
+    /// Slurm limits given for the user. Limits not given are `None`.
+    pub limits: AssociationLimits,
 }
 
 impl Entity {
@@ -29,12 +32,13 @@ impl Entity {
     ///
     /// - If public key file could not be read
     pub fn new(
+        username: TrimmedNonEmptyText,
         firstname: Option<TrimmedNonEmptyText>,
         lastname: Option<TrimmedNonEmptyText>,
         to_add: CommonUserFields,
         config: &MgmtConfig,
     ) -> AppResult<Self> {
-        Self::new_inner(firstname, lastname, to_add, config, |path| {
+        Self::new_inner(username, firstname, lastname, to_add, config, |path| {
             fs::read_to_string(path).with_context(|| {
                 format!(
                     "Unable to read PublicKey from file from path {} !",
@@ -51,13 +55,14 @@ impl Entity {
     /// - If the default quality of service is not valid quality of service. See [`ValidQos`]
     /// - If loading the public key, parameter `on_load_pubkey`, of an user fails.
     pub fn new_inner(
+        username: TrimmedNonEmptyText,
         firstname: Option<TrimmedNonEmptyText>,
         lastname: Option<TrimmedNonEmptyText>,
         to_add: CommonUserFields,
         config: &MgmtConfig,
         on_load_pubkey: impl Fn(&Path) -> AppResult<String>,
     ) -> AppResult<Self> {
-        let (username, group, mail, default_qos, publickey, qos) = to_add.into();
+        let (group, mail, default_qos, publickey, qos, limits) = to_add.into();
         let group = group
             .map(|group| {
                 let group_id = Group::from_str(group.as_ref().as_str())
@@ -107,6 +112,7 @@ impl Entity {
             publickey,
             qos,
             mail,
+            limits: limits.into(),
         })
     }
 
@@ -114,17 +120,42 @@ impl Entity {
     ///
     /// See [`Entity::new`]
     pub fn new_modifieble_conf(modif: Modifiable, conf: &MgmtConfig) -> AppResult<Self> {
-        let (firstname, lastname, common_user_fields) = modif.into();
-        Self::new(firstname, lastname, common_user_fields, conf)
+        let (username, firstname, lastname, common_user_fields) = modif.into();
+        Self::new(username, firstname, lastname, common_user_fields, conf)
     }
 
     /// # Errors
     ///
-    /// See [`Entity::new`]
+    /// See [`Entity::new_addition`]
     pub fn new_user_addition_conf(to_add: UserToAdd, conf: &MgmtConfig) -> AppResult<Self> {
-        let (firstname, lastname, common_user_fields) = to_add.into();
-        let (firstname, lastname) = (Some(firstname), Some(lastname));
-        Self::new(firstname, lastname, common_user_fields, conf)
+        Self::new_addition(
+            to_add.username,
+            to_add.firstname,
+            to_add.lastname,
+            to_add.common_user_fields,
+            conf,
+        )
+    }
+
+    /// Same as [`Entity::new`] for an user to add.
+    /// Only the username of an user to add must follow the username policy.
+    /// This way existing users can still be modified after the policy became stricter.
+    ///
+    /// # Errors
+    ///
+    /// - If no username is given.
+    /// - If the username does not follow the username policy. See [`UsernamePolicy`]
+    /// - See [`Entity::new`]
+    pub fn new_addition(
+        username: Option<TrimmedNonEmptyText>,
+        firstname: TrimmedNonEmptyText,
+        lastname: TrimmedNonEmptyText,
+        to_add: CommonUserFields,
+        config: &MgmtConfig,
+    ) -> AppResult<Self> {
+        let username = username.context("A username must be given")?;
+        UsernamePolicy::from_config(config)?.check(username.as_ref())?;
+        Self::new(username, Some(firstname), Some(lastname), to_add, config)
     }
 }
 
@@ -134,18 +165,30 @@ mod testing {
 
     #[test]
     fn error_for_not_valid_default_qos() {
-        let mut input = CommonUserFields::new("SomeUser".try_into().unwrap());
-        input.default_qos = Some("NotValid".try_into().unwrap());
-        let actual = Entity::new_inner(None, None, input, &MgmtConfig::default(), |_| panic!());
+        let input = CommonUserFields {
+            default_qos: Some("NotValid".try_into().unwrap()),
+            ..Default::default()
+        };
+        let actual = Entity::new_inner(
+            "SomeUser".try_into().unwrap(),
+            None,
+            None,
+            input,
+            &MgmtConfig::default(),
+            |_| panic!(),
+        );
 
         insta::assert_debug_snapshot!(actual);
     }
 
     #[test]
     fn error_for_not_valid_group_of_qos() {
-        let mut input = CommonUserFields::new("SomeUser".try_into().unwrap());
-        input.qos = vec!["valid".into(), "not_valid".into()];
+        let input = CommonUserFields {
+            qos: vec!["valid".into(), "not_valid".into()],
+            ..Default::default()
+        };
         let actual = Entity::new_inner(
+            "SomeUser".try_into().unwrap(),
             None,
             None,
             input,
@@ -161,14 +204,16 @@ mod testing {
 
     #[test]
     fn ok_with_valid_default_and_group_of_qos_pubkey() {
-        let mut input = CommonUserFields::new("Some_User".try_into().unwrap());
-        input.group = Some("staff".try_into().unwrap());
-        input.default_qos = Some("staff".try_into().unwrap());
-        input.qos = vec!["valid".into(), "basic".into()];
-        input.default_qos = Some("valid".try_into().unwrap());
-        input.publickey = Some("Some_path".try_into().unwrap());
-        input.mail = Some("faculty@xxx.de".try_into().unwrap());
+        let input = CommonUserFields {
+            group: Some("staff".try_into().unwrap()),
+            qos: vec!["valid".into(), "basic".into()],
+            default_qos: Some("valid".try_into().unwrap()),
+            publickey: Some("Some_path".try_into().unwrap()),
+            mail: Some("faculty@xxx.de".try_into().unwrap()),
+            ..Default::default()
+        };
         let actual = Entity::new_inner(
+            "Some_User".try_into().unwrap(),
             Some("First".try_into().unwrap()),
             None,
             input,
@@ -181,4 +226,38 @@ mod testing {
 
         insta::assert_debug_snapshot!(actual);
     }
+
+    #[test]
+    fn username_policy_only_for_users_to_add() {
+        let config = MgmtConfig {
+            username_min_length: Some(6),
+            ..MgmtConfig::default()
+        };
+
+        let to_modify = Modifiable::new("bob".try_into().unwrap());
+        assert!(Entity::new_modifieble_conf(to_modify, &config).is_ok());
+        let to_add = UserToAdd::new(
+            "Bob".try_into().unwrap(),
+            "Builder".try_into().unwrap(),
+            CommonUserFields::default(),
+        )
+        .with_username("bob".try_into().unwrap());
+        assert!(Entity::new_user_addition_conf(to_add, &config).is_err());
+    }
+
+    #[test]
+    fn strict_username_rules_only_for_users_to_add() {
+        let config = MgmtConfig::default();
+        let legacy = "1legacy@example";
+
+        let to_modify = Modifiable::new(legacy.try_into().unwrap());
+        assert!(Entity::new_modifieble_conf(to_modify, &config).is_ok());
+        let to_add = UserToAdd::new(
+            "Legacy".try_into().unwrap(),
+            "User".try_into().unwrap(),
+            CommonUserFields::default(),
+        )
+        .with_username(legacy.try_into().unwrap());
+        assert!(Entity::new_user_addition_conf(to_add, &config).is_err());
+    }
 }
@@ -0,0 +1,67 @@
+//! Abstraction over running commands on the local machine or on a remote machine over ssh.
+//! Slurm actions and the directory management only talk to a [`CommandExecutor`].
+//! This way these actions can be tested via [`RecordingExecutor`] without any real process or
+//! ssh connection.
+
+mod command_output;
+mod exec_command;
+mod local_executor;
+mod recording_executor;
+
+pub use command_output::CommandOutput;
+pub use exec_command::ExecCommand;
+pub use local_executor::LocalExecutor;
+pub use recording_executor::{RecordedCommand, RecordingExecutor};
+
+use crate::prelude::AppResult;
+
+/// Runs a command somewhere and reports its exit code, stdout and stderr.
+pub trait CommandExecutor {
+    /// Runs the given command and waits until it has finished.
+    ///
+    /// # Errors
+    ///
+    /// - If the command could not be started or its output could not be retrieved.
+    ///   A non-zero exit code is not an error on this level.
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput>;
+}
+
+impl<T> CommandExecutor for &T
+where
+    T: CommandExecutor + ?Sized,
+{
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput> {
+        (**self).run(command)
+    }
+}
+
+/// Runs commands either on the local machine or via the given executor for a remote machine.
+pub enum LocalOrRemoteExecutor<'a, R> {
+    Local(LocalExecutor),
+    Remote(&'a R),
+}
+
+impl<'a, R> LocalOrRemoteExecutor<'a, R>
+where
+    R: CommandExecutor,
+{
+    pub fn new(run_remote: bool, remote: &'a R) -> Self {
+        if run_remote {
+            Self::Remote(remote)
+        } else {
+            Self::Local(LocalExecutor)
+        }
+    }
+}
+
+impl<'a, R> CommandExecutor for LocalOrRemoteExecutor<'a, R>
+where
+    R: CommandExecutor,
+{
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput> {
+        match self {
+            Self::Local(local) => local.run(command),
+            Self::Remote(remote) => remote.run(command),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use getset::{CopyGetters, Getters};
+use serde::Serialize;
+
+/// Result of a finished command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Getters, CopyGetters)]
+pub struct CommandOutput {
+    #[getset(get_copy = "pub")]
+    exit_code: i32,
+    #[getset(get = "pub")]
+    stdout: String,
+    #[getset(get = "pub")]
+    stderr: String,
+}
+
+impl CommandOutput {
+    pub fn new(exit_code: i32, stdout: String, stderr: String) -> Self {
+        Self {
+            exit_code,
+            stdout,
+            stderr,
+        }
+    }
+
+    /// Successful output with the given text as stdout.
+    pub fn success(stdout: &str) -> Self {
+        Self::new(0, stdout.to_owned(), String::new())
+    }
+
+    /// Failed output with the given exit code and text as stderr.
+    pub fn failure(exit_code: i32, stderr: &str) -> Self {
+        Self::new(exit_code, String::new(), stderr.to_owned())
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    pub fn into_stdout(self) -> String {
+        self.stdout
+    }
+}
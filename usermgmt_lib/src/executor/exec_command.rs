@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use derive_more::Display;
+use getset::Getters;
+use serde::Serialize;
+
+/// Program with its arguments to be run by a [`super::CommandExecutor`].
+///
+/// The arguments are kept separately so a local execution does not go through a shell at all.
+/// For the execution over ssh the program and arguments are joined into one command line.
+/// Every part of this command line is quoted for a POSIX shell if needed.
+/// This way no argument can be interpreted as shell syntax by the remote shell.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Getters)]
+#[display(fmt = "{}", "self.command_line()")]
+#[getset(get = "pub")]
+pub struct ExecCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExecCommand {
+    pub fn new<S>(program: impl Into<String>, args: impl IntoIterator<Item = S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Program and arguments quoted and joined by spaces as one line for a POSIX shell.
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .map(quote_for_shell)
+            .collect::<Vec<Cow<'_, str>>>()
+            .join(" ")
+    }
+
+    /// Command for spawning a process on the local machine.
+    pub fn to_local_command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+}
+
+/// Characters which have no special meaning for a POSIX shell, even at the start of a word.
+fn is_shell_safe(character: char) -> bool {
+    character.is_ascii_alphanumeric() || "-_./=:,%@+".contains(character)
+}
+
+/// Returns the argument unchanged if it consists of shell safe characters only.
+/// Otherwise the argument is enclosed in single quotes.
+/// Within single quotes a POSIX shell interprets nothing except a single quote itself.
+/// A contained single quote therefore closes the quoting, is escaped and opens the quoting again.
+fn quote_for_shell(argument: &str) -> Cow<'_, str> {
+    if !argument.is_empty() && argument.chars().all(is_shell_safe) {
+        Cow::Borrowed(argument)
+    } else {
+        Cow::Owned(format!("'{}'", argument.replace('\'', r"'\''")))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn leave_safe_arguments_unquoted() {
+        let command = ExecCommand::new(
+            "/usr/local/bin/sacctmgr",
+            [
+                "modify",
+                "User",
+                "somebody",
+                "set",
+                "QOS=basic,gpu",
+                "--immediate",
+            ],
+        );
+        assert_eq!(
+            "/usr/local/bin/sacctmgr modify User somebody set QOS=basic,gpu --immediate",
+            command.command_line()
+        );
+    }
+
+    #[test]
+    fn quote_arguments_with_shell_syntax() {
+        let hostile = [
+            "",
+            "some body",
+            "x; rm -rf /",
+            "$(reboot)",
+            "`reboot`",
+            "a|b&c>d<e",
+            "it's",
+            "'; reboot; '",
+            "line\nbreak",
+            "~root",
+            "*",
+        ];
+        let command = ExecCommand::new("sudo", hostile);
+
+        insta::assert_snapshot!(command.command_line());
+    }
+
+    #[test]
+    fn shell_parses_quoted_arguments_back_unchanged() {
+        let hostile = [
+            "x; touch /tmp/usermgmt_injected",
+            "$(id)",
+            "`id`",
+            "it's",
+            "'; id; '",
+            "a\"b\\c",
+            "tab\tand\nnewline",
+            "",
+        ];
+        let command = ExecCommand::new("printf", ["%s\\0"].into_iter().chain(hostile));
+
+        let output = std::process::Command::new("sh")
+            .args(["-c", &command.command_line()])
+            .output()
+            .unwrap();
+
+        let actual: Vec<String> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .map(ToOwned::to_owned)
+            .collect();
+        assert_eq!(hostile.to_vec(), actual);
+    }
+}
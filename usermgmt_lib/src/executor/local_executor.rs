@@ -0,0 +1,32 @@
+use anyhow::Context;
+use log::debug;
+
+use crate::prelude::AppResult;
+
+use super::{CommandExecutor, CommandOutput, ExecCommand};
+
+/// Exit code reported if a local process was terminated by a signal and therefore has no exit code.
+const NO_EXIT_CODE: i32 = -1;
+
+/// Runs commands as processes on the local machine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalExecutor;
+
+impl CommandExecutor for LocalExecutor {
+    /// # Errors
+    ///
+    /// - If the process could not be spawned, for example because the program was not found.
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput> {
+        debug!("Running local command ({})", command);
+        let output = command
+            .to_local_command()
+            .output()
+            .with_context(|| format!("Unable to execute local command ({})", command))?;
+
+        Ok(CommandOutput::new(
+            output.status.code().unwrap_or(NO_EXIT_CODE),
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
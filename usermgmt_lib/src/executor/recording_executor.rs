@@ -0,0 +1,93 @@
+use std::{cell::RefCell, rc::Rc};
+
+use serde::Serialize;
+
+use crate::prelude::AppResult;
+
+use super::{CommandExecutor, CommandOutput, ExecCommand};
+
+/// Command which was run by a [`RecordingExecutor`] together with the host it was meant for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedCommand {
+    pub host: Option<String>,
+    pub command: ExecCommand,
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    recorded: Vec<RecordedCommand>,
+    /// Pairs of the start of a command line and the output returned for a matching command.
+    scripted: Vec<(String, CommandOutput)>,
+}
+
+/// In-memory executor which never runs anything.
+/// It records every command and answers with scripted outputs.
+/// Commands without a scripted output succeed with empty stdout and stderr.
+///
+/// Clones and executors created via [`RecordingExecutor::for_host`] share the same recording.
+/// This way commands for several hosts end up in one list in the order of their execution.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingExecutor {
+    host: Option<String>,
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl RecordingExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an executor which shares the recording but marks commands with the given `host`.
+    pub fn for_host(&self, host: &str) -> Self {
+        Self {
+            host: Some(host.to_owned()),
+            recording: Rc::clone(&self.recording),
+        }
+    }
+
+    /// Every command whose command line starts with `command_start` returns `output`.
+    /// Outputs scripted earlier take precedence over later ones.
+    pub fn respond_with(self, command_start: &str, output: CommandOutput) -> Self {
+        self.recording
+            .borrow_mut()
+            .scripted
+            .push((command_start.to_owned(), output));
+        self
+    }
+
+    /// All commands run so far in the order of their execution.
+    pub fn recorded(&self) -> Vec<RecordedCommand> {
+        self.recording.borrow().recorded.clone()
+    }
+
+    /// Command lines of all commands run so far, prefixed by the host if there is one.
+    pub fn recorded_lines(&self) -> Vec<String> {
+        self.recording
+            .borrow()
+            .recorded
+            .iter()
+            .map(|recorded| match &recorded.host {
+                Some(host) => format!("{}: {}", host, recorded.command),
+                None => recorded.command.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl CommandExecutor for RecordingExecutor {
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput> {
+        let mut recording = self.recording.borrow_mut();
+        recording.recorded.push(RecordedCommand {
+            host: self.host.clone(),
+            command: command.clone(),
+        });
+        let command_line = command.command_line();
+        let output = recording
+            .scripted
+            .iter()
+            .find(|(command_start, _)| command_line.starts_with(command_start.as_str()))
+            .map(|(_, output)| output.clone())
+            .unwrap_or_default();
+        Ok(output)
+    }
+}
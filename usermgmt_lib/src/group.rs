@@ -4,22 +4,17 @@ use crate::AppError;
 use anyhow::anyhow;
 
 use derive_more::Display;
-#[derive(Clone, PartialEq, Copy, Debug, Eq, Display)]
+#[derive(Clone, PartialEq, Copy, Debug, Eq, Display, Default)]
 pub enum Group {
     #[display(fmt = "staff")]
     Staff,
     #[display(fmt = "student")]
+    #[default]
     Student,
     #[display(fmt = "faculty")]
     Faculty,
 }
 
-impl Default for Group {
-    fn default() -> Self {
-        Self::Student
-    }
-}
-
 use once_cell::sync::Lazy;
 
 static GROUP_FROM_STR_MAP: Lazy<HashMap<Box<str>, Group>> = Lazy::new(|| {
@@ -2,13 +2,15 @@ mod ldap_config;
 mod ldap_credential;
 mod ldap_paths;
 mod ldap_search_result;
+mod ldap_search_scope;
 mod ldap_session;
 mod ldap_simple_credential;
 pub mod text_list_output;
 
 pub use ldap_config::LDAPConfig;
 pub use ldap_credential::LdapCredential;
-pub use ldap_search_result::LdapSearchResult;
+pub use ldap_search_result::{LdapSearchResult, LdapUserRow};
+pub use ldap_search_scope::LdapSearchScope;
 pub use ldap_session::LdapSession;
 pub use ldap_simple_credential::LdapSimpleCredential;
 use once_cell::sync::Lazy;
@@ -19,8 +21,11 @@ use crate::prelude::AppResult;
 use crate::util::{get_new_uid, hashset_from_vec_str};
 use crate::{prelude::*, NewEntity};
 use crate::{ChangesToUser, MgmtConfig};
+use ldap3::adapters::{Adapter, EntriesOnly, PagedResults};
 use ldap3::controls::{MakeCritical, RelaxRules};
-use ldap3::{LdapConn, LdapError, LdapResult, Mod, Scope, SearchEntry, SearchResult};
+use ldap3::{
+    dn_escape, ldap_escape, LdapConn, LdapError, LdapResult, Mod, Scope, SearchEntry, SearchResult,
+};
 use log::{debug, info, warn};
 use maplit::hashset;
 use std::collections::HashSet;
@@ -116,7 +121,7 @@ where
 
         ldap_session.action(|connection, ldap_config| {
             let result_form_adding = connection.add(
-                &format!("uid={},{}", entity.username, ldap_config.base()),
+                &user_dn(un, ldap_config.base()),
                 vec![
                     ("cn", hashset! {un}),
                     (
@@ -143,6 +148,9 @@ where
     }
 }
 
+/// Deletes the LDAP entry of the user.
+/// Nothing is deleted if no entry exists for the user.
+///
 /// # Errors
 ///
 /// - If finding the LDAP-DN by the UID fails. See [`find_dn_by_uid`]
@@ -152,7 +160,7 @@ where
     T: LdapCredential,
 {
     let dn = find_dn_by_uid(username, ldap_session)
-        .with_context(|| format!("No DN found for username {}!", username))?;
+        .with_context(|| format!("Unable to find DN for username {}!", username))?;
     debug!(
         "LDAP connection established to {}",
         ldap_session.config().bind()
@@ -178,6 +186,7 @@ where
 /// # Errors
 ///
 /// - If finding the DN-LDAP  by the UID fails. See [`find_dn_by_uid`]
+/// - If no entry exists for the user.
 /// - If finding the quality of service by the UID fails. See [`find_qos_by_uid`]
 pub fn modify_ldap_user<T>(
     modifiable: &ChangesToUser,
@@ -189,11 +198,16 @@ where
     let dn = find_dn_by_uid(modifiable.username.as_ref(), ldap_session)
         .with_context(|| {
             format!(
-                "No DN found for username {}! Unable to modify user.",
+                "Unable to find DN for username {}! Unable to modify user.",
                 modifiable.username.as_ref()
             )
         })?
-        .ok_or(anyhow!("No dn found for uid"))?;
+        .ok_or_else(|| {
+            anyhow!(
+                "No LDAP entry found for username {}! Unable to modify user.",
+                modifiable.username
+            )
+        })?;
 
     // Prepare replace operation
 
@@ -224,6 +238,53 @@ where
 /// - If the connection to the LDAP instance fails. See [`make_ldap_connection`]
 /// - If the searching in LDAP failed
 pub fn list_ldap_users<T>(ldap_config: LDAPConfig<T>) -> AppResult<LdapSearchResult>
+where
+    T: LdapCredential,
+{
+    list_ldap_users_with_filter(ldap_config, ALL_ENTRIES_FILTER)
+}
+
+/// List the LDAP users matching the given LDAP filter, e.g. `(gidNumber=1001)`.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails. See [`make_ldap_connection`]
+/// - If the searching in LDAP failed, e.g. because of an invalid filter
+pub fn list_ldap_users_with_filter<T>(
+    ldap_config: LDAPConfig<T>,
+    filter: &str,
+) -> AppResult<LdapSearchResult>
+where
+    T: LdapCredential,
+{
+    search_listed_users(ldap_config, filter, &SORTED_LDAP_LISTING_ATTRIBUTES)
+}
+
+/// Same as [`list_ldap_users_with_filter`] but also fetches the group id (gidNumber) of every user.
+/// Needed for merging users of LDAP and Slurm or grouping them by their group.
+///
+/// # Errors
+///
+/// See [`list_ldap_users_with_filter`]
+pub fn list_ldap_users_with_gid<T>(
+    ldap_config: LDAPConfig<T>,
+    filter: &str,
+) -> AppResult<LdapSearchResult>
+where
+    T: LdapCredential,
+{
+    search_listed_users(
+        ldap_config,
+        filter,
+        &SORTED_LDAP_LISTING_ATTRIBUTES_WITH_GID,
+    )
+}
+
+fn search_listed_users<T>(
+    ldap_config: LDAPConfig<T>,
+    filter: &str,
+    attrs: &[&str],
+) -> AppResult<LdapSearchResult>
 where
     T: LdapCredential,
 {
@@ -237,20 +298,49 @@ where
         ldap_config.base()
     );
 
-    let attrs = SORTED_LDAP_LISTING_ATTRIBUTES.as_slice();
-    // Search for all entities under base dn
+    // Search for the matching entities under base dn
+    let filter = combine_filters(ldap_config.search_filter(), filter);
+    let search_result = paged_search(&mut ldap, &ldap_config, &filter, attrs)
+        .with_context(|| format!("Error during LDAP search with filter {}!", filter))?;
+
+    let search_result = LdapSearchResult::from_ldap_raw_search(attrs.iter(), &search_result);
+
+    Ok(search_result)
+}
+
+/// Fetches all attributes of the user aka parameter `username` which this tool sets while adding.
+/// The result has no rows if the user does not exist in LDAP.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails. See [`make_ldap_connection`]
+/// - If the searching in LDAP failed
+/// - If more than one entry is found in the LDAP query under the given user. See [`single_entry`]
+pub fn find_ldap_user<T>(username: &str, ldap_config: &LDAPConfig<T>) -> AppResult<LdapSearchResult>
+where
+    T: LdapCredential,
+{
+    let mut ldap =
+        make_ldap_connection(ldap_config).context("Error while connecting via LDAP !")?;
+    debug!("LDAP connection established to {}", ldap_config.bind());
+
+    let attrs = SHOWN_LDAP_ATTRIBUTES.as_slice();
     let search_result = ldap
         .search(
             ldap_config.base(),
-            Scope::OneLevel,
-            "(objectclass=*)",
+            ldap_config.search_scope().into(),
+            &uid_filter(username),
             attrs,
         )
         .context("Error during LDAP search!")?;
+    let entries = single_entry(search_result.0, username)?
+        .into_iter()
+        .collect();
 
-    let search_result = LdapSearchResult::from_ldap_raw_search(attrs.iter(), &search_result);
-
-    Ok(search_result)
+    Ok(LdapSearchResult::from_ldap_raw_search(
+        attrs.iter(),
+        &SearchResult(entries, search_result.1),
+    ))
 }
 
 /// Creates modification parameters which are used by `ldap3` library to modify an user in LDAP.
@@ -316,21 +406,41 @@ where
             "find_next_available_uid: LDAP connection established to {}",
             config.bind(),
         );
-
-        debug!("Search under {}", config.base());
     }
 
     // Search for all uidNumbers under base dn
-    let search_result = ldap_session.action(|connection, config| {
-        connection
-            .search(
-                config.base(),
-                Scope::OneLevel,
-                "(objectclass=*)",
-                vec!["uidNumber"],
-            )
-            .context("Error during uid search!")
-    })?;
+    // Every page is fetched since a missing uid could lead to a duplicate uidNumber.
+    ldap_session.action(|connection, config| {
+        find_next_uid_with(config, group, |filter| {
+            let search_result = paged_search(connection, config, filter, &["uidNumber"])
+                .context("Error during uid search!")?;
+            parse_uids(&search_result)
+        })
+    })
+}
+
+/// Finds the next available UID among the uids returned by `search_uids` for a filter.
+/// The filter from the configuration is not used. It is meant for listing and could hide
+/// entries whose uids would then be handed out again.
+fn find_next_uid_with<T>(
+    config: &LDAPConfig<T>,
+    group: crate::Group,
+    search_uids: impl FnOnce(&str) -> AppResult<Vec<u32>>,
+) -> AppResult<u32>
+where
+    T: LdapCredential,
+{
+    debug!(
+        "Search under {} with {} instead of the search filter {}",
+        config.base(),
+        UID_SEARCH_FILTER,
+        config.search_filter()
+    );
+    let uids = search_uids(UID_SEARCH_FILTER)?;
+    get_new_uid(&uids, group)
+}
+
+fn parse_uids(search_result: &SearchResult) -> AppResult<Vec<u32>> {
     let mut uids: Vec<u32> = Vec::new();
     for elem in search_result.0.iter() {
         let search_result = SearchEntry::construct(elem.to_owned());
@@ -348,16 +458,16 @@ where
 
         uids.push(uid);
     }
-
-    get_new_uid(&uids, group)
+    Ok(uids)
 }
 
 /// Search for a specific UID and return the corresponding dn.
+/// Returns `None` if no entry exists for the given user aka parameter `username`.
 ///
 /// # Errors
 ///
 /// - If the connection to a LDAP instance can not be established
-/// - If nothing is found in the LDAP query under the given user aka parameter `username`
+/// - If more than one entry is found in the LDAP query under the given user. See [`single_entry`]
 pub fn find_dn_by_uid<T>(
     username: &str,
     ldap_session: &mut LdapSession<T>,
@@ -374,30 +484,24 @@ where
     let search: SearchResult = ldap_session.action(|con, config| {
         con.search(
             config.base(),
-            Scope::OneLevel,
-            &format!("(uid={username})"),
+            config.search_scope().into(),
+            &uid_filter(username),
             vec!["dn"],
         )
         .context("LDAP search failed")
     })?;
 
-    let entry = search
-        .0
-        .into_iter()
-        .next()
-        .with_context(|| format!("No LDAP entry found for user {}", username))?;
-
-    let sr = SearchEntry::construct(entry);
+    let sr = single_entry(search.0, username)?.map(SearchEntry::construct);
     debug!("SR for deletion: {:?}", sr);
 
-    Ok(Some(sr.dn))
+    Ok(sr.map(|entry| entry.dn))
 }
 
 /// Search for a specific uid and return the corresponding qos.
 /// # Errors
 ///
 /// - If the connection to the LDAP instance fails
-/// - If nothing is found in the LDAP query under the given user aka parameter `username`
+/// - If more than one entry is found in the LDAP query under the given user. See [`single_entry`]
 pub fn find_qos_by_uid<T>(
     username: &str,
     ldap_session: &mut LdapSession<T>,
@@ -417,8 +521,8 @@ where
         ldap_connection
             .search(
                 ldap_config.base(),
-                Scope::OneLevel,
-                &format!("(uid={})", username),
+                ldap_config.search_scope().into(),
+                &uid_filter(username),
                 vec!["slurmQos"],
             )
             .with_context(|| {
@@ -429,9 +533,13 @@ where
             })
     })?;
 
-    for elem in search.0.iter() {
-        let search_result = SearchEntry::construct(elem.to_owned());
-        let q = &search_result.attrs["slurmQos"];
+    if let Some(entry) = single_entry(search.0, username)? {
+        let search_result = SearchEntry::construct(entry);
+        let q = search_result
+            .attrs
+            .get("slurmQos")
+            .map(Vec::as_slice)
+            .unwrap_or_default();
         for one_qos in q {
             debug!("Fetched QOS: {:?}", one_qos);
             fetched_all_qos.push(one_qos.clone());
@@ -448,7 +556,7 @@ where
 ///
 /// - If the connection to the LDAP instance fails
 /// - If nothing is found in the LDAP query under the given user aka parameter `username`
-pub fn username_exists<T>(username: &String, ldap_config: &LDAPConfig<T>) -> AppResult<bool>
+pub fn username_exists<T>(username: &str, ldap_config: &LDAPConfig<T>) -> AppResult<bool>
 where
     T: LdapCredential,
 {
@@ -459,8 +567,8 @@ where
     // Search for all uid under base dn and return dn of user
     let search_result = ldap.search(
         ldap_config.base(),
-        Scope::OneLevel,
-        &format!("(uid={username})"),
+        ldap_config.search_scope().into(),
+        &uid_filter(username),
         vec!["dn"],
     )?;
     match search_result.0.into_iter().next() {
@@ -474,6 +582,120 @@ where
     Ok(username_exists)
 }
 
+/// Reads the entry of the base itself.
+/// Used to check whether searching in LDAP works with the given connection and configuration
+/// without fetching all entries under the base.
+///
+/// # Errors
+///
+/// - If the connection to the LDAP instance fails. See [`make_ldap_connection`]
+/// - If the search fails, e.g. because the base does not exist or may not be read.
+pub fn search_base_entry<T>(ldap_config: &LDAPConfig<T>) -> AppResult
+where
+    T: LdapCredential,
+{
+    let mut ldap = make_ldap_connection(ldap_config)?;
+    ldap.search(
+        ldap_config.base(),
+        Scope::Base,
+        ALL_ENTRIES_FILTER,
+        vec!["1.1"],
+    )
+    .and_then(|result| result.success())
+    .with_context(|| format!("Could not search under the base {}", ldap_config.base()))?;
+    Ok(())
+}
+
+/// Searches all entries matching the filter page by page.
+/// This way the result is complete even if it has more entries than the size limit of the server.
+///
+/// # Errors
+///
+/// - If the search fails.
+/// - If the server still stops the search at its size limit, e.g. because it does not support paging.
+///   A partial result is not returned since it could lead to duplicate uids.
+fn paged_search<T>(
+    connection: &mut LdapConn,
+    ldap_config: &LDAPConfig<T>,
+    filter: &str,
+    attrs: &[&str],
+) -> AppResult<SearchResult>
+where
+    T: LdapCredential,
+{
+    let page_size = i32::try_from(ldap_config.page_size()).unwrap_or(i32::MAX);
+    let adapters: Vec<Box<dyn Adapter<_, _>>> = vec![
+        Box::new(EntriesOnly::new()),
+        Box::new(PagedResults::new(page_size)),
+    ];
+    let mut stream = connection.streaming_search_with(
+        adapters,
+        ldap_config.base(),
+        ldap_config.search_scope().into(),
+        filter,
+        attrs,
+    )?;
+    let mut entries = Vec::new();
+    while let Some(entry) = stream.next()? {
+        entries.push(entry);
+    }
+    let result = stream.result();
+    if result.rc == SIZE_LIMIT_EXCEEDED {
+        bail!(
+            "LDAP search stopped at the size limit of the server after {} entries. \
+            Make sure the server supports paged results or lower ldap_page_size below its size limit",
+            entries.len()
+        );
+    }
+    let result = result.success()?;
+    Ok(SearchResult(entries, result))
+}
+
+/// Combines the filter for all user entries of the configuration with another filter.
+fn combine_filters(search_filter: &str, filter: &str) -> String {
+    if filter == ALL_ENTRIES_FILTER || filter == search_filter {
+        search_filter.to_owned()
+    } else if search_filter == ALL_ENTRIES_FILTER {
+        filter.to_owned()
+    } else {
+        format!("(&{}{})", search_filter, filter)
+    }
+}
+
+/// Filter which matches entries whose uid is exactly the given username.
+/// The username is escaped according to RFC 4515.
+/// This way characters like `*` or `)` in the username can not alter the filter.
+fn uid_filter(username: &str) -> String {
+    format!("(uid={})", ldap_escape(username))
+}
+
+/// DN of an user entry with the given username under the given base.
+/// The username is escaped according to RFC 4514.
+fn user_dn(username: &str, base: &str) -> String {
+    format!("uid={},{}", dn_escape(username), base)
+}
+
+/// Returns the only entry of a search result or `None` if nothing was found.
+///
+/// # Errors
+///
+/// - If more than one entry was found for the user aka parameter `username`.
+///   Acting on such a result could change the wrong entry.
+fn single_entry<E>(mut entries: Vec<E>, username: &str) -> AppResult<Option<E>> {
+    match entries.len() {
+        0 => {
+            debug!("No LDAP entry found for user {}", username);
+            Ok(None)
+        }
+        1 => Ok(entries.pop()),
+        found => bail!(
+            "Found {} LDAP entries for user {} instead of exactly one. Refusing to act on them.",
+            found,
+            username
+        ),
+    }
+}
+
 /// If ok is returned then ldap operation happened with zero error code, LDAP_SUCCESS
 ///
 /// Even if a call to ldap returns ok it has an error code inside it. Only if the code is zero
@@ -511,6 +733,30 @@ where
     Ok((ldap_user.trim().to_owned(), ldap_pass.trim().to_owned()))
 }
 
+/// Attributes of a single user shown in detail.
+const SHOWN_LDAP_ATTRIBUTES: [&str; 13] = [
+    "uid",
+    "uidNumber",
+    "gidNumber",
+    "cn",
+    "givenName",
+    "sn",
+    "mail",
+    "homeDirectory",
+    "loginShell",
+    "sshPublicKey",
+    "slurmDefaultQos",
+    "slurmQos",
+    "objectClass",
+];
+
+/// LDAP filter which matches every entity.
+pub const ALL_ENTRIES_FILTER: &str = "(objectclass=*)";
+/// Matches every entry with a uid, e.g. for finding the next available UID.
+const UID_SEARCH_FILTER: &str = "(uidNumber=*)";
+/// Result code of LDAP if the server returns no more entries than its size limit.
+const SIZE_LIMIT_EXCEEDED: u32 = 4;
+
 static SORTED_LDAP_LISTING_ATTRIBUTES: Lazy<Vec<&str>> = Lazy::new(|| {
     // Make sure the keys are sorted alphabetic
     // This way the order fields in the final output deterministic
@@ -526,3 +772,10 @@ static SORTED_LDAP_LISTING_ATTRIBUTES: Lazy<Vec<&str>> = Lazy::new(|| {
     to_sort.sort();
     to_sort
 });
+
+static SORTED_LDAP_LISTING_ATTRIBUTES_WITH_GID: Lazy<Vec<&str>> = Lazy::new(|| {
+    let mut to_sort = SORTED_LDAP_LISTING_ATTRIBUTES.clone();
+    to_sort.push("gidNumber");
+    to_sort.sort();
+    to_sort
+});
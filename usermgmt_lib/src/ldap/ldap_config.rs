@@ -2,13 +2,16 @@ use log::info;
 
 use crate::{prelude::AppResult, MgmtConfig};
 
-use super::{ldap_paths::LdapPaths, LdapCredential};
+use super::{ldap_paths::LdapPaths, LdapCredential, LdapSearchScope};
 #[derive(Debug, Default)]
 /// Contains all information for creating/deleting and modifying an user aka writing actions
 pub struct LDAPConfig<T> {
     ldap_server: String,
     ldap_credentials: T,
     ldap_paths: LdapPaths,
+    search_scope: LdapSearchScope,
+    search_filter: String,
+    page_size: u32,
 }
 
 impl<T> LDAPConfig<T>
@@ -48,6 +51,9 @@ where
             ldap_paths,
             ldap_credentials: credentials,
             ldap_server,
+            search_scope: config.ldap_search_scope,
+            search_filter: config.ldap_search_filter.clone(),
+            page_size: config.ldap_page_size,
         })
     }
 
@@ -75,6 +81,9 @@ where
             ldap_server: ldap_server.to_string(),
             ldap_credentials: credentials,
             ldap_paths,
+            search_scope: config.ldap_search_scope,
+            search_filter: config.ldap_search_filter.clone(),
+            page_size: config.ldap_page_size,
         })
     }
 
@@ -94,6 +103,16 @@ where
     pub fn ldap_server(&self) -> &str {
         &self.ldap_server
     }
+    pub fn search_scope(&self) -> LdapSearchScope {
+        self.search_scope
+    }
+    /// Filter which every listed user entry has to match.
+    pub fn search_filter(&self) -> &str {
+        &self.search_filter
+    }
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
 }
 
 #[cfg(test)]
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use ldap3::{SearchEntry, SearchResult};
 use log::warn;
 
+use crate::{config::MgmtConfig, util::ResolvedGid};
+
 /// Table with depth 3:
 /// 1st: Rows of table
 /// 2st: columns of a row
@@ -81,6 +83,18 @@ impl LdapSearchResult {
         Self::new(header, map)
     }
 
+    /// Rows which have an uid, i.e. the rows of users.
+    pub fn user_rows(&self) -> Vec<LdapUserRow<'_>> {
+        self.fields
+            .iter()
+            .map(|cells| LdapUserRow {
+                header: &self.header,
+                cells,
+            })
+            .filter(|row| row.first("uid").is_some())
+            .collect()
+    }
+
     pub fn headers(&self) -> Vec<&str> {
         self.header.iter().map(|string| string.as_str()).collect()
     }
@@ -97,3 +111,34 @@ impl LdapSearchResult {
             .collect()
     }
 }
+
+/// Values of a LDAP user by the name of their attribute.
+#[derive(Debug, Clone, Copy)]
+pub struct LdapUserRow<'a> {
+    header: &'a [String],
+    cells: &'a [Vec<String>],
+}
+
+impl<'a> LdapUserRow<'a> {
+    /// All values of the attribute. Empty if the user has no value for it.
+    pub fn values(&self, attribute: &str) -> Vec<String> {
+        self.header
+            .iter()
+            .position(|header| header == attribute)
+            .and_then(|index| self.cells.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn first(&self, attribute: &str) -> Option<String> {
+        self.values(attribute).into_iter().next()
+    }
+
+    /// Name of the group whose gid in `config` is the gid of the user.
+    pub fn group(&self, config: &MgmtConfig) -> Option<String> {
+        self.first("gidNumber")
+            .and_then(|gid| gid.parse::<i32>().ok())
+            .and_then(|gid| ResolvedGid::from_gid(gid, config))
+            .map(|resolved| resolved.id().to_string())
+    }
+}
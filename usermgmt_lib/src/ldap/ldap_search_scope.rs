@@ -0,0 +1,22 @@
+use ldap3::Scope;
+use serde::{Deserialize, Serialize};
+
+/// Depth of LDAP searches below the base of the configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LdapSearchScope {
+    /// Only the entries directly below the base.
+    #[default]
+    OneLevel,
+    /// All entries below the base including the ones in nested organizational units.
+    Subtree,
+}
+
+impl From<LdapSearchScope> for Scope {
+    fn from(value: LdapSearchScope) -> Self {
+        match value {
+            LdapSearchScope::OneLevel => Scope::OneLevel,
+            LdapSearchScope::Subtree => Scope::Subtree,
+        }
+    }
+}
@@ -127,7 +127,7 @@ fn should_produce_simple_output() {
     let given_search_entries = vec!["qos", "name"];
     let ldap_search_result = LdapSearchResult::new(given_search_entries, given_entries);
     let actual = text_list_output::ldap_simple_output(&ldap_search_result);
-    insta::assert_display_snapshot!(actual);
+    insta::assert_snapshot!(actual);
 }
 
 #[test]
@@ -146,5 +146,70 @@ fn should_produce_table_from_ldap_search() {
     let given_search_entries = vec!["qos", "name"];
     let ldap_search_result = LdapSearchResult::new(given_search_entries, given_entries);
     let actual = text_list_output::ldap_search_to_pretty_table(&ldap_search_result);
-    insta::assert_display_snapshot!(actual);
+    insta::assert_snapshot!(actual);
+}
+
+#[test]
+fn escape_username_in_filter_and_dn() {
+    let base = "ou=people,dc=example,dc=com";
+    let actual: Vec<(String, String)> = [
+        "somebody",
+        "*",
+        "admin)(uid=*",
+        "a\\b",
+        "a,ou=admins",
+        " #x+y ",
+    ]
+    .into_iter()
+    .map(|username| (uid_filter(username), user_dn(username, base)))
+    .collect();
+
+    insta::assert_yaml_snapshot!(actual);
+}
+
+#[test]
+fn act_only_on_exactly_one_entry() {
+    assert_eq!(None, single_entry(Vec::<&str>::new(), "somebody").unwrap());
+    assert_eq!(Some("one"), single_entry(vec!["one"], "somebody").unwrap());
+    assert!(single_entry(vec!["one", "two"], "somebody").is_err());
+}
+
+#[test]
+fn combine_search_filter_with_other_filters() {
+    assert_eq!(
+        "(uidNumber=*)",
+        combine_filters(ALL_ENTRIES_FILTER, "(uidNumber=*)")
+    );
+    assert_eq!(
+        "(objectClass=posixAccount)",
+        combine_filters("(objectClass=posixAccount)", ALL_ENTRIES_FILTER)
+    );
+    assert_eq!(
+        "(&(objectClass=posixAccount)(uidNumber=*))",
+        combine_filters("(objectClass=posixAccount)", "(uidNumber=*)")
+    );
+}
+
+#[test]
+fn find_next_uid_beyond_entries_hidden_by_search_filter() {
+    let config = LDAPConfig::new(
+        &MgmtConfig {
+            ldap_search_filter: "(objectClass=slurmRole)".to_owned(),
+            ..MgmtConfig::default()
+        },
+        LdapSimpleCredential::new("xxx".to_owned(), "xxxx".to_owned()),
+    )
+    .unwrap();
+    // The entry with the highest uid has no slurmRole and is hidden by the search filter.
+    let search_uids = |filter: &str| {
+        if filter.contains("slurmRole") {
+            Ok(vec![10001, 10002])
+        } else {
+            Ok(vec![10001, 10002, 10005])
+        }
+    };
+
+    let actual = find_next_uid_with(&config, crate::Group::Student, search_uids).unwrap();
+
+    assert_eq!(10006, actual);
 }
@@ -1,4 +1,5 @@
 #![deny(clippy::unwrap_used)]
+#![cfg_attr(test, allow(clippy::unwrap_used))]
 #![forbid(unsafe_code)]
 
 pub use entity::Entity;
@@ -10,11 +11,15 @@ pub mod cli;
 pub mod config;
 pub mod constants;
 pub mod dir;
+pub mod doctor;
 pub mod entity;
+pub mod executor;
 pub mod ldap;
 pub mod logging;
 pub mod new_entity;
 pub mod operations;
+pub mod output;
+pub mod report;
 pub mod slurm;
 pub mod ssh;
 pub mod util;
@@ -38,8 +43,6 @@ pub mod prelude {
 pub mod app_panic_hook;
 mod group;
 
-extern crate confy;
-
 /// Removes all invalid elements of `qos`. An element is valid if `valid_qos` contains it.
 /// Filters out duplicates too.
 /// Returns an empty vector if `qos` or `valid_qos` is empty.
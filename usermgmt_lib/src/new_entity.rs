@@ -1,9 +1,11 @@
+use anyhow::ensure;
 use log::warn;
 
 use crate::{
     cli::UserToAdd,
     config::MgmtConfig,
     prelude::AppResult,
+    slurm::AssociationLimits,
     util::{ResolvedGid, TrimmedNonEmptyText, ValidGroupOfQos, ValidQos},
     Entity, Group,
 };
@@ -18,6 +20,16 @@ pub struct NewEntity {
     pub default_qos: ValidQos,
     pub publickey: Option<TrimmedNonEmptyText>,
     pub qos: ValidGroupOfQos,
+    /// Slurm accounts of the user. Contains at least one account.
+    pub accounts: Vec<String>,
+    /// Explicit default slurm account. It is one of the field `accounts`.
+    pub default_account: Option<String>,
+    /// Limits of the group from the configuration overridden by the limits given for the user.
+    pub limits: AssociationLimits,
+    /// Slurm clusters of the user. Empty for the default cluster of slurm.
+    pub clusters: Vec<String>,
+    /// If true, the user becomes a coordinator of its default account.
+    pub coordinator: bool,
 }
 
 impl NewEntity {
@@ -44,6 +56,9 @@ impl NewEntity {
             .map(Ok)
             .unwrap_or_else(|| ValidGroupOfQos::from_group(group.id(), config))?;
 
+        let limits = AssociationLimits::from_group(group.id(), config).overridden_by(entity.limits);
+        let clusters = config.clusters_of_group(group.id());
+
         if publickey.is_none() {
             warn!("No public key was supplied for new user. Remember to add it later via modification");
         }
@@ -51,6 +66,11 @@ impl NewEntity {
         Ok(Self {
             username: entity.username,
             default_qos,
+            accounts: vec![group.id().to_string()],
+            default_account: None,
+            limits,
+            clusters,
+            coordinator: false,
             group,
             firstname,
             lastname,
@@ -62,11 +82,75 @@ impl NewEntity {
 
     /// # Errors
     ///
-    /// - If an user entity could not be created. See [`Entity::new`]
+    /// - If an user entity could not be created. See [`Entity::new_addition`]
+    /// - If the accounts could not be set. See [`NewEntity::with_accounts`]
+    /// - If the clusters are not part of the configured clusters. See [`MgmtConfig::ensure_known_clusters`]
     pub fn new_user_addition_conf(to_add: UserToAdd, conf: &MgmtConfig) -> AppResult<Self> {
-        let (firstname, lastname, common_user_fields) = to_add.into();
-        let (firstname, lastname) = (Some(firstname), Some(lastname));
-        let entity = Entity::new(firstname, lastname, common_user_fields, conf)?;
-        Self::new(entity, conf)
+        let UserToAdd {
+            username,
+            firstname,
+            lastname,
+            common_user_fields,
+            accounts,
+            default_account,
+            partitions,
+            clusters,
+            coordinator,
+        } = to_add;
+        let entity = Entity::new_addition(username, firstname, lastname, common_user_fields, conf)?;
+        let mut new_entity = Self::new(entity, conf)?.with_accounts(accounts, default_account)?;
+        if !partitions.is_empty() {
+            new_entity.limits.partitions = partitions.into_iter().map(Into::into).collect();
+        }
+        if !clusters.is_empty() {
+            new_entity.clusters = clusters.into_iter().map(Into::into).collect();
+        }
+        conf.ensure_known_clusters(&new_entity.clusters)?;
+        new_entity.coordinator = coordinator;
+        Ok(new_entity)
+    }
+
+    /// Account the user coordinates if the user is a coordinator.
+    /// It is the explicit default account or otherwise the first account like slurm chooses it.
+    pub fn coordinated_account(&self) -> Option<&str> {
+        if !self.coordinator {
+            return None;
+        }
+        self.default_account
+            .as_deref()
+            .or_else(|| self.accounts.first().map(String::as_str))
+    }
+
+    /// Sets the slurm accounts of the user.
+    /// Without any given account, the user stays in the account named after its group
+    /// or is only put into the given default account.
+    ///
+    /// # Errors
+    ///
+    /// - If the default account is not one of the given accounts.
+    pub fn with_accounts(
+        mut self,
+        accounts: Vec<TrimmedNonEmptyText>,
+        default_account: Option<TrimmedNonEmptyText>,
+    ) -> AppResult<Self> {
+        let default_account: Option<String> = default_account.map(Into::into);
+        let mut accounts: Vec<String> = accounts.into_iter().map(Into::into).collect();
+        if accounts.is_empty() {
+            if let Some(default_account) = &default_account {
+                accounts = vec![default_account.clone()];
+            }
+        } else if let Some(default_account) = &default_account {
+            ensure!(
+                accounts.contains(default_account),
+                "Default account ({}) is not one of the accounts ({:?})",
+                default_account,
+                accounts
+            );
+        }
+        if !accounts.is_empty() {
+            self.accounts = accounts;
+        }
+        self.default_account = default_account;
+        Ok(self)
     }
 }
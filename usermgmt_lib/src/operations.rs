@@ -1,19 +1,33 @@
-use log::debug;
+use anyhow::{bail, Context};
+use log::{debug, info};
 
 use crate::{
-    cli::{OnWhichSystem, UserToAdd},
+    cli::{
+        AccountFields, CoordinatorFields, InactiveUsersArgs, ListArgs, OnWhichSystem, QosFields,
+        UsageArgs, UserToAdd,
+    },
     config::MgmtConfig,
     dir,
+    doctor::{self, CheckResult},
+    executor::LocalOrRemoteExecutor,
     ldap::{self, text_list_output, LDAPConfig, LdapCredential, LdapSession},
-    slurm,
+    output::{self, ListedUser, OutputFormat, UserAction, UserChange},
+    report,
+    slurm::{self, QosCache},
     ssh::{SshConnection, SshCredentials},
-    AppResult, ChangesToUser, NewEntity,
+    util::{self, TrimmedNonEmptyText, UsernamePolicy, UsernameTemplate},
+    AppResult, ChangesToUser, Group, NewEntity,
 };
 
+/// Adds an user on the systems selected by parameter `on_which_sys`.
+/// If no username is given, it is derived from the first and last name via the
+/// `username_template` of parameter `config`. See [`generate_username`].
+///
 /// # Errors
 ///
+/// - If no username is given and none could be generated. See [`generate_username`].
 /// - If the attributes of the parameter `to_add` is not compatible with fields of
-/// parameter `config`. See [`NewEntity::new_user_addition_conf`].
+///   parameter `config`. See [`NewEntity::new_user_addition_conf`].
 /// - If the execution of adding an users fails. See [`perform_action_on_context`].
 pub fn add_user<T, C>(
     to_add: UserToAdd,
@@ -21,14 +35,27 @@ pub fn add_user<T, C>(
     config: &MgmtConfig,
     ldap_credentials: T,
     ssh_credentials: C,
-) -> AppResult
+) -> AppResult<UserChange>
 where
     T: LdapCredential + Clone,
     C: SshCredentials + Clone,
 {
     debug!("Start adding user");
 
+    let to_add = if to_add.username.is_none() {
+        let username = generate_username(
+            &to_add,
+            on_which_sys,
+            config,
+            &ldap_credentials,
+            &ssh_credentials,
+        )?;
+        to_add.with_username(username)
+    } else {
+        to_add
+    };
     let entity = NewEntity::new_user_addition_conf(to_add, config)?;
+    let qos_cache = QosCache::default();
 
     perform_action_on_context(
         on_which_sys,
@@ -36,30 +63,126 @@ where
         ldap_credentials.clone(),
         &ssh_credentials,
         |session| ldap::add_ldap_user(&entity, config, session),
-        |ssh_con| slurm::add_slurm_user(&entity, config, ssh_con),
+        |executor| slurm::add_slurm_user(&entity, config, executor, &qos_cache),
         |_| dir::add_user_directories(&entity, config, &ssh_credentials),
     )?;
 
     debug!("Finished add_user");
 
-    Ok(())
+    let on_slurm = on_which_sys.slurm();
+    Ok(
+        UserChange::new(UserAction::Add, entity.username.to_string(), on_which_sys).with_clusters(
+            if on_slurm {
+                entity.clusters
+            } else {
+                Vec::new()
+            },
+        ),
+    )
+}
+
+/// Derives a username from the first and last name of parameter `to_add`
+/// via the `username_template` of parameter `config`.
+/// A numeric suffix is appended as long as the username violates the username policy
+/// or is already taken in LDAP or Slurm.
+/// Only the systems selected by parameter `on_which_sys` are checked.
+///
+/// # Errors
+///
+/// - If no `username_template` is configured or the template is not valid.
+///   See [`UsernameTemplate::new`].
+/// - If the username policy could not be created. See [`UsernamePolicy::from_config`].
+/// - If checking for an existing user in LDAP or Slurm fails.
+/// - If no available username could be found. See [`UsernameTemplate::generate`].
+pub fn generate_username<T, C>(
+    to_add: &UserToAdd,
+    on_which_sys: &OnWhichSystem,
+    config: &MgmtConfig,
+    ldap_credentials: &T,
+    ssh_credentials: &C,
+) -> AppResult<TrimmedNonEmptyText>
+where
+    T: LdapCredential + Clone,
+    C: SshCredentials + Clone,
+{
+    let template = config
+        .username_template
+        .as_deref()
+        .context("No username given and no username_template configured to derive one")?;
+    let template = UsernameTemplate::new(template)?;
+    let policy = UsernamePolicy::from_config(config)?;
+    let ldap_config = if on_which_sys.ldap() {
+        Some(LDAPConfig::new(config, ldap_credentials.clone())?)
+    } else {
+        None
+    };
+    let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
+    let slurm_executor = slurm::executor_from_config(config, &ssh_session);
+
+    let username = template.generate(
+        to_add.firstname().as_ref(),
+        to_add.lastname().as_ref(),
+        policy.max_length(),
+        |candidate| {
+            if let Err(violation) = policy.check(candidate) {
+                debug!("{}", violation);
+                return Ok(false);
+            }
+            if let Some(ldap_config) = &ldap_config {
+                if ldap::username_exists(candidate, ldap_config)? {
+                    return Ok(false);
+                }
+            }
+            if on_which_sys.slurm() && slurm::user_exists(candidate, config, &slurm_executor)? {
+                return Ok(false);
+            }
+            Ok(true)
+        },
+    )?;
+
+    username.try_into()
 }
 
+/// With parameter `clusters` the user is only removed from these Slurm clusters
+/// and is kept in LDAP, since the user is still on the other clusters.
+///
 /// # Errors
 ///
+/// - If the username is empty or only white spaces.
 /// - If the execution of deleting an users fails. See [`perform_action_on_context`].
 pub fn delete_user<T, C>(
     user: &str,
+    clusters: &[String],
     on_which_sys: &OnWhichSystem,
     config: &MgmtConfig,
     ldap_credentials: T,
     credentials: C,
-) -> AppResult
+) -> AppResult<UserChange>
 where
     T: LdapCredential,
     C: SshCredentials,
 {
     debug!("Start delete_user");
+    let user = TrimmedNonEmptyText::try_from(user).context("Username of user to delete")?;
+    let user = user.as_ref().as_str();
+
+    let only_from_clusters;
+    let on_which_sys = if !clusters.is_empty() && on_which_sys.ldap() {
+        info!(
+            "User {} is kept in LDAP since it is only removed from the clusters {}",
+            user,
+            clusters.join(",")
+        );
+        only_from_clusters = OnWhichSystem::new(
+            on_which_sys.slurm(),
+            false,
+            on_which_sys.dirs(),
+            on_which_sys.ssh_path().clone(),
+        );
+        &only_from_clusters
+    } else {
+        on_which_sys
+    };
 
     perform_action_context_no_dirs(
         on_which_sys,
@@ -68,11 +191,14 @@ where
         &credentials,
         false,
         |ldap_session| ldap::delete_ldap_user(user, ldap_session),
-        |ssh_connection| slurm::delete_slurm_user(user, config, ssh_connection),
+        |executor| slurm::delete_slurm_user(user, clusters, config, executor),
     )?;
 
     debug!("Finished delete_user");
-    Ok(())
+    Ok(
+        UserChange::new(UserAction::Delete, user.to_owned(), on_which_sys)
+            .with_clusters(clusters.to_vec()),
+    )
 }
 
 /// # Errors
@@ -84,12 +210,13 @@ pub fn modify_user<T, C>(
     config: &MgmtConfig,
     ldap_credentials: T,
     credential: C,
-) -> AppResult
+) -> AppResult<UserChange>
 where
     C: SshCredentials,
     T: LdapCredential,
 {
     debug!("Start modify_user for {}", modifiable.username);
+    let qos_cache = QosCache::default();
 
     perform_action_context_no_dirs(
         on_which_sys,
@@ -98,22 +225,38 @@ where
         &credential,
         false,
         |ldap_session| ldap::modify_ldap_user(&modifiable, ldap_session),
-        |ssh_connection| slurm::modify_slurm_user(&modifiable, config, ssh_connection),
+        |executor| slurm::modify_slurm_user(&modifiable, config, executor, &qos_cache),
     )?;
 
     debug!("Finished modify_user");
-    Ok(())
+    Ok(UserChange::new(
+        UserAction::Modify,
+        modifiable.username.to_string(),
+        on_which_sys,
+    ))
 }
 
+/// Without parameter `output` the users of LDAP and Slurm are printed as separate tables.
+/// With it the users of LDAP and Slurm are merged per user and printed in this format.
+/// See [`ListedUser`].
+///
+/// The filters of parameter `query` are applied in the LDAP search and on the associations of Slurm.
+/// Sorting, selecting columns or paging also merges the users of LDAP and Slurm per user.
+///
 /// # Errors
 ///
 /// - If the execution of listing an users fails. See [`perform_action_on_context`].
-/// - If the execution of the slurm command fails. See [`slurm::list_users`].
-/// - If the execution of the LDAP command fails. See [`ldap::list_ldap_users`].
+/// - If the execution of the slurm command fails. See [`slurm::list_associations`].
+/// - If the users of the group to filter by could not be fetched from LDAP.
+/// - If the execution of the LDAP command fails. See [`ldap::list_ldap_users_with_filter`]
+///   and [`ldap::list_ldap_users_with_gid`].
+/// - If the users could not be printed in the chosen format. See [`output::render_listed_users`].
 pub fn print_list_of_users_to_stdout<T, C>(
     config: &MgmtConfig,
     on_which_sys: &OnWhichSystem,
     simple_output_ldap: bool,
+    query: &ListArgs,
+    output: Option<OutputFormat>,
     ldap_credentials: T,
     credentials: C,
 ) -> AppResult
@@ -121,6 +264,47 @@ where
     T: LdapCredential,
     C: SshCredentials,
 {
+    let ldap_filter = output::ldap_filter_of(query, config);
+    let group_credentials = ldap_credentials.clone();
+    let list_associations = |executor: &LocalOrRemoteExecutor<SshConnection<C>>| {
+        let group_members = query
+            .group
+            .map(|group| usernames_of_group(group, config, group_credentials.clone()))
+            .transpose()?;
+        let mut associations = slurm::list_associations(config, executor)?;
+        associations.retain(|association| {
+            output::association_matches(query, group_members.as_deref(), association)
+        });
+        AppResult::Ok(associations)
+    };
+
+    if output.is_some() || query.needs_merged_users() {
+        let (mut ldap_users, mut associations) = (None, Vec::new());
+        perform_action_context_no_dirs(
+            on_which_sys,
+            config,
+            ldap_credentials.clone(),
+            &credentials,
+            true,
+            |_ldap_session| {
+                let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
+                ldap_users = Some(ldap::list_ldap_users_with_gid(ldap_config, &ldap_filter)?);
+                Ok(())
+            },
+            |executor| {
+                associations = list_associations(executor)?;
+                Ok(())
+            },
+        )?;
+        let listed = ListedUser::merge(ldap_users.as_ref(), &associations, config);
+        let listed = output::sort_and_page(query, output::filter_merged_users(query, listed));
+        println!(
+            "{}",
+            output::render_listed_users(&listed, &query.columns, output.unwrap_or_default())?
+        );
+        return Ok(());
+    }
+
     perform_action_context_no_dirs(
         on_which_sys,
         config,
@@ -129,7 +313,7 @@ where
         true,
         |_ldap_session| {
             let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
-            let search_result_data = ldap::list_ldap_users(ldap_config)?;
+            let search_result_data = ldap::list_ldap_users_with_filter(ldap_config, &ldap_filter)?;
 
             let output = if simple_output_ldap {
                 text_list_output::ldap_simple_output(&search_result_data)
@@ -139,9 +323,9 @@ where
             println!("{}", &output);
             Ok(())
         },
-        |ssh_connection| {
-            let output = slurm::list_users(config, ssh_connection, false)?;
-            println!("{}", output);
+        |executor| {
+            let associations = list_associations(executor)?;
+            println!("{}", slurm::associations_to_pretty_table(&associations));
             Ok(())
         },
     )?;
@@ -149,12 +333,404 @@ where
     Ok(())
 }
 
+/// Usernames of the users with the given group in LDAP.
+/// Slurm does not know the group of a user, so the associations in Slurm are filtered by them.
+fn usernames_of_group<T>(
+    group: Group,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+) -> AppResult<Vec<String>>
+where
+    T: LdapCredential,
+{
+    let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
+    let found =
+        ldap::list_ldap_users_with_filter(ldap_config, &output::group_ldap_filter(group, config))?;
+    Ok(found
+        .user_rows()
+        .iter()
+        .filter_map(|row| row.first("uid"))
+        .collect())
+}
+
+/// Prints everything known about the user aka parameter `username` on the systems of parameter
+/// `on_which_sys` together with the inconsistencies between these systems.
+/// Without parameter `output` the user is printed as text for humans.
+///
+/// # Errors
+///
+/// - If the username is empty or only white spaces.
+/// - If fetching the user from LDAP fails. See [`ldap::find_ldap_user`].
+/// - If listing the associations fails. See [`slurm::list_associations_of_user`].
+/// - If inspecting the directories fails. See [`dir::inspect_user_directories_with`].
+/// - If the user is found neither in LDAP nor in Slurm.
+/// - If the user could not be printed in the chosen format. See [`output::render_document`].
+pub fn print_user_details_to_stdout<T, C>(
+    username: &str,
+    on_which_sys: &OnWhichSystem,
+    output: Option<OutputFormat>,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    credentials: C,
+) -> AppResult
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    let username = TrimmedNonEmptyText::try_from(username).context("Username of user to show")?;
+    let username = username.as_ref().as_str();
+
+    let ldap = if on_which_sys.ldap() {
+        let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
+        let found = ldap::find_ldap_user(username, &ldap_config)?;
+        let details = found
+            .user_rows()
+            .first()
+            .map(|row| report::LdapDetails::from_row(row, config));
+        details
+    } else {
+        None
+    };
+
+    let ssh_session = SshConnection::from_head_node(config, credentials.clone());
+    let associations = if on_which_sys.slurm() {
+        slurm::list_associations_of_user(
+            username,
+            config,
+            &slurm::executor_from_config(config, &ssh_session),
+        )?
+    } else {
+        Vec::new()
+    };
+
+    let directories = if on_which_sys.dirs() {
+        let group = ldap
+            .as_ref()
+            .and_then(|ldap| ldap.gid_number)
+            .and_then(|gid| i32::try_from(gid).ok())
+            .and_then(|gid| util::ResolvedGid::from_gid(gid, config))
+            .map(|resolved| resolved.id());
+        dir::inspect_user_directories_with(username, group, config, |host| {
+            SshConnection::new(host, config, credentials.clone())
+        })?
+    } else {
+        Vec::new()
+    };
+
+    let details = report::UserDetails::new(
+        username.to_owned(),
+        report::InspectedSystems {
+            ldap: on_which_sys.ldap(),
+            slurm: on_which_sys.slurm(),
+            directories: on_which_sys.dirs(),
+        },
+        ldap,
+        associations,
+        directories,
+    );
+    if details.is_unknown() && (on_which_sys.ldap() || on_which_sys.slurm()) {
+        bail!("User {} was found neither in LDAP nor in Slurm", username);
+    }
+
+    let rendered = match output {
+        None | Some(OutputFormat::Table) => details.to_pretty_text()?,
+        Some(format) => output::render_document(&details, format)?,
+    };
+    println!("{}", rendered);
+    Ok(())
+}
+
+/// Prints the users in LDAP without a job submission in Slurm within the days of parameter `args`
+/// in the format of parameter `output`.
+/// Job submissions are taken from sacct, which is run like the other slurm commands.
+/// With the option `lastlog` users who logged in on the head node recently are left out.
+///
+/// # Errors
+///
+/// - If listing the users in LDAP fails. See [`ldap::list_ldap_users_with_gid`].
+/// - If sacct or lastlog fails. See [`report::collect_inactive_users`].
+/// - If the users could not be printed in the chosen format. See [`output::render`].
+pub fn print_inactive_users_to_stdout<T, C>(
+    args: &InactiveUsersArgs,
+    output: OutputFormat,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    credentials: C,
+) -> AppResult
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
+    let ldap_users = ldap::list_ldap_users_with_gid(ldap_config, ldap::ALL_ENTRIES_FILTER)?;
+
+    let ssh_session = SshConnection::from_head_node(config, credentials);
+    let inactive_users = report::collect_inactive_users(
+        &ldap_users,
+        args.days,
+        args.lookback_days,
+        config,
+        &slurm::executor_from_config(config, &ssh_session),
+        args.lastlog.then_some(&ssh_session),
+    )?;
+
+    if args.names_only {
+        for user in inactive_users {
+            println!("{}", user.username);
+        }
+    } else {
+        println!("{}", output::render(&inactive_users, output)?);
+    }
+    Ok(())
+}
+
+/// Prints the CPU and GPU hours per user within the time window of parameter `args`
+/// together with their names and mail from LDAP in the format of parameter `output`.
+/// Without a time window the previous month is used.
+/// With the option `by_group` the hours are summed up per group instead.
+///
+/// # Errors
+///
+/// - If listing the users in LDAP fails. See [`ldap::list_ldap_users_with_gid`].
+/// - If sreport fails. See [`report::collect_usage`].
+/// - If the usage could not be printed in the chosen format. See [`output::render`].
+pub fn print_usage_to_stdout<T, C>(
+    args: &UsageArgs,
+    output: OutputFormat,
+    config: &MgmtConfig,
+    ldap_credentials: T,
+    credentials: C,
+) -> AppResult
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    let (start, end) = match (args.start, args.end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => report::last_month(chrono::Local::now().date_naive()),
+    };
+    info!("Usage from {} until {}", start, end);
+
+    let ldap_config = LDAPConfig::new_readonly(config, ldap_credentials)?;
+    let ldap_users = ldap::list_ldap_users_with_gid(ldap_config, ldap::ALL_ENTRIES_FILTER)?;
+
+    let ssh_session = SshConnection::from_head_node(config, credentials);
+    let usage = report::collect_usage(
+        &ldap_users,
+        start,
+        end,
+        config,
+        &slurm::executor_from_config(config, &ssh_session),
+    )?;
+
+    let rendered = if args.by_group {
+        output::render(&report::summarize_by_group(&usage), output)?
+    } else {
+        output::render(&usage, output)?
+    };
+    println!("{}", rendered);
+    Ok(())
+}
+
+/// Creates a Slurm account with the parent, description and organization from parameter `account`.
+///
+/// # Errors
+///
+/// - If creating the account fails. See [`slurm::add_slurm_account`].
+pub fn add_account<C>(account: AccountFields, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    let name = account.name.to_string();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::add_slurm_account(&name, account.into(), config, executor)
+    })
+}
+
+/// Changes parent, description or organization of a Slurm account.
+///
+/// # Errors
+///
+/// - If modifying the account fails. See [`slurm::modify_slurm_account`].
+pub fn modify_account<C>(account: AccountFields, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    let name = account.name.to_string();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::modify_slurm_account(&name, account.into(), config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If deleting the account fails. See [`slurm::delete_slurm_account`].
+pub fn delete_account<C>(name: &str, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::delete_slurm_account(name, config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If listing the accounts fails. See [`slurm::list_accounts`].
+pub fn print_list_of_accounts_to_stdout<C>(config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    perform_slurm_action(config, credentials, |executor| {
+        let output = slurm::list_accounts(config, executor, false)?;
+        println!("{}", output);
+        Ok(())
+    })
+}
+
+/// # Errors
+///
+/// - If adding the coordinators fails. See [`slurm::add_slurm_coordinators`].
+pub fn add_coordinators<C>(
+    coordinators: CoordinatorFields,
+    config: &MgmtConfig,
+    credentials: C,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let users: Vec<String> = coordinators.users.into_iter().map(Into::into).collect();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::add_slurm_coordinators(coordinators.account.as_ref(), &users, config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If Slurm is managed via slurmrestd which offers no way to remove coordinators.
+/// - If removing the coordinators fails. See [`slurm::delete_slurm_coordinators`].
+pub fn remove_coordinators<C>(
+    coordinators: CoordinatorFields,
+    config: &MgmtConfig,
+    credentials: C,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    if config.use_slurmrestd {
+        bail!(
+            "Coordinators can not be removed via slurmrestd. \
+            Set use_slurmrestd to false to remove them via sacctmgr"
+        );
+    }
+    let users: Vec<String> = coordinators.users.into_iter().map(Into::into).collect();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::delete_slurm_coordinators(coordinators.account.as_ref(), &users, config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If listing the coordinators fails. See [`slurm::list_coordinators`].
+pub fn print_list_of_coordinators_to_stdout<C>(config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    perform_slurm_action(config, credentials, |executor| {
+        let output = slurm::list_coordinators(config, executor, false)?;
+        println!("{}", output);
+        Ok(())
+    })
+}
+
+/// Creates a QOS in Slurm with the priority, limits and preemption settings from parameter `qos`.
+///
+/// # Errors
+///
+/// - If creating the QOS fails. See [`slurm::add_slurm_qos`].
+pub fn add_qos<C>(qos: QosFields, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    let name = qos.name.to_string();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::add_slurm_qos(&name, qos.into(), config, executor)
+    })
+}
+
+/// Changes priority, limits or preemption settings of a QOS in Slurm.
+///
+/// # Errors
+///
+/// - If modifying the QOS fails. See [`slurm::modify_slurm_qos`].
+pub fn modify_qos<C>(qos: QosFields, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    let name = qos.name.to_string();
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::modify_slurm_qos(&name, qos.into(), config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If deleting the QOS fails. See [`slurm::delete_slurm_qos`].
+pub fn delete_qos<C>(name: &str, config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    perform_slurm_action(config, credentials, |executor| {
+        slurm::delete_slurm_qos(name, config, executor)
+    })
+}
+
+/// # Errors
+///
+/// - If listing the QOS fails. See [`slurm::list_qos`].
+pub fn print_list_of_qos_to_stdout<C>(config: &MgmtConfig, credentials: C) -> AppResult
+where
+    C: SshCredentials,
+{
+    perform_slurm_action(config, credentials, |executor| {
+        let output = slurm::list_qos(config, executor, false)?;
+        println!("{}", output);
+        Ok(())
+    })
+}
+
+/// Performs an action on Slurm only.
+/// The action receives the executor chosen by the configuration.
+/// See [`slurm::executor_from_config`].
+///
+/// # Errors
+///
+/// - If establishing the ssh connection fails
+/// - If the action `on_slurm_action` fails.
+fn perform_slurm_action<C>(
+    config: &MgmtConfig,
+    ssh_credentials: C,
+    on_slurm_action: impl FnOnce(&LocalOrRemoteExecutor<SshConnection<C>>) -> AppResult,
+) -> AppResult
+where
+    C: SshCredentials,
+{
+    let ssh_session = SshConnection::from_head_node(config, ssh_credentials);
+    if config.run_slurm_remote && !config.use_slurmrestd {
+        ssh_session.establish_connection()?;
+    }
+    on_slurm_action(&slurm::executor_from_config(config, &ssh_session))
+}
+
 /// Performs an action on all the three systems on the cluster.
 ///
 /// - LDAP
 /// - Slurm
 /// - Directory management
 ///
+/// The slurm action receives the executor chosen by the configuration.
+/// See [`slurm::executor_from_config`].
+///
 /// # Errors
 ///
 /// - If getting of credentials for LDAP fails. See [`LdapSession::new`]
@@ -166,7 +742,7 @@ pub fn perform_action_on_context<T, C>(
     ldap_credentials: T,
     ssh_credentials: &C,
     on_ldap_action: impl FnOnce(&mut LdapSession<T>) -> AppResult,
-    on_slurm_action: impl FnOnce(&SshConnection<C>) -> AppResult,
+    on_slurm_action: impl FnOnce(&LocalOrRemoteExecutor<SshConnection<C>>) -> AppResult,
     on_dir_action: impl FnOnce(&SshConnection<C>) -> AppResult,
 ) -> AppResult
 where
@@ -176,7 +752,7 @@ where
     let ssh_session = SshConnection::from_head_node(config, ssh_credentials.clone());
     let mut ldap_session = LdapSession::new(config, ldap_credentials)?;
 
-    if on_which_sys.slurm() {
+    if on_which_sys.slurm() && !config.use_slurmrestd {
         ssh_session.establish_connection()?;
     }
 
@@ -186,7 +762,7 @@ where
     }
 
     if on_which_sys.slurm() {
-        on_slurm_action(&ssh_session)?;
+        on_slurm_action(&slurm::executor_from_config(config, &ssh_session))?;
     }
 
     if on_which_sys.dirs() {
@@ -204,7 +780,7 @@ fn perform_action_context_no_dirs<T, C>(
     ssh_credentials: &C,
     readonly: bool,
     on_ldap_action: impl FnOnce(&mut LdapSession<T>) -> AppResult,
-    on_slurm_action: impl FnOnce(&SshConnection<C>) -> AppResult,
+    on_slurm_action: impl FnOnce(&LocalOrRemoteExecutor<SshConnection<C>>) -> AppResult,
 ) -> AppResult
 where
     T: LdapCredential,
@@ -217,7 +793,7 @@ where
         LdapSession::new(config, ldap_credentials)?
     };
 
-    if on_which_sys.slurm() {
+    if on_which_sys.slurm() && !config.use_slurmrestd {
         ssh_session.establish_connection()?;
     }
 
@@ -227,8 +803,32 @@ where
     }
 
     if on_which_sys.slurm() {
-        on_slurm_action(&ssh_session)?;
+        on_slurm_action(&slurm::executor_from_config(config, &ssh_session))?;
     }
 
     Ok(())
 }
+
+/// Checks the connections to LDAP, Slurm and the hosts of the systems chosen in `on_which_sys`.
+/// Failed checks are part of the result and not an error.
+/// See [`doctor::check_ldap`] and [`doctor::check_hosts`].
+pub fn check_connections<T, C>(
+    config: &MgmtConfig,
+    on_which_sys: &OnWhichSystem,
+    ldap_credentials: T,
+    credentials: C,
+) -> Vec<CheckResult>
+where
+    T: LdapCredential,
+    C: SshCredentials,
+{
+    let mut results = if on_which_sys.ldap() {
+        doctor::check_ldap(config, ldap_credentials)
+    } else {
+        Vec::new()
+    };
+    results.extend(doctor::check_hosts(config, on_which_sys, |host| {
+        SshConnection::new(host, config, credentials.clone())
+    }));
+    results
+}
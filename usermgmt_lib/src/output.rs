@@ -0,0 +1,171 @@
+//! Rendering of listed entities as a table for humans or as CSV, JSON or YAML for other tools.
+
+mod list_query;
+mod listed_user;
+mod user_change;
+
+pub use list_query::{
+    association_matches, filter_merged_users, group_ldap_filter, ldap_filter_of,
+    render_listed_users, sort_and_page,
+};
+pub use listed_user::{ListColumn, ListedUser};
+pub use user_change::{UserAction, UserChange};
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use prettytable::{Cell, Row, Table};
+use serde::Serialize;
+
+use crate::prelude::AppResult;
+
+/// Format in which listed entities are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned table for humans.
+    #[default]
+    Table,
+    /// Comma separated values with a header row.
+    Csv,
+    /// JSON array with an object per entity.
+    Json,
+    /// YAML sequence with a mapping per entity.
+    Yaml,
+}
+
+/// Entity which can be printed as a row of a table.
+/// The JSON and YAML output is made from its implementation of [`Serialize`].
+pub trait TableRow: Serialize {
+    /// Titles of the columns in the order of the cells from [`TableRow::cells`].
+    fn headers() -> Vec<&'static str>;
+    /// Cells of the row. Missing values are empty cells.
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Renders all given entities in the given format.
+///
+/// # Errors
+///
+/// - If the entities could not be turned into CSV, JSON or YAML.
+pub fn render<T>(rows: &[T], format: OutputFormat) -> AppResult<String>
+where
+    T: TableRow,
+{
+    match format {
+        OutputFormat::Table | OutputFormat::Csv => render_cells(
+            T::headers(),
+            rows.iter().map(TableRow::cells).collect(),
+            format,
+        ),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(rows).context("Could not write rows as JSON")
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(rows).context("Could not write rows as YAML"),
+    }
+}
+
+/// Renders rows of cells below the given headers as table or CSV.
+///
+/// # Errors
+///
+/// - If another format is chosen since cells have no field names for JSON or YAML.
+/// - If the cells could not be turned into CSV.
+pub fn render_cells(
+    headers: Vec<&str>,
+    rows: Vec<Vec<String>>,
+    format: OutputFormat,
+) -> AppResult<String> {
+    let mut table = Table::new();
+    table.set_titles(Row::new(headers.into_iter().map(Cell::new).collect()));
+    for row in rows {
+        table.add_row(Row::new(row.iter().map(|cell| Cell::new(cell)).collect()));
+    }
+    match format {
+        OutputFormat::Table => Ok(table.to_string()),
+        OutputFormat::Csv => {
+            let writer = table
+                .to_csv(Vec::new())
+                .context("Could not write rows as CSV")?;
+            let bytes = writer.into_inner().context("Could not write rows as CSV")?;
+            String::from_utf8(bytes).context("CSV output is not valid UTF-8")
+        }
+        OutputFormat::Json | OutputFormat::Yaml => bail!(
+            "Output format {} is not supported for cells. Only table and csv are supported",
+            format!("{:?}", format).to_lowercase()
+        ),
+    }
+}
+
+/// Renders a single entity with nested fields as JSON or YAML.
+///
+/// # Errors
+///
+/// - If another format is chosen since the entity does not fit into rows.
+/// - If the entity could not be turned into JSON or YAML.
+pub fn render_document<T>(document: &T, format: OutputFormat) -> AppResult<String>
+where
+    T: Serialize,
+{
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(document).context("Could not write entity as JSON")
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(document).context("Could not write entity as YAML")
+        }
+        OutputFormat::Table | OutputFormat::Csv => bail!(
+            "Output format {} is not supported for a single entity. Only json and yaml are supported",
+            format!("{:?}", format).to_lowercase()
+        ),
+    }
+}
+
+/// For subcommands which only print the text of Slurm as it is.
+///
+/// # Errors
+///
+/// - If a format other than a table is chosen.
+pub fn ensure_table_output(format: Option<OutputFormat>) -> AppResult {
+    match format {
+        None | Some(OutputFormat::Table) => Ok(()),
+        Some(other) => bail!(
+            "Output format {} is not supported by this subcommand. Only table is supported",
+            format!("{:?}", other).to_lowercase()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Listed {
+        name: String,
+        qos: Vec<String>,
+    }
+
+    impl TableRow for Listed {
+        fn headers() -> Vec<&'static str> {
+            vec!["Name", "QOS"]
+        }
+
+        fn cells(&self) -> Vec<String> {
+            vec![self.name.clone(), self.qos.join(",")]
+        }
+    }
+
+    #[test]
+    fn render_csv_json_and_yaml() {
+        let rows = vec![Listed {
+            name: "somebody".to_owned(),
+            qos: vec!["basic".to_owned(), "interactive".to_owned()],
+        }];
+
+        assert_eq!(
+            "Name,QOS\nsomebody,\"basic,interactive\"\n",
+            render(&rows, OutputFormat::Csv).unwrap()
+        );
+        insta::assert_snapshot!(render(&rows, OutputFormat::Json).unwrap());
+        insta::assert_snapshot!(render(&rows, OutputFormat::Yaml).unwrap());
+    }
+}
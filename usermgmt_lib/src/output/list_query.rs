@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+use super::{ListColumn, ListedUser, OutputFormat};
+use crate::{
+    cli::ListArgs, config::MgmtConfig, ldap, prelude::AppResult, slurm::SlurmAssociation,
+    util::ResolvedGid, Group,
+};
+
+/// LDAP filter for the filters of `args`.
+/// Values are escaped so that they are matched literally. The raw LDAP filter is taken as it is.
+pub fn ldap_filter_of(args: &ListArgs, config: &MgmtConfig) -> String {
+    let mut conditions = Vec::new();
+    if let Some(group) = args.group {
+        conditions.push(group_ldap_filter(group, config));
+    }
+    if let Some(qos) = &args.qos {
+        conditions.push(format!("(slurmQos={})", ldap3::ldap_escape(qos)));
+    }
+    if let Some(default_qos) = &args.default_qos {
+        conditions.push(format!(
+            "(slurmDefaultQos={})",
+            ldap3::ldap_escape(default_qos)
+        ));
+    }
+    if let Some(search) = &args.search {
+        let search = ldap3::ldap_escape(search);
+        let any_of: String = ["uid", "givenName", "sn", "mail"]
+            .iter()
+            .map(|attribute| format!("({}=*{}*)", attribute, search))
+            .collect();
+        conditions.push(format!("(|{})", any_of));
+    }
+    if let Some(uid_min) = args.uid_min {
+        conditions.push(format!("(uidNumber>={})", uid_min));
+    }
+    if let Some(uid_max) = args.uid_max {
+        conditions.push(format!("(uidNumber<={})", uid_max));
+    }
+    if let Some(raw) = &args.ldap_filter {
+        let raw = raw.trim();
+        if raw.starts_with('(') {
+            conditions.push(raw.to_owned());
+        } else {
+            conditions.push(format!("({})", raw));
+        }
+    }
+
+    match conditions.len() {
+        0 => ldap::ALL_ENTRIES_FILTER.to_owned(),
+        1 => conditions.remove(0),
+        _ => format!("(&{})", conditions.concat()),
+    }
+}
+
+/// LDAP filter for the users of the given group.
+pub fn group_ldap_filter(group: Group, config: &MgmtConfig) -> String {
+    format!("(gidNumber={})", ResolvedGid::new(group, config).gid())
+}
+
+/// True if the association fulfills the filters of `args` which are known in Slurm.
+/// Associations of accounts alone are only kept without any filter.
+///
+/// Slurm does not know the group of a user. With `group_members`, the users of the group
+/// of `args` in LDAP, only associations of these users are kept regardless of their account.
+pub fn association_matches(
+    args: &ListArgs,
+    group_members: Option<&[String]>,
+    association: &SlurmAssociation,
+) -> bool {
+    let Some(user) = &association.user else {
+        return !args.has_filters();
+    };
+    group_members.map_or(true, |members| members.contains(user))
+        && args
+            .qos
+            .as_ref()
+            .map_or(true, |qos| association.qos.contains(qos))
+        && args.default_qos.as_ref().map_or(true, |default_qos| {
+            association.default_qos.as_ref() == Some(default_qos)
+        })
+        && args.search.as_ref().map_or(true, |search| {
+            user.to_lowercase().contains(&search.to_lowercase())
+        })
+}
+
+/// Removes users which can not fulfill the filters only known in LDAP.
+/// With a uid range or a raw LDAP filter only users in LDAP are kept.
+pub fn filter_merged_users(args: &ListArgs, users: Vec<ListedUser>) -> Vec<ListedUser> {
+    let ldap_only = args.uid_min.is_some() || args.uid_max.is_some() || args.ldap_filter.is_some();
+    users
+        .into_iter()
+        .filter(|user| !ldap_only || user.in_ldap)
+        .collect()
+}
+
+/// Sorts the users by the chosen column and keeps only the users of the chosen page.
+/// Users with the same value in the column stay sorted by their username.
+pub fn sort_and_page(args: &ListArgs, mut users: Vec<ListedUser>) -> Vec<ListedUser> {
+    if let Some(column) = args.sort_by {
+        users.sort_by(|left, right| {
+            let ordering = compare_by(column, left, right);
+            if args.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    match args.page_size {
+        Some(page_size) => {
+            let page = args.page.map_or(1, |page| page.get());
+            users
+                .into_iter()
+                .skip((page - 1) * page_size.get())
+                .take(page_size.get())
+                .collect()
+        }
+        None => users,
+    }
+}
+
+/// Renders the users with only the given columns in the given format.
+/// All columns are rendered if no column is given.
+///
+/// # Errors
+///
+/// - If the users could not be turned into the chosen format. See [`super::render`].
+pub fn render_listed_users(
+    users: &[ListedUser],
+    columns: &[ListColumn],
+    format: OutputFormat,
+) -> AppResult<String> {
+    if columns.is_empty() {
+        return super::render(users, format);
+    }
+    match format {
+        OutputFormat::Table | OutputFormat::Csv => super::render_cells(
+            columns.iter().map(|column| column.header()).collect(),
+            users
+                .iter()
+                .map(|user| columns.iter().map(|&column| user.cell(column)).collect())
+                .collect(),
+            format,
+        ),
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let selected: Vec<SelectedColumns> = users
+                .iter()
+                .map(|user| SelectedColumns { user, columns })
+                .collect();
+            super::render_document(&selected, format)
+        }
+    }
+}
+
+fn compare_by(column: ListColumn, left: &ListedUser, right: &ListedUser) -> Ordering {
+    match column {
+        ListColumn::Uid => left.uid_number.cmp(&right.uid_number),
+        ListColumn::Gid => left.gid_number.cmp(&right.gid_number),
+        other => left.cell(other).cmp(&right.cell(other)),
+    }
+}
+
+/// Listed user serialized with the chosen columns only.
+struct SelectedColumns<'a> {
+    user: &'a ListedUser,
+    columns: &'a [ListColumn],
+}
+
+impl Serialize for SelectedColumns<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for &column in self.columns {
+            self.user.serialize_column(column, &mut map)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::Group;
+
+    fn listed(username: &str, uid: Option<u32>, mail: &str) -> ListedUser {
+        ListedUser {
+            username: username.to_owned(),
+            in_ldap: uid.is_some(),
+            in_slurm: uid.is_none(),
+            uid_number: uid,
+            mail: Some(mail.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn users() -> Vec<ListedUser> {
+        vec![
+            listed("alice", Some(10003), "alice@example.org"),
+            listed("bob", Some(10001), "bob@example.org"),
+            listed("carol", None, ""),
+            listed("dave", Some(10002), "dave@example.org"),
+        ]
+    }
+
+    #[test]
+    fn combine_filters_into_one_ldap_filter() {
+        let config = MgmtConfig::default();
+        assert_eq!(
+            ldap::ALL_ENTRIES_FILTER,
+            ldap_filter_of(&ListArgs::default(), &config)
+        );
+        let only_qos = ListArgs {
+            qos: Some("basic".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!("(slurmQos=basic)", ldap_filter_of(&only_qos, &config));
+
+        let args = ListArgs {
+            group: Some(Group::Staff),
+            default_qos: Some("basic".to_owned()),
+            search: Some("mu*ller)".to_owned()),
+            uid_min: Some(10000),
+            uid_max: Some(20000),
+            ldap_filter: Some("loginShell=/bin/zsh".to_owned()),
+            ..Default::default()
+        };
+        insta::assert_snapshot!(ldap_filter_of(&args, &config));
+    }
+
+    #[test]
+    fn filter_associations_on_the_client() {
+        let associations = SlurmAssociation::parse_all(
+            "alpha|staff|||||||||\n\
+             alpha|staff|alice||advanced|advanced,interactive|||||1\n\
+             alpha|student|bob||basic|basic|||||1\n\
+             beta|staff|Robert||basic|basic|||||1\n\
+             alpha|project_a|alice||basic|basic|||||1\n",
+        )
+        .unwrap();
+        let matching_members = |args: &ListArgs, members: Option<&[String]>| -> Vec<String> {
+            associations
+                .iter()
+                .filter(|association| association_matches(args, members, association))
+                .map(|association| {
+                    format!(
+                        "{}:{}",
+                        association.user.clone().unwrap_or_default(),
+                        association.account
+                    )
+                })
+                .collect()
+        };
+        let matching = |args: &ListArgs| -> Vec<String> { matching_members(args, None) };
+
+        assert_eq!(5, matching(&ListArgs::default()).len());
+        assert_eq!(
+            vec!["alice:staff", "alice:project_a"],
+            matching_members(
+                &ListArgs {
+                    group: Some(Group::Staff),
+                    ..Default::default()
+                },
+                Some(&["alice".to_owned()])
+            )
+        );
+        assert_eq!(
+            vec!["bob:student", "Robert:staff"],
+            matching(&ListArgs {
+                qos: Some("basic".to_owned()),
+                search: Some("OB".to_owned()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn sort_by_column_and_page() {
+        let args = ListArgs {
+            sort_by: Some(ListColumn::Uid),
+            descending: true,
+            page_size: NonZeroUsize::new(2),
+            page: NonZeroUsize::new(2),
+            ..Default::default()
+        };
+        let actual: Vec<String> = sort_and_page(&args, users())
+            .into_iter()
+            .map(|user| user.username)
+            .collect();
+        assert_eq!(vec!["bob", "carol"], actual);
+
+        let only_ldap = filter_merged_users(
+            &ListArgs {
+                uid_min: Some(1),
+                ..Default::default()
+            },
+            users(),
+        );
+        assert!(only_ldap.iter().all(|user| user.in_ldap));
+    }
+
+    #[test]
+    fn render_only_selected_columns() {
+        let columns = [ListColumn::Mail, ListColumn::Username];
+
+        assert_eq!(
+            "Mail,Username\nalice@example.org,alice\nbob@example.org,bob\n",
+            render_listed_users(&users()[..2], &columns, OutputFormat::Csv).unwrap()
+        );
+        insta::assert_snapshot!(
+            render_listed_users(&users()[..2], &columns, OutputFormat::Json).unwrap()
+        );
+    }
+}
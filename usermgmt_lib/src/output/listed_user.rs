@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde::{ser::SerializeMap, Deserialize, Serialize};
+
+use super::TableRow;
+use crate::{
+    config::MgmtConfig,
+    ldap::{LdapSearchResult, LdapUserRow},
+    slurm::SlurmAssociation,
+};
+
+/// Column of listed users for sorting and selecting columns.
+/// The columns are in the same order as the cells of a [`ListedUser`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListColumn {
+    Username,
+    Ldap,
+    Slurm,
+    Uid,
+    Gid,
+    Group,
+    Firstname,
+    Lastname,
+    Mail,
+    DefaultQos,
+    Qos,
+    Clusters,
+    Accounts,
+}
+
+impl ListColumn {
+    /// Title of the column in a table.
+    pub fn header(self) -> &'static str {
+        ListedUser::headers()[self as usize]
+    }
+}
+
+/// User with the data of LDAP and Slurm merged into one entity.
+/// This is the schema of listed users for the output formats other than the table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListedUser {
+    pub username: String,
+    /// True if the user was found in LDAP. All fields from LDAP are empty otherwise.
+    pub in_ldap: bool,
+    /// True if the user has at least one association in Slurm.
+    pub in_slurm: bool,
+    pub uid_number: Option<u32>,
+    pub gid_number: Option<u32>,
+    /// Group whose gid in the configuration is the gid of the user.
+    pub group: Option<String>,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub mail: Option<String>,
+    /// Default QOS stored in LDAP.
+    pub default_qos: Option<String>,
+    /// QOS stored in LDAP.
+    pub qos: Vec<String>,
+    /// Associations of the user in Slurm, one per cluster, account and partition.
+    pub associations: Vec<SlurmAssociation>,
+}
+
+impl ListedUser {
+    /// Merges the users of LDAP and the user associations of Slurm by the username.
+    /// Associations of accounts alone are left out.
+    /// The users are sorted by their username.
+    pub fn merge(
+        ldap_users: Option<&LdapSearchResult>,
+        associations: &[SlurmAssociation],
+        config: &MgmtConfig,
+    ) -> Vec<Self> {
+        let mut merged: BTreeMap<String, Self> = BTreeMap::new();
+        for row in ldap_users
+            .map(LdapSearchResult::user_rows)
+            .unwrap_or_default()
+        {
+            if let Some(username) = row.first("uid") {
+                merged.insert(username.clone(), Self::from_ldap(username, &row, config));
+            }
+        }
+        for association in associations {
+            if let Some(username) = &association.user {
+                let user = merged.entry(username.clone()).or_insert_with(|| Self {
+                    username: username.clone(),
+                    ..Default::default()
+                });
+                user.in_slurm = true;
+                user.associations.push(association.clone());
+            }
+        }
+        merged.into_values().collect()
+    }
+
+    fn from_ldap(username: String, row: &LdapUserRow, config: &MgmtConfig) -> Self {
+        let number = |attribute: &str| {
+            row.first(attribute)
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        Self {
+            username,
+            in_ldap: true,
+            uid_number: number("uidNumber"),
+            gid_number: number("gidNumber"),
+            group: row.group(config),
+            firstname: row.first("givenName"),
+            lastname: row.first("sn"),
+            mail: row.first("mail"),
+            default_qos: row.first("slurmDefaultQos"),
+            qos: row.values("slurmQos"),
+            ..Default::default()
+        }
+    }
+
+    /// Cell of the given column as shown in a table.
+    pub fn cell(&self, column: ListColumn) -> String {
+        self.cells().swap_remove(column as usize)
+    }
+
+    /// Adds the value of the given column to a map under the name of its field.
+    /// Clusters and accounts are added as lists of distinct values.
+    pub(super) fn serialize_column<M>(
+        &self,
+        column: ListColumn,
+        map: &mut M,
+    ) -> Result<(), M::Error>
+    where
+        M: SerializeMap,
+    {
+        match column {
+            ListColumn::Username => map.serialize_entry("username", &self.username),
+            ListColumn::Ldap => map.serialize_entry("in_ldap", &self.in_ldap),
+            ListColumn::Slurm => map.serialize_entry("in_slurm", &self.in_slurm),
+            ListColumn::Uid => map.serialize_entry("uid_number", &self.uid_number),
+            ListColumn::Gid => map.serialize_entry("gid_number", &self.gid_number),
+            ListColumn::Group => map.serialize_entry("group", &self.group),
+            ListColumn::Firstname => map.serialize_entry("firstname", &self.firstname),
+            ListColumn::Lastname => map.serialize_entry("lastname", &self.lastname),
+            ListColumn::Mail => map.serialize_entry("mail", &self.mail),
+            ListColumn::DefaultQos => map.serialize_entry("default_qos", &self.default_qos),
+            ListColumn::Qos => map.serialize_entry("qos", &self.qos),
+            ListColumn::Clusters => map.serialize_entry(
+                "clusters",
+                &self.distinct_of_associations(|association| &association.cluster),
+            ),
+            ListColumn::Accounts => map.serialize_entry(
+                "accounts",
+                &self.distinct_of_associations(|association| &association.account),
+            ),
+        }
+    }
+
+    /// Distinct values of the associations in the order of their first occurrence.
+    fn distinct_of_associations(&self, value: impl Fn(&SlurmAssociation) -> &str) -> Vec<&str> {
+        let mut distinct: Vec<&str> = Vec::new();
+        for next in self.associations.iter().map(value) {
+            if !distinct.contains(&next) {
+                distinct.push(next);
+            }
+        }
+        distinct
+    }
+}
+
+impl TableRow for ListedUser {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Username",
+            "LDAP",
+            "Slurm",
+            "Uid",
+            "Gid",
+            "Group",
+            "Firstname",
+            "Lastname",
+            "Mail",
+            "Def QOS",
+            "QOS",
+            "Clusters",
+            "Accounts",
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        let number = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+        vec![
+            self.username.clone(),
+            self.in_ldap.to_string(),
+            self.in_slurm.to_string(),
+            number(self.uid_number),
+            number(self.gid_number),
+            or_empty(&self.group),
+            or_empty(&self.firstname),
+            or_empty(&self.lastname),
+            or_empty(&self.mail),
+            or_empty(&self.default_qos),
+            self.qos.join(","),
+            self.distinct_of_associations(|association| &association.cluster)
+                .join(","),
+            self.distinct_of_associations(|association| &association.account)
+                .join(","),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use maplit::hashmap;
+
+    use super::*;
+    use crate::output::{self, OutputFormat};
+
+    #[test]
+    fn merge_ldap_and_slurm_per_user() {
+        let ldap_users = LdapSearchResult::new(
+            ["gidNumber", "givenName", "mail", "sn", "uid", "uidNumber"],
+            vec![
+                hashmap! {
+                    "uid".to_owned() => vec!["alice".to_owned()],
+                    "uidNumber".to_owned() => vec!["10001".to_owned()],
+                    "gidNumber".to_owned() => vec!["1001".to_owned()],
+                    "givenName".to_owned() => vec!["Alice".to_owned()],
+                    "sn".to_owned() => vec!["Muster".to_owned()],
+                    "mail".to_owned() => vec!["alice@example.org".to_owned()],
+                },
+                hashmap! { "uid".to_owned() => vec!["only_ldap".to_owned()] },
+            ],
+        );
+        let associations = SlurmAssociation::parse_all(
+            "alpha|staff|||||||||\n\
+             alpha|staff|alice||advanced|advanced,interactive|||||1\n\
+             beta|project_x|alice||advanced|advanced|||||1\n\
+             alpha|student|only_slurm||basic|basic|||||1\n",
+        )
+        .unwrap();
+
+        let actual = ListedUser::merge(Some(&ldap_users), &associations, &Default::default());
+
+        insta::assert_yaml_snapshot!(actual);
+        insta::assert_snapshot!(output::render(&actual, OutputFormat::Csv).unwrap());
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use super::TableRow;
+use crate::cli::OnWhichSystem;
+
+/// Kind of change made to an user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, derive_more::Display)]
+#[serde(rename_all = "lowercase")]
+pub enum UserAction {
+    #[display(fmt = "add")]
+    Add,
+    #[display(fmt = "modify")]
+    Modify,
+    #[display(fmt = "delete")]
+    Delete,
+}
+
+/// Result of adding, modifying or deleting an user.
+/// This is the schema printed for these subcommands if an output format is chosen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserChange {
+    pub action: UserAction,
+    pub username: String,
+    pub ldap: bool,
+    pub slurm: bool,
+    pub directories: bool,
+    /// Slurm clusters the change was restricted to. Empty if not restricted.
+    pub clusters: Vec<String>,
+}
+
+impl UserChange {
+    /// Change on the systems of `on_which_sys`. No Slurm clusters are chosen.
+    pub fn new(action: UserAction, username: String, on_which_sys: &OnWhichSystem) -> Self {
+        Self {
+            action,
+            username,
+            ldap: on_which_sys.ldap(),
+            slurm: on_which_sys.slurm(),
+            directories: on_which_sys.dirs(),
+            clusters: Vec::new(),
+        }
+    }
+
+    pub fn with_clusters(self, clusters: Vec<String>) -> Self {
+        Self { clusters, ..self }
+    }
+}
+
+impl TableRow for UserChange {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Action",
+            "Username",
+            "LDAP",
+            "Slurm",
+            "Directories",
+            "Clusters",
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.action.to_string(),
+            self.username.clone(),
+            self.ldap.to_string(),
+            self.slurm.to_string(),
+            self.directories.to_string(),
+            self.clusters.join(","),
+        ]
+    }
+}
@@ -0,0 +1,45 @@
+//! Reports which combine the users in LDAP with data from Slurm and the hosts of the cluster.
+
+mod inactive_users;
+mod usage;
+mod user_details;
+
+pub use inactive_users::{
+    collect_inactive_users, find_inactive_users, lastlog_command, parse_last_submits,
+    parse_recent_logins, sacct_submits_command, InactiveUser,
+};
+pub use usage::{
+    collect_usage, join_usage_with_ldap, last_month, parse_usage, sreport_usage_command,
+    summarize_by_group, GroupUsage, TresHours, UserUsage,
+};
+pub use user_details::{InspectedSystems, LdapDetails, UserDetails};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    executor::{CommandExecutor, ExecCommand},
+    prelude::AppResult,
+};
+
+/// Runs the command and returns its stdout.
+///
+/// # Errors
+///
+/// - If the command could not be run or exits with a non-zero exit code.
+fn run_for_stdout<E>(executor: &E, command: &ExecCommand) -> AppResult<String>
+where
+    E: CommandExecutor,
+{
+    let output = executor
+        .run(command)
+        .with_context(|| format!("Could not run command ({})", command))?;
+    if !output.is_success() {
+        bail!(
+            "Command ({}) failed with exit code {}: {}",
+            command,
+            output.exit_code(),
+            output.stderr().trim()
+        );
+    }
+    Ok(output.into_stdout())
+}
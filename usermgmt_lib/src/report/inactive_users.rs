@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, Local, NaiveDateTime};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use super::run_for_stdout;
+use crate::{
+    config::MgmtConfig,
+    executor::{CommandExecutor, ExecCommand},
+    ldap::LdapSearchResult,
+    output::TableRow,
+    prelude::AppResult,
+};
+
+/// Format of the submit time printed by sacct.
+const SACCT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const SPLIT_BETWEEN_CELLS: char = '|';
+
+/// User in LDAP which has neither submitted a job nor logged in on the head node recently.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InactiveUser {
+    pub username: String,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub mail: Option<String>,
+    /// Group derived from the gid of the user. `None` if the gid matches no configured group.
+    pub group: Option<String>,
+    pub default_qos: Option<String>,
+    pub qos: Vec<String>,
+    /// Last job submission found within the time span searched by sacct.
+    pub last_job_submit: Option<String>,
+}
+
+impl TableRow for InactiveUser {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Username",
+            "Firstname",
+            "Lastname",
+            "Mail",
+            "Group",
+            "Def QOS",
+            "QOS",
+            "Last submit",
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            self.username.clone(),
+            or_empty(&self.firstname),
+            or_empty(&self.lastname),
+            or_empty(&self.mail),
+            or_empty(&self.group),
+            or_empty(&self.default_qos),
+            self.qos.join(","),
+            or_empty(&self.last_job_submit),
+        ]
+    }
+}
+
+/// Command for sacct which lists the user and submit time of every job
+/// submitted after `since` on the configured clusters.
+pub fn sacct_submits_command(config: &MgmtConfig, since: NaiveDateTime) -> ExecCommand {
+    let mut args = vec![
+        "--allusers".to_owned(),
+        "--allocations".to_owned(),
+        "--noheader".to_owned(),
+        "--parsable2".to_owned(),
+        format!("--starttime={}", since.format(SACCT_TIME_FORMAT)),
+        "--format=User,Submit".to_owned(),
+    ];
+    if !config.clusters.is_empty() {
+        args.push(format!("--clusters={}", config.clusters.join(",")));
+    }
+    ExecCommand::new(config.sacct_path.clone(), args)
+}
+
+/// Command which lists the users who logged in on the machine within the last `days`.
+pub fn lastlog_command(days: u32) -> ExecCommand {
+    ExecCommand::new("lastlog", ["--time".to_owned(), days.to_string()])
+}
+
+/// Latest submit time per user from the output of [`sacct_submits_command`].
+/// Lines without user or with a submit time sacct could not provide, e.g. `Unknown`, are skipped.
+pub fn parse_last_submits(output: &str) -> HashMap<String, NaiveDateTime> {
+    let mut last_submits: HashMap<String, NaiveDateTime> = HashMap::new();
+    for line in output.lines().filter(|line| !line.trim().is_empty()) {
+        let parsed = line
+            .split_once(SPLIT_BETWEEN_CELLS)
+            .and_then(|(user, submit)| {
+                let user = user.trim();
+                let submit =
+                    NaiveDateTime::parse_from_str(submit.trim(), SACCT_TIME_FORMAT).ok()?;
+                (!user.is_empty()).then(|| (user.to_owned(), submit))
+            });
+        match parsed {
+            Some((user, submit)) => {
+                let latest = last_submits.entry(user).or_insert(submit);
+                if submit > *latest {
+                    *latest = submit;
+                }
+            }
+            None => debug!(
+                "Skipped line of sacct without user or submit time: {}",
+                line
+            ),
+        }
+    }
+    last_submits
+}
+
+/// Usernames listed in the output of [`lastlog_command`]. The header line is skipped.
+pub fn parse_recent_logins(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Returns all users of `ldap_users` which have not submitted a job since `cutoff`
+/// and are not part of `recent_logins`. The users are sorted by their username.
+pub fn find_inactive_users(
+    ldap_users: &LdapSearchResult,
+    last_submits: &HashMap<String, NaiveDateTime>,
+    recent_logins: &HashSet<String>,
+    cutoff: NaiveDateTime,
+    config: &MgmtConfig,
+) -> Vec<InactiveUser> {
+    let mut inactive: Vec<InactiveUser> = ldap_users
+        .user_rows()
+        .into_iter()
+        .filter_map(|row| {
+            let username = row.first("uid")?;
+            let last_submit = last_submits.get(&username);
+            let is_active = last_submit.is_some_and(|submit| *submit >= cutoff)
+                || recent_logins.contains(&username);
+            if is_active {
+                return None;
+            }
+            Some(InactiveUser {
+                firstname: row.first("givenName"),
+                lastname: row.first("sn"),
+                mail: row.first("mail"),
+                group: row.group(config),
+                default_qos: row.first("slurmDefaultQos"),
+                qos: row.values("slurmQos"),
+                last_job_submit: last_submit.map(|submit| submit.to_string()),
+                username,
+            })
+        })
+        .collect();
+    inactive.sort_by(|left, right| left.username.cmp(&right.username));
+    inactive
+}
+
+/// Lists the users of `ldap_users` without a job submission within the last `days`.
+/// Job submissions are searched via sacct on `slurm_executor` within the last `lookback_days`.
+/// Users who logged in on the head node within the last `days` count as active
+/// if `login_executor` is given. Their logins are taken from lastlog.
+///
+/// # Errors
+///
+/// - If sacct or lastlog could not be run or fails.
+pub fn collect_inactive_users<E, L>(
+    ldap_users: &LdapSearchResult,
+    days: u32,
+    lookback_days: u32,
+    config: &MgmtConfig,
+    slurm_executor: &E,
+    login_executor: Option<&L>,
+) -> AppResult<Vec<InactiveUser>>
+where
+    E: CommandExecutor,
+    L: CommandExecutor,
+{
+    if lookback_days < days {
+        warn!(
+            "Jobs are only searched within the last {} days. Users without a job in this time span are listed as inactive for {} days",
+            lookback_days, days
+        );
+    }
+    let now = Local::now().naive_local();
+    let sacct_output = run_for_stdout(
+        slurm_executor,
+        &sacct_submits_command(config, now - Duration::days(lookback_days.into())),
+    )?;
+    let last_submits = parse_last_submits(&sacct_output);
+    let recent_logins = match login_executor {
+        Some(executor) => parse_recent_logins(&run_for_stdout(executor, &lastlog_command(days))?),
+        None => HashSet::new(),
+    };
+    Ok(find_inactive_users(
+        ldap_users,
+        &last_submits,
+        &recent_logins,
+        now - Duration::days(days.into()),
+        config,
+    ))
+}
+
+#[cfg(test)]
+mod testing {
+    use maplit::hashmap;
+
+    use super::*;
+    use crate::executor::{CommandOutput, RecordingExecutor};
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, SACCT_TIME_FORMAT).unwrap()
+    }
+
+    fn ldap_user(uid: &str, gid: &str) -> HashMap<String, Vec<String>> {
+        hashmap! {
+            "uid".to_owned() => vec![uid.to_owned()],
+            "gidNumber".to_owned() => vec![gid.to_owned()],
+            "givenName".to_owned() => vec!["Max".to_owned()],
+            "sn".to_owned() => vec!["Muster".to_owned()],
+            "mail".to_owned() => vec![format!("{}@example.org", uid)],
+            "slurmDefaultQos".to_owned() => vec!["basic".to_owned()],
+            "slurmQos".to_owned() => vec!["basic".to_owned(), "interactive".to_owned()],
+        }
+    }
+
+    fn ldap_users() -> LdapSearchResult {
+        LdapSearchResult::new(
+            [
+                "gidNumber",
+                "givenName",
+                "mail",
+                "slurmDefaultQos",
+                "slurmQos",
+                "sn",
+                "uid",
+            ],
+            vec![
+                ldap_user("submitted", "1002"),
+                ldap_user("logged_in", "1001"),
+                ldap_user("long_ago", "1002"),
+                ldap_user("never", "4242"),
+            ],
+        )
+    }
+
+    #[test]
+    fn parse_latest_submit_per_user() {
+        let input = "alice|2024-01-02T10:00:00\n\
+                     alice|2024-03-01T08:30:00\n\
+                     bob|2023-12-24T18:00:00\n\
+                     |2024-01-01T00:00:00\n\
+                     carol|Unknown\n";
+
+        let actual = parse_last_submits(input);
+
+        assert_eq!(
+            hashmap! {
+                "alice".to_owned() => time("2024-03-01T08:30:00"),
+                "bob".to_owned() => time("2023-12-24T18:00:00"),
+            },
+            actual
+        );
+    }
+
+    #[test]
+    fn parse_users_of_lastlog() {
+        let input = "Username         Port     From             Latest\n\
+                     alice            pts/0    10.0.0.1         Fri Mar  1 08:30:00 +0100 2024\n\
+                     bob              pts/1    10.0.0.2         Thu Feb 29 12:00:00 +0100 2024\n";
+
+        let actual = parse_recent_logins(input);
+
+        assert_eq!(
+            HashSet::from(["alice".to_owned(), "bob".to_owned()]),
+            actual
+        );
+    }
+
+    #[test]
+    fn list_users_without_recent_job_or_login() {
+        let last_submits = hashmap! {
+            "submitted".to_owned() => time("2024-03-01T08:30:00"),
+            "long_ago".to_owned() => time("2023-01-01T00:00:00"),
+        };
+        let recent_logins = HashSet::from(["logged_in".to_owned()]);
+
+        let actual = find_inactive_users(
+            &ldap_users(),
+            &last_submits,
+            &recent_logins,
+            time("2024-01-01T00:00:00"),
+            &MgmtConfig::default(),
+        );
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn run_sacct_and_lastlog_for_inactive_users() {
+        let config = MgmtConfig {
+            clusters: vec!["alpha".to_owned()],
+            ..Default::default()
+        };
+        let slurm = RecordingExecutor::new().respond_with(
+            "/usr/local/bin/sacct",
+            CommandOutput::success("never|2000-01-01T00:00:00\n"),
+        );
+        let head_node = slurm.for_host("head").respond_with(
+            "lastlog",
+            CommandOutput::success("Username Port From Latest\nlogged_in pts/0 x y\n"),
+        );
+
+        let actual =
+            collect_inactive_users(&ldap_users(), 180, 365, &config, &slurm, Some(&head_node))
+                .unwrap();
+
+        let usernames: Vec<&str> = actual.iter().map(|user| user.username.as_str()).collect();
+        assert_eq!(vec!["long_ago", "never", "submitted"], usernames);
+        let lines = slurm.recorded_lines();
+        assert!(lines[0].starts_with(
+            "/usr/local/bin/sacct --allusers --allocations --noheader --parsable2 --starttime="
+        ));
+        assert!(lines[0].ends_with("--format=User,Submit --clusters=alpha"));
+        assert_eq!("head: lastlog --time 180", lines[1]);
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{ensure, Context};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use super::run_for_stdout;
+use crate::{
+    config::MgmtConfig,
+    executor::{CommandExecutor, ExecCommand},
+    ldap::{LdapSearchResult, LdapUserRow},
+    output::TableRow,
+    prelude::AppResult,
+};
+
+const SREPORT_DATE_FORMAT: &str = "%Y-%m-%d";
+const SPLIT_BETWEEN_CELLS: char = '|';
+/// Cells of a line of sreport: Cluster, Account, Login, Proper Name, TRES Name and Used.
+const SREPORT_CELLS: usize = 6;
+const TRES_CPU: &str = "cpu";
+const TRES_GPU: &str = "gres/gpu";
+
+/// Hours of CPU and GPU used by jobs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TresHours {
+    pub cpu_hours: u64,
+    pub gpu_hours: u64,
+}
+
+impl TresHours {
+    fn add(&mut self, other: TresHours) {
+        self.cpu_hours += other.cpu_hours;
+        self.gpu_hours += other.gpu_hours;
+    }
+}
+
+/// Usage of an user within the time window of the report.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserUsage {
+    pub username: String,
+    /// `None` if the user is not found in LDAP.
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub mail: Option<String>,
+    /// Group derived from the gid of the user in LDAP.
+    pub group: Option<String>,
+    pub cpu_hours: u64,
+    pub gpu_hours: u64,
+}
+
+impl TableRow for UserUsage {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "Username",
+            "Firstname",
+            "Lastname",
+            "Mail",
+            "Group",
+            "CPU hours",
+            "GPU hours",
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            self.username.clone(),
+            or_empty(&self.firstname),
+            or_empty(&self.lastname),
+            or_empty(&self.mail),
+            or_empty(&self.group),
+            self.cpu_hours.to_string(),
+            self.gpu_hours.to_string(),
+        ]
+    }
+}
+
+/// Usage of all users of a group within the time window of the report.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupUsage {
+    /// `None` for users without a known group.
+    pub group: Option<String>,
+    pub users: usize,
+    pub cpu_hours: u64,
+    pub gpu_hours: u64,
+}
+
+impl TableRow for GroupUsage {
+    fn headers() -> Vec<&'static str> {
+        vec!["Group", "Users", "CPU hours", "GPU hours"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.group.clone().unwrap_or_default(),
+            self.users.to_string(),
+            self.cpu_hours.to_string(),
+            self.gpu_hours.to_string(),
+        ]
+    }
+}
+
+/// First day of the previous month and first day of the month of `today`.
+pub fn last_month(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let start_of_month = today.with_day(1).expect("Every month has a first day");
+    let start_of_last_month = (start_of_month - Duration::days(1))
+        .with_day(1)
+        .expect("Every month has a first day");
+    (start_of_last_month, start_of_month)
+}
+
+/// Command for sreport which lists the CPU and GPU hours per account and user
+/// from `start` until `end` (exclusive) on the configured clusters.
+pub fn sreport_usage_command(config: &MgmtConfig, start: NaiveDate, end: NaiveDate) -> ExecCommand {
+    let mut args = vec![
+        "--noheader".to_owned(),
+        "--parsable2".to_owned(),
+        "-t".to_owned(),
+        "Hours".to_owned(),
+        format!("--tres={},{}", TRES_CPU, TRES_GPU),
+        "cluster".to_owned(),
+        "AccountUtilizationByUser".to_owned(),
+        format!("start={}", start.format(SREPORT_DATE_FORMAT)),
+        format!("end={}", end.format(SREPORT_DATE_FORMAT)),
+    ];
+    if !config.clusters.is_empty() {
+        args.push(format!("clusters={}", config.clusters.join(",")));
+    }
+    ExecCommand::new(config.sreport_path.clone(), args)
+}
+
+/// Hours per user from the output of [`sreport_usage_command`].
+/// Hours of an user in several accounts or clusters are added up.
+/// Lines of accounts alone are skipped since they sum up the lines of their users.
+///
+/// # Errors
+///
+/// - If a line does not have the cells of sreport or the used hours are not a number.
+pub fn parse_usage(output: &str) -> AppResult<HashMap<String, TresHours>> {
+    let mut usage: HashMap<String, TresHours> = HashMap::new();
+    for line in output.lines().filter(|line| !line.trim().is_empty()) {
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).map(str::trim).collect();
+        ensure!(
+            cells.len() == SREPORT_CELLS,
+            "Expected {} cells but found {} in line of sreport ({})",
+            SREPORT_CELLS,
+            cells.len(),
+            line
+        );
+        let (login, tres, used) = (cells[2], cells[4], cells[5]);
+        if login.is_empty() {
+            continue;
+        }
+        let used: u64 = used
+            .parse()
+            .with_context(|| format!("Used hours ({}) of user {} is not a number", used, login))?;
+        let hours = match tres {
+            TRES_CPU => TresHours {
+                cpu_hours: used,
+                ..Default::default()
+            },
+            TRES_GPU => TresHours {
+                gpu_hours: used,
+                ..Default::default()
+            },
+            _ => continue,
+        };
+        usage.entry(login.to_owned()).or_default().add(hours);
+    }
+    Ok(usage)
+}
+
+/// Combines the hours per user with the names, mail and group of the users in LDAP.
+/// Users not found in LDAP are kept without these fields.
+/// The users are sorted by their username.
+pub fn join_usage_with_ldap(
+    usage: &HashMap<String, TresHours>,
+    ldap_users: &LdapSearchResult,
+    config: &MgmtConfig,
+) -> Vec<UserUsage> {
+    let ldap_rows: HashMap<String, LdapUserRow> = ldap_users
+        .user_rows()
+        .into_iter()
+        .filter_map(|row| row.first("uid").map(|uid| (uid, row)))
+        .collect();
+    let mut joined: Vec<UserUsage> = usage
+        .iter()
+        .map(|(username, hours)| {
+            let row = ldap_rows.get(username);
+            let first = |attribute: &str| row.and_then(|row| row.first(attribute));
+            UserUsage {
+                username: username.clone(),
+                firstname: first("givenName"),
+                lastname: first("sn"),
+                mail: first("mail"),
+                group: row.and_then(|row| row.group(config)),
+                cpu_hours: hours.cpu_hours,
+                gpu_hours: hours.gpu_hours,
+            }
+        })
+        .collect();
+    joined.sort_by(|left, right| left.username.cmp(&right.username));
+    joined
+}
+
+/// Adds up the hours of the users per group. Groups are sorted by their name.
+pub fn summarize_by_group(users: &[UserUsage]) -> Vec<GroupUsage> {
+    let mut groups: BTreeMap<Option<String>, GroupUsage> = BTreeMap::new();
+    for user in users {
+        let group = groups
+            .entry(user.group.clone())
+            .or_insert_with(|| GroupUsage {
+                group: user.group.clone(),
+                ..Default::default()
+            });
+        group.users += 1;
+        group.cpu_hours += user.cpu_hours;
+        group.gpu_hours += user.gpu_hours;
+    }
+    groups.into_values().collect()
+}
+
+/// Lists the CPU and GPU hours per user from `start` until `end` (exclusive)
+/// together with the names, mail and group of the users in LDAP.
+/// The hours are taken from sreport on `executor`.
+///
+/// # Errors
+///
+/// - If sreport could not be run, fails or its output could not be parsed.
+pub fn collect_usage<E>(
+    ldap_users: &LdapSearchResult,
+    start: NaiveDate,
+    end: NaiveDate,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult<Vec<UserUsage>>
+where
+    E: CommandExecutor,
+{
+    ensure!(
+        start < end,
+        "Start ({}) of the time window must be before its end ({})",
+        start,
+        end
+    );
+    let output = run_for_stdout(executor, &sreport_usage_command(config, start, end))?;
+    let usage = parse_usage(&output)?;
+    Ok(join_usage_with_ldap(&usage, ldap_users, config))
+}
+
+#[cfg(test)]
+mod testing {
+    use maplit::hashmap;
+
+    use super::*;
+    use crate::executor::{CommandOutput, RecordingExecutor};
+
+    const SREPORT_OUTPUT: &str = "alpha|staff|||cpu|170\n\
+                                  alpha|staff|||gres/gpu|4\n\
+                                  alpha|staff|alice|Alice A|cpu|120\n\
+                                  alpha|staff|alice|Alice A|gres/gpu|4\n\
+                                  alpha|project_x|alice|Alice A|cpu|30\n\
+                                  alpha|student|bob|Bob B|cpu|50\n\
+                                  alpha|student|bob|Bob B|gres/gpu|0\n\
+                                  beta|student|ghost||cpu|7\n";
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, SREPORT_DATE_FORMAT).unwrap()
+    }
+
+    fn ldap_users() -> LdapSearchResult {
+        let user = |uid: &str, gid: &str| {
+            hashmap! {
+                "uid".to_owned() => vec![uid.to_owned()],
+                "gidNumber".to_owned() => vec![gid.to_owned()],
+                "givenName".to_owned() => vec![uid.to_uppercase()],
+                "sn".to_owned() => vec!["Muster".to_owned()],
+                "mail".to_owned() => vec![format!("{}@example.org", uid)],
+            }
+        };
+        LdapSearchResult::new(
+            ["gidNumber", "givenName", "mail", "sn", "uid"],
+            vec![user("alice", "1001"), user("bob", "1002")],
+        )
+    }
+
+    #[test]
+    fn window_of_last_month() {
+        assert_eq!(
+            (date("2024-02-01"), date("2024-03-01")),
+            last_month(date("2024-03-15"))
+        );
+        assert_eq!(
+            (date("2023-12-01"), date("2024-01-01")),
+            last_month(date("2024-01-01"))
+        );
+    }
+
+    #[test]
+    fn add_up_hours_per_user() {
+        let actual = parse_usage(SREPORT_OUTPUT).unwrap();
+
+        assert_eq!(
+            hashmap! {
+                "alice".to_owned() => TresHours { cpu_hours: 150, gpu_hours: 4 },
+                "bob".to_owned() => TresHours { cpu_hours: 50, gpu_hours: 0 },
+                "ghost".to_owned() => TresHours { cpu_hours: 7, gpu_hours: 0 },
+            },
+            actual
+        );
+        assert!(parse_usage("alpha|staff|alice|Alice A|cpu|many\n").is_err());
+        assert!(parse_usage("alpha|staff|alice\n").is_err());
+    }
+
+    #[test]
+    fn join_usage_with_ldap_and_sum_up_groups() {
+        let executor = RecordingExecutor::new().respond_with(
+            "/usr/local/bin/sreport",
+            CommandOutput::success(SREPORT_OUTPUT),
+        );
+        let config = MgmtConfig {
+            clusters: vec!["alpha".to_owned(), "beta".to_owned()],
+            ..Default::default()
+        };
+
+        let actual = collect_usage(
+            &ldap_users(),
+            date("2024-02-01"),
+            date("2024-03-01"),
+            &config,
+            &executor,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec!["/usr/local/bin/sreport --noheader --parsable2 -t Hours --tres=cpu,gres/gpu cluster AccountUtilizationByUser start=2024-02-01 end=2024-03-01 clusters=alpha,beta"],
+            executor.recorded_lines()
+        );
+        insta::assert_yaml_snapshot!(actual);
+        insta::assert_yaml_snapshot!(summarize_by_group(&actual));
+    }
+}
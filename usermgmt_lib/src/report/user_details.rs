@@ -0,0 +1,322 @@
+use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::MgmtConfig,
+    dir::DirectoryState,
+    ldap::LdapUserRow,
+    output::{self, OutputFormat},
+    prelude::AppResult,
+    slurm::{self, SlurmAssociation},
+};
+
+/// Attributes of an user in LDAP as set by this tool while adding.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LdapDetails {
+    pub uid_number: Option<u32>,
+    pub gid_number: Option<u32>,
+    /// Group whose gid in the configuration is the gid of the user.
+    pub group: Option<String>,
+    pub common_name: Option<String>,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub mail: Option<String>,
+    pub home_directory: Option<String>,
+    pub login_shell: Option<String>,
+    pub default_qos: Option<String>,
+    pub qos: Vec<String>,
+    pub ssh_public_keys: Vec<String>,
+    pub object_classes: Vec<String>,
+}
+
+impl LdapDetails {
+    pub fn from_row(row: &LdapUserRow, config: &MgmtConfig) -> Self {
+        let number = |attribute: &str| {
+            row.first(attribute)
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        Self {
+            uid_number: number("uidNumber"),
+            gid_number: number("gidNumber"),
+            group: row.group(config),
+            common_name: row.first("cn"),
+            firstname: row.first("givenName"),
+            lastname: row.first("sn"),
+            mail: row.first("mail"),
+            home_directory: row.first("homeDirectory"),
+            login_shell: row.first("loginShell"),
+            default_qos: row.first("slurmDefaultQos"),
+            qos: row.values("slurmQos"),
+            ssh_public_keys: row.values("sshPublicKey"),
+            object_classes: row.values("objectClass"),
+        }
+    }
+
+    fn to_rows(&self) -> Vec<(&'static str, String)> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        let number = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+        vec![
+            ("Uid", number(self.uid_number)),
+            ("Gid", number(self.gid_number)),
+            ("Group", or_empty(&self.group)),
+            ("Common name", or_empty(&self.common_name)),
+            ("Firstname", or_empty(&self.firstname)),
+            ("Lastname", or_empty(&self.lastname)),
+            ("Mail", or_empty(&self.mail)),
+            ("Home directory", or_empty(&self.home_directory)),
+            ("Login shell", or_empty(&self.login_shell)),
+            ("Def QOS", or_empty(&self.default_qos)),
+            ("QOS", self.qos.join(",")),
+            ("SSH public keys", self.ssh_public_keys.join("\n")),
+            ("Object classes", self.object_classes.join(",")),
+        ]
+    }
+}
+
+/// Systems on which an user was looked up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InspectedSystems {
+    pub ldap: bool,
+    pub slurm: bool,
+    pub directories: bool,
+}
+
+/// Everything known about an user on the inspected systems
+/// together with the inconsistencies between these systems.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserDetails {
+    pub username: String,
+    pub inspected: InspectedSystems,
+    /// `None` if the user is not in LDAP or LDAP was not inspected.
+    pub ldap: Option<LdapDetails>,
+    pub associations: Vec<SlurmAssociation>,
+    pub directories: Vec<DirectoryState>,
+    pub inconsistencies: Vec<String>,
+}
+
+impl UserDetails {
+    /// Combines the data of the inspected systems and finds inconsistencies between them.
+    pub fn new(
+        username: String,
+        inspected: InspectedSystems,
+        ldap: Option<LdapDetails>,
+        associations: Vec<SlurmAssociation>,
+        directories: Vec<DirectoryState>,
+    ) -> Self {
+        let mut details = Self {
+            username,
+            inspected,
+            ldap,
+            associations,
+            directories,
+            inconsistencies: Vec::new(),
+        };
+        details.inconsistencies = details.find_inconsistencies();
+        details
+    }
+
+    /// True if the user was found on none of the inspected systems.
+    pub fn is_unknown(&self) -> bool {
+        self.ldap.is_none() && self.associations.is_empty()
+    }
+
+    fn find_inconsistencies(&self) -> Vec<String> {
+        let mut found = Vec::new();
+        let both_inspected = self.inspected.ldap && self.inspected.slurm;
+        match &self.ldap {
+            None if both_inspected && !self.associations.is_empty() => {
+                found.push("User has associations in Slurm but no entry in LDAP".to_owned())
+            }
+            Some(_) if both_inspected && self.associations.is_empty() => {
+                found.push("User has an entry in LDAP but no association in Slurm".to_owned())
+            }
+            _ => (),
+        }
+
+        if let Some(ldap) = &self.ldap {
+            if let (Some(gid), None) = (ldap.gid_number, &ldap.group) {
+                found.push(format!(
+                    "Gid {} in LDAP matches none of the configured groups",
+                    gid
+                ));
+            }
+            let mut ldap_qos = ldap.qos.clone();
+            ldap_qos.sort();
+            for association in &self.associations {
+                let place = format!(
+                    "on cluster {} in account {}",
+                    association.cluster, association.account
+                );
+                if association.default_qos != ldap.default_qos {
+                    found.push(format!(
+                        "Default QOS in LDAP ({}) differs from Slurm ({}) {}",
+                        ldap.default_qos.as_deref().unwrap_or_default(),
+                        association.default_qos.as_deref().unwrap_or_default(),
+                        place
+                    ));
+                }
+                let mut slurm_qos = association.qos.clone();
+                slurm_qos.sort();
+                if slurm_qos != ldap_qos {
+                    found.push(format!(
+                        "QOS in LDAP ({}) differ from Slurm ({}) {}",
+                        ldap_qos.join(","),
+                        slurm_qos.join(","),
+                        place
+                    ));
+                }
+            }
+        }
+
+        for directory in &self.directories {
+            match &directory.owner {
+                None => found.push(format!(
+                    "Directory {} is missing on {}",
+                    directory.path, directory.host
+                )),
+                Some(owner) if owner.split(':').next() != Some(self.username.as_str()) => found
+                    .push(format!(
+                        "Directory {} on {} is owned by {} instead of {}",
+                        directory.path, directory.host, owner, self.username
+                    )),
+                _ => (),
+            }
+        }
+        found
+    }
+
+    /// Text for humans with a section per inspected system and the inconsistencies at the end.
+    ///
+    /// # Errors
+    ///
+    /// - If the directories could not be rendered as table. See [`output::render`].
+    pub fn to_pretty_text(&self) -> AppResult<String> {
+        let mut sections = vec![format!("User: {}", self.username)];
+        if self.inspected.ldap {
+            sections.push(match &self.ldap {
+                Some(ldap) => {
+                    let mut table = Table::new();
+                    for (title, value) in ldap.to_rows() {
+                        table.add_row(Row::new(vec![Cell::new(title), Cell::new(&value)]));
+                    }
+                    format!("LDAP:\n{}", table)
+                }
+                None => "LDAP: No entry".to_owned(),
+            });
+        }
+        if self.inspected.slurm {
+            sections.push(if self.associations.is_empty() {
+                "Slurm: No associations".to_owned()
+            } else {
+                format!(
+                    "Slurm:\n{}",
+                    slurm::associations_to_pretty_table(&self.associations)
+                )
+            });
+        }
+        if self.inspected.directories {
+            sections.push(format!(
+                "Directories:\n{}",
+                output::render(&self.directories, OutputFormat::Table)?
+            ));
+        }
+        sections.push(if self.inconsistencies.is_empty() {
+            "Inconsistencies: None found".to_owned()
+        } else {
+            let listed: Vec<String> = self
+                .inconsistencies
+                .iter()
+                .map(|inconsistency| format!("- {}", inconsistency))
+                .collect();
+            format!("Inconsistencies:\n{}", listed.join("\n"))
+        });
+        Ok(sections.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn ldap_details() -> LdapDetails {
+        LdapDetails {
+            uid_number: Some(10001),
+            gid_number: Some(4242),
+            default_qos: Some("basic".to_owned()),
+            qos: vec!["interactive".to_owned(), "basic".to_owned()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flag_inconsistencies_between_systems() {
+        let associations = SlurmAssociation::parse_all(
+            "alpha|student|somebody||basic|basic,interactive|||||1\n\
+             beta|student|somebody||advanced|advanced|||||1\n",
+        )
+        .unwrap();
+        let directories = vec![
+            DirectoryState {
+                host: "node1".to_owned(),
+                path: "/mnt/md0/user/somebody".to_owned(),
+                exists: false,
+                owner: None,
+                quota: None,
+            },
+            DirectoryState {
+                host: "home".to_owned(),
+                path: "/home/somebody".to_owned(),
+                exists: true,
+                owner: Some("root:root".to_owned()),
+                quota: None,
+            },
+        ];
+        let inspected = InspectedSystems {
+            ldap: true,
+            slurm: true,
+            directories: true,
+        };
+
+        let actual = UserDetails::new(
+            "somebody".to_owned(),
+            inspected,
+            Some(ldap_details()),
+            associations,
+            directories,
+        );
+
+        insta::assert_yaml_snapshot!(actual.inconsistencies);
+    }
+
+    #[test]
+    fn flag_user_missing_in_one_system_only_if_both_are_inspected() {
+        let inspected = InspectedSystems {
+            ldap: true,
+            slurm: true,
+            directories: false,
+        };
+        let missing_in_slurm = UserDetails::new(
+            "somebody".to_owned(),
+            inspected,
+            Some(ldap_details()),
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(missing_in_slurm.inconsistencies[0].contains("no association in Slurm"));
+
+        let only_ldap = UserDetails::new(
+            "somebody".to_owned(),
+            InspectedSystems {
+                slurm: false,
+                ..inspected
+            },
+            Some(ldap_details()),
+            Vec::new(),
+            Vec::new(),
+        );
+        assert!(!only_ldap
+            .inconsistencies
+            .iter()
+            .any(|inconsistency| inconsistency.contains("Slurm")));
+    }
+}
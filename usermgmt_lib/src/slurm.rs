@@ -1,179 +1,594 @@
-use std::process::Command;
-
-use anyhow::{anyhow, Context};
-use log::{debug, info};
+use anyhow::{anyhow, bail, Context};
+use log::{debug, info, warn};
 
+mod association_limits;
 mod command_builder;
-mod listed_user;
-use crate::{config::MgmtConfig, prelude::AppResult, ssh};
+mod live_qos;
+mod sacctmgr_outcome;
+mod slurm_association;
+mod slurmrestd;
+use crate::{config::MgmtConfig, prelude::AppResult};
 
+pub use self::association_limits::AssociationLimits;
 use self::command_builder::CommandBuilder;
+pub use self::command_builder::{AccountAttributes, QosAttributes};
 
-use crate::ssh::{SshConnection, SshCredentials};
+use crate::executor::{CommandExecutor, CommandOutput, ExecCommand, LocalOrRemoteExecutor};
 use crate::{ChangesToUser, NewEntity};
 
-pub use listed_user::ListedUser;
+pub use live_qos::QosCache;
+pub use sacctmgr_outcome::SacctmgrOutcome;
+pub use slurm_association::{associations_to_pretty_table, SlurmAssociation};
+pub use slurmrestd::{SlurmRestClient, SLURM_JWT_ENV};
 
-/// Creates a user in a slurm database on a remote machine over ssh
-pub fn add_slurm_user<C>(
+/// Creates a user in a slurm database via the given executor
+/// QOS known by slurm are taken from `qos_cache` or fetched into it.
+pub fn add_slurm_user<E>(
     entity: &NewEntity,
     config: &MgmtConfig,
-    session: &SshConnection<C>,
+    executor: &E,
+    qos_cache: &QosCache,
 ) -> AppResult
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
+    let qos: Vec<String> = entity.qos.clone().into();
+    ensure_qos_known_by_slurm(
+        &qos,
+        &entity.default_qos.to_string(),
+        config,
+        executor,
+        qos_cache,
+    )?;
     let action = CommandBuilder::new_add(
         entity.username.to_string(),
-        entity.group.id(),
+        entity.accounts.clone(),
+        entity.default_account.clone(),
         entity.default_qos.to_string(),
         entity.qos.clone().into(),
+        entity.limits.clone(),
+        entity.clusters.clone(),
     );
 
-    run_slurm_action(action, config, session).with_context(|| {
+    run_slurm_action(action, config, executor).with_context(|| {
         format!(
-            "Failed to add user {} with account {}",
-            entity.username, entity.group
+            "Failed to add user {} with accounts {}",
+            entity.username,
+            entity.accounts.join(",")
         )
     })?;
 
     info!(
-        "Added user {} with account {}, qos {:?} and default qos {}",
-        entity.username, entity.group, entity.qos, entity.default_qos
+        "Added user {} with accounts {}, qos {:?} and default qos {}",
+        entity.username,
+        entity.accounts.join(","),
+        entity.qos,
+        entity.default_qos
     );
+    if !entity.clusters.is_empty() {
+        info!(
+            "User {} was added on clusters {}",
+            entity.username,
+            entity.clusters.join(",")
+        );
+    }
+    if let Some(account) = entity.coordinated_account() {
+        add_slurm_coordinators(account, &[entity.username.to_string()], config, executor)?;
+    }
 
     Ok(())
 }
 
-/// Deletes a user in a slurm database
+/// Deletes a user in a slurm database.
+/// With parameter `clusters` the user is only removed from the given clusters.
+/// Otherwise the user is removed from all clusters.
 ///
 /// # Errors
 ///
+/// - If the clusters are not part of the configured clusters. See [`MgmtConfig::ensure_known_clusters`]
 /// - See [`run_slurm_action`]
-pub fn delete_slurm_user<C>(
+pub fn delete_slurm_user<E>(
     user: &str,
+    clusters: &[String],
     config: &MgmtConfig,
-    session: &SshConnection<C>,
+    executor: &E,
 ) -> AppResult
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
-    let action = CommandBuilder::new_delete(user.to_string());
-    run_slurm_action(action, config, session)
+    config.ensure_known_clusters(clusters)?;
+    let action = CommandBuilder::new_delete(user.to_string(), clusters.to_vec());
+    run_slurm_action(action, config, executor)
         .with_context(|| format!("Failed to delete user with name {}", user))?;
-    info!("Deleted user with name {}", user);
+    if clusters.is_empty() {
+        info!("Deleted user with name {}", user);
+    } else {
+        info!(
+            "Removed user with name {} from clusters {}",
+            user,
+            clusters.join(",")
+        );
+    }
     Ok(())
 }
 
-/// Modifies a user in a slurm database via SSH session on a remote machine
-/// It currently only modifies the quality of services of a user !
+/// Modifies a user in a slurm database via the given executor
+/// It currently only modifies the quality of services and the limits of a user !
+/// QOS known by slurm are taken from `qos_cache` or fetched into it.
 ///
 /// # Errors
 ///
 /// - See [`run_slurm_action`]
-pub fn modify_slurm_user<C>(
+pub fn modify_slurm_user<E>(
     modifiable: &ChangesToUser,
     config: &MgmtConfig,
-    session: &SshConnection<C>,
+    executor: &E,
+    qos_cache: &QosCache,
 ) -> AppResult
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     if let Some((qos, default_qos)) = modifiable.may_qos_and_default_qos() {
+        ensure_qos_known_by_slurm(&qos, &default_qos, config, executor, qos_cache)?;
         let action = CommandBuilder::new_modify_qos_default_qows(
             modifiable.username.to_string(),
             default_qos,
             qos,
         );
 
-        run_slurm_action(action, config, session)?;
+        run_slurm_action(action, config, executor)?;
+    }
+    if !modifiable.limits.is_empty() {
+        let action =
+            CommandBuilder::new_modify_limits(modifiable.username.to_string(), &modifiable.limits);
+        run_slurm_action(action, config, executor)
+            .with_context(|| format!("Failed to change limits of user {}", modifiable.username))?;
+        info!(
+            "Changed limits of user {} to {:?}",
+            modifiable.username, modifiable.limits
+        );
     }
     Ok(())
 }
 
-/// Lists all users in slurm database
+/// Lists all associations in slurm database.
+/// Associations of accounts alone are included and have no user.
+/// If the field `clusters` of the configuration is not empty, only associations on these clusters are listed.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+/// - If the output of slurm can not be parsed. See [`SlurmAssociation::parse_all`]
+pub fn list_associations<E>(config: &MgmtConfig, executor: &E) -> AppResult<Vec<SlurmAssociation>>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show(config.clusters.clone());
+    let output = run_slurm_action(action, config, executor)?;
+
+    SlurmAssociation::parse_all(&output)
+}
+
+/// Lists the associations of the user aka parameter `username` like [`list_associations`].
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+/// - If the output of slurm can not be parsed. See [`SlurmAssociation::parse_all`]
+pub fn list_associations_of_user<E>(
+    username: &str,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult<Vec<SlurmAssociation>>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show_of_user(username.to_owned(), config.clusters.clone());
+    let output = run_slurm_action(action, config, executor)?;
+
+    SlurmAssociation::parse_all(&output)
+}
+
+/// Creates an account in a slurm database via the given executor
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn add_slurm_account<E>(
+    name: &str,
+    attributes: AccountAttributes,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_add_account(name.to_owned(), attributes);
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to add account {}", name))?;
+    info!("Added account {}", name);
+    Ok(())
+}
+
+/// Changes parent, description or organization of an account in a slurm database
+///
+/// # Errors
+///
+/// - If no attribute is given to change
+/// - See [`run_slurm_action`]
+pub fn modify_slurm_account<E>(
+    name: &str,
+    attributes: AccountAttributes,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    if attributes.is_empty() {
+        bail!("Nothing given to modify for account {}", name);
+    }
+    let action = CommandBuilder::new_modify_account(name.to_owned(), attributes);
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to modify account {}", name))?;
+    info!("Modified account {}", name);
+    Ok(())
+}
+
+/// Deletes an account in a slurm database
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn delete_slurm_account<E>(name: &str, config: &MgmtConfig, executor: &E) -> AppResult
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_delete_account(name.to_owned());
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to delete account {}", name))?;
+    info!("Deleted account {}", name);
+    Ok(())
+}
+
+/// Lists all accounts in slurm database
 ///
 /// # Errors
 ///
 /// See [`run_slurm_action`]
-pub fn list_users<T>(
+pub fn list_accounts<E>(config: &MgmtConfig, executor: &E, parseable: bool) -> AppResult<String>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show_accounts(parseable);
+    run_slurm_action(action, config, executor)
+}
+
+/// Makes the given users coordinators of the account with the parameter `account`.
+/// Coordinators can manage the account and its users themselves.
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn add_slurm_coordinators<E>(
+    account: &str,
+    users: &[String],
     config: &MgmtConfig,
-    session: &SshConnection<T>,
-    parseable: bool,
-) -> AppResult<String>
+    executor: &E,
+) -> AppResult
 where
-    T: SshCredentials,
+    E: CommandExecutor,
 {
-    let action = CommandBuilder::new_show(parseable);
-    let output = run_slurm_action(action, config, session)?;
+    let action = CommandBuilder::new_add_coordinators(account.to_owned(), users.to_vec());
+    run_slurm_action(action, config, executor).with_context(|| {
+        format!(
+            "Failed to add coordinators {} to account {}",
+            users.join(","),
+            account
+        )
+    })?;
+    info!(
+        "Added coordinators {} to account {}",
+        users.join(","),
+        account
+    );
+    Ok(())
+}
 
-    Ok(output)
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn delete_slurm_coordinators<E>(
+    account: &str,
+    users: &[String],
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_delete_coordinators(account.to_owned(), users.to_vec());
+    run_slurm_action(action, config, executor).with_context(|| {
+        format!(
+            "Failed to remove coordinators {} from account {}",
+            users.join(","),
+            account
+        )
+    })?;
+    info!(
+        "Removed coordinators {} from account {}",
+        users.join(","),
+        account
+    );
+    Ok(())
 }
 
-/// Runs the slurm command on a local machine or remotely somewhere else.
-/// Whether run remotely or locally depends on the parameter `config`.
+/// Lists every account with its coordinators in slurm database
 ///
 /// # Errors
 ///
-/// - If running the command remotely fails. See [`run_remote_report_slurm_cmd`]
-/// - If running the command on the local machine. See [`run_remote_report_slurm_cmd`]
-pub fn run_slurm_action<C>(
+/// See [`run_slurm_action`]
+pub fn list_coordinators<E>(config: &MgmtConfig, executor: &E, parseable: bool) -> AppResult<String>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show_coordinators(parseable);
+    run_slurm_action(action, config, executor)
+}
+
+/// Creates a QOS in a slurm database via the given executor
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn add_slurm_qos<E>(
+    name: &str,
+    attributes: QosAttributes,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_add_qos(name.to_owned(), attributes);
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to add QOS {}", name))?;
+    info!("Added QOS {}", name);
+    Ok(())
+}
+
+/// Changes priority, limits or preemption settings of a QOS in a slurm database
+///
+/// # Errors
+///
+/// - If no attribute is given to change
+/// - See [`run_slurm_action`]
+pub fn modify_slurm_qos<E>(
+    name: &str,
+    attributes: QosAttributes,
+    config: &MgmtConfig,
+    executor: &E,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    if attributes.is_empty() {
+        bail!("Nothing given to modify for QOS {}", name);
+    }
+    let action = CommandBuilder::new_modify_qos(name.to_owned(), attributes);
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to modify QOS {}", name))?;
+    info!("Modified QOS {}", name);
+    Ok(())
+}
+
+/// Deletes a QOS in a slurm database
+///
+/// # Errors
+///
+/// - See [`run_slurm_action`]
+pub fn delete_slurm_qos<E>(name: &str, config: &MgmtConfig, executor: &E) -> AppResult
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_delete_qos(name.to_owned());
+    run_slurm_action(action, config, executor)
+        .with_context(|| format!("Failed to delete QOS {}", name))?;
+    info!("Deleted QOS {}", name);
+    Ok(())
+}
+
+/// Lists all QOS with their priority, limits and preemption settings in slurm database
+///
+/// # Errors
+///
+/// See [`run_slurm_action`]
+pub fn list_qos<E>(config: &MgmtConfig, executor: &E, parseable: bool) -> AppResult<String>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show_qos(parseable);
+    run_slurm_action(action, config, executor)
+}
+
+/// Returns the names of all QOS within the slurm database.
+/// The QOS are only fetched once for the given `qos_cache`. See [`QosCache`]
+///
+/// # Errors
+///
+/// See [`run_slurm_action`]
+pub fn fetch_qos<E>(
+    config: &MgmtConfig,
+    executor: &E,
+    qos_cache: &QosCache,
+) -> AppResult<Vec<String>>
+where
+    E: CommandExecutor,
+{
+    qos_cache.get_or_fetch(config, executor)
+}
+
+/// Checks the QOS against the QOS within the slurm database
+/// if `validate_qos_against_slurm` is set in `config`.
+///
+/// # Errors
+///
+/// - If one of the QOS does not exist in slurm. See [`QosCache::ensure_known`]
+fn ensure_qos_known_by_slurm<E>(
+    qos: &[String],
+    default_qos: &str,
+    config: &MgmtConfig,
+    executor: &E,
+    qos_cache: &QosCache,
+) -> AppResult
+where
+    E: CommandExecutor,
+{
+    if !config.validate_qos_against_slurm {
+        return Ok(());
+    }
+    let to_check: Vec<String> = qos
+        .iter()
+        .cloned()
+        .chain(std::iter::once(default_qos.to_owned()))
+        .collect();
+    qos_cache.ensure_known(&to_check, config, executor)
+}
+
+/// Returns true if the user aka parameter `username` exists in the slurm database.
+///
+/// # Errors
+///
+/// See [`run_slurm_action`]
+pub fn user_exists<E>(username: &str, config: &MgmtConfig, executor: &E) -> AppResult<bool>
+where
+    E: CommandExecutor,
+{
+    let action = CommandBuilder::new_show_user(username.to_owned());
+    let output = run_slurm_action(action, config, executor)?;
+
+    Ok(!output.trim().is_empty())
+}
+
+/// Returns the executor for slurm commands.
+/// Slurm commands are run over ssh via `remote` if `run_slurm_remote` is set in `config`,
+/// otherwise they are run on the local machine.
+pub fn executor_from_config<'a, R>(
+    config: &MgmtConfig,
+    remote: &'a R,
+) -> LocalOrRemoteExecutor<'a, R>
+where
+    R: CommandExecutor,
+{
+    LocalOrRemoteExecutor::new(config.run_slurm_remote, remote)
+}
+
+/// Runs the slurm commands via the given executor.
+/// Whether run remotely or locally depends on the parameter `executor`.
+/// See [`executor_from_config`] for choosing it by the configuration.
+///
+/// If `use_slurmrestd` is set in `config`, the commands are sent to slurmrestd instead
+/// and the parameter `executor` is not used. See [`SlurmRestClient`]
+///
+/// # Errors
+///
+/// - If running one of the commands fails. See [`run_and_report_slurm_cmd`]
+pub fn run_slurm_action<E>(
     mut actions: CommandBuilder,
     config: &MgmtConfig,
-    session: &SshConnection<C>,
+    executor: &E,
 ) -> AppResult<String>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
     let mut output = String::new();
     actions = actions
         .immediate(true)
         .sacctmgr_path(config.sacctmgr_path.clone());
-    if config.run_slurm_remote {
-        for cmd in actions.remote_commands() {
-            debug!("Run remote slurm command ({})", &cmd);
-            let next_output = run_remote_report_slurm_cmd(session, &cmd)?;
-            output.push_str(&next_output);
-        }
-    } else {
-        for cmd in actions.local_commands() {
-            let next_output = run_local_and_report_slurm_cmd(cmd)?;
-            output.push_str(&next_output);
+    if config.use_slurmrestd {
+        let client = SlurmRestClient::from_config(config)?;
+        let (username, parts) = actions.into_parts();
+        for (sub_command, cmd) in parts {
+            debug!("Run slurm command ({}) via slurmrestd", &cmd);
+            let adds = sub_command.adds();
+            let next_output = client.run(&username, sub_command).with_context(|| {
+                format!(
+                    "Error: For slurm command ({}) via slurmrestd at {}",
+                    cmd, config.slurmrestd_url
+                )
+            })?;
+            output.push_str(&report_slurm_output(&cmd, next_output, adds)?);
         }
+        return Ok(output);
+    }
+    let (_, parts) = actions.into_parts();
+    for (sub_command, cmd) in parts {
+        debug!("Run slurm command ({})", &cmd);
+        let next_output = run_and_report_slurm_cmd(executor, &cmd, sub_command.adds())?;
+        output.push_str(&next_output);
     }
     Ok(output)
 }
 
+/// Runs a single slurm command and returns its stdout.
+/// Adding an already existing entity or deleting a non-existing one is not treated as an error.
+/// This way adding or deleting a user can be repeated without failing.
+/// Parameter `adds` is true if the command adds an entity. See [`SacctmgrOutcome::from_output`]
+///
 /// # Errors
 ///
-/// - If execution of the command fails. See [`SshConnection::exec`].
+/// - If execution of the command fails. See [`CommandExecutor::run`].
 /// - If the exit code of executed command is an error code.
-pub fn run_remote_report_slurm_cmd<C>(session: &SshConnection<C>, cmd: &str) -> AppResult<String>
+///   The error contains the message which sacctmgr printed.
+pub fn run_and_report_slurm_cmd<E>(executor: &E, cmd: &ExecCommand, adds: bool) -> AppResult<String>
 where
-    C: SshCredentials,
+    E: CommandExecutor,
 {
-    let (exit_code, output) = ssh::run_remote_command(session, cmd)
-        .with_context(|| format!("Error: For remote slurm command ({}).", cmd,))?;
+    let output = executor.run(cmd).with_context(|| {
+        format!(
+            "Error: For slurm command ({}). Is the path to sacctmgr specified in your config correct?",
+            cmd
+        )
+    })?;
 
-    if exit_code == 0 {
-        debug!("Success: For remote slurm command ({})", cmd);
-        Ok(output)
-    } else {
-        Err(anyhow!(
-            "Error: For remote slurm command ({}) with error code {}",
+    report_slurm_output(cmd, output, adds)
+}
+
+/// Classifies the output of a slurm command and returns its stdout.
+/// See [`run_and_report_slurm_cmd`]
+fn report_slurm_output(cmd: &ExecCommand, output: CommandOutput, adds: bool) -> AppResult<String> {
+    match SacctmgrOutcome::from_output(output, adds) {
+        SacctmgrOutcome::Done(stdout) => {
+            debug!("Success: For slurm command ({})", cmd);
+            Ok(stdout)
+        }
+        SacctmgrOutcome::AlreadyExists => {
+            warn!(
+                "Slurm command ({}) changed nothing: Entity exists already",
+                cmd
+            );
+            Ok(String::new())
+        }
+        SacctmgrOutcome::NothingDeleted => {
+            warn!(
+                "Slurm command ({}) changed nothing: Entity does not exist",
+                cmd
+            );
+            Ok(String::new())
+        }
+        SacctmgrOutcome::UnknownQos(message) => Err(anyhow!(
+            "Error: For slurm command ({}) a given QOS is not known by slurm: {}",
             cmd,
-            exit_code
-        ))
+            message
+        )),
+        SacctmgrOutcome::Failed { exit_code, message } => Err(anyhow!(
+            "Error: For slurm command ({}) with error code {}: {}",
+            cmd,
+            exit_code,
+            message
+        )),
     }
 }
 
-/// # Errors
-///
-/// - If output of command could not be retrieved
-pub fn run_local_and_report_slurm_cmd(mut command: Command) -> AppResult<String> {
-    let output = command.output().context(
-        "Unable to execute sacctmgr command. Is the path specified in your config correct?",
-    )?;
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
+#[cfg(test)]
+mod testing;
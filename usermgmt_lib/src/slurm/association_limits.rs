@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{config::MgmtConfig, Group};
+
+pub(super) const GRP_TRES: &str = "GrpTRES";
+pub(super) const MAX_JOBS: &str = "MaxJobs";
+pub(super) const MAX_SUBMIT_JOBS: &str = "MaxSubmitJobs";
+pub(super) const MAX_WALL: &str = "MaxWall";
+pub(super) const FAIRSHARE: &str = "Fairshare";
+pub(super) const PARTITION: &str = "Partition";
+
+/// Limits of the slurm association of an user.
+/// Fields with `None` are left out of the command and slurm keeps its current value.
+///
+/// Partitions can only be given while adding an user, since slurm creates
+/// an own association for every partition.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssociationLimits {
+    /// Limit of trackable resources for all running jobs together, e.g. `cpu=64,gres/gpu=2`.
+    pub grp_tres: Option<String>,
+    pub max_jobs: Option<u32>,
+    pub max_submit_jobs: Option<u32>,
+    /// Maximum wall time per job, e.g. `2-00:00:00`.
+    pub max_wall: Option<String>,
+    pub fairshare: Option<u32>,
+    pub partitions: Vec<String>,
+}
+
+impl AssociationLimits {
+    /// Default limits for the given group as configured by `student_limits` or `staff_limits`.
+    pub fn from_group(group: Group, config: &MgmtConfig) -> Self {
+        match group {
+            Group::Staff | Group::Faculty => config.staff_limits.clone(),
+            Group::Student => config.student_limits.clone(),
+        }
+    }
+
+    /// Every limit given by the parameter `overrides` replaces the limit of `self`.
+    pub fn overridden_by(self, overrides: Self) -> Self {
+        Self {
+            grp_tres: overrides.grp_tres.or(self.grp_tres),
+            max_jobs: overrides.max_jobs.or(self.max_jobs),
+            max_submit_jobs: overrides.max_submit_jobs.or(self.max_submit_jobs),
+            max_wall: overrides.max_wall.or(self.max_wall),
+            fairshare: overrides.fairshare.or(self.fairshare),
+            partitions: if overrides.partitions.is_empty() {
+                self.partitions
+            } else {
+                overrides.partitions
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Keys and values of all given limits except the partitions
+    /// for the set clause of sacctmgr.
+    pub(super) fn to_set(&self) -> Vec<(&'static str, Vec<String>)> {
+        [
+            (GRP_TRES, self.grp_tres.clone()),
+            (MAX_JOBS, self.max_jobs.map(|value| value.to_string())),
+            (
+                MAX_SUBMIT_JOBS,
+                self.max_submit_jobs.map(|value| value.to_string()),
+            ),
+            (MAX_WALL, self.max_wall.clone()),
+            (FAIRSHARE, self.fairshare.map(|value| value.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, vec![value])))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn take_group_defaults_and_override_given_limits() {
+        let config = MgmtConfig {
+            student_limits: AssociationLimits {
+                max_jobs: Some(10),
+                max_wall: Some("1-00:00:00".to_owned()),
+                partitions: vec!["cpu".to_owned()],
+                ..Default::default()
+            },
+            staff_limits: AssociationLimits {
+                max_jobs: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let actual = AssociationLimits::from_group(Group::Student, &config).overridden_by(
+            AssociationLimits {
+                max_jobs: Some(20),
+                fairshare: Some(5),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            AssociationLimits {
+                max_jobs: Some(20),
+                max_wall: Some("1-00:00:00".to_owned()),
+                fairshare: Some(5),
+                partitions: vec!["cpu".to_owned()],
+                ..Default::default()
+            },
+            actual
+        );
+        assert_eq!(
+            Some(100),
+            AssociationLimits::from_group(Group::Faculty, &config).max_jobs
+        );
+    }
+
+    #[test]
+    fn leave_out_partitions_and_missing_limits_from_set_clause() {
+        let limits = AssociationLimits {
+            grp_tres: Some("cpu=64,gres/gpu=2".to_owned()),
+            max_submit_jobs: Some(50),
+            partitions: vec!["gpu".to_owned()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            vec![
+                (GRP_TRES, vec!["cpu=64,gres/gpu=2".to_owned()]),
+                (MAX_SUBMIT_JOBS, vec!["50".to_owned()]),
+            ],
+            limits.to_set()
+        );
+    }
+}
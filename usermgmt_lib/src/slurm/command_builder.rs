@@ -1,7 +1,8 @@
-use crate::Group;
+use super::association_limits::{AssociationLimits, MAX_WALL, PARTITION};
+use super::slurm_association::FORMAT_FIELDS;
+use crate::executor::ExecCommand;
 use std::collections::HashMap;
 use std::iter;
-use std::process::Command;
 
 const SACCTMG_NAME: &str = "sacctmgr";
 const IMMEDIATE: &str = "--immediate";
@@ -12,42 +13,261 @@ const SUB_COMMAND_DELETE: &str = "delete";
 
 const SET: &str = "set";
 const ASSOCIATION: &str = "assoc";
-const USER: &str = "User";
-const ACCOUNT: &str = "Account";
-const DEFAULT_QOS: &str = "DefaultQOS";
-const QOS: &str = "QOS";
+pub(super) const CLUSTER: &str = "Cluster";
+pub(super) const USER: &str = "User";
+pub(super) const ACCOUNT: &str = "Account";
+const DEFAULT_ACCOUNT: &str = "DefaultAccount";
+const PARENT: &str = "Parent";
+const DESCRIPTION: &str = "Description";
+const ORGANIZATION: &str = "Organization";
+pub(super) const DEFAULT_QOS: &str = "DefaultQOS";
+pub(super) const QOS: &str = "QOS";
 const SLURM_PRASEABLE_ARG: &str = "--parsable";
+const SLURM_PRASEABLE_2_ARG: &str = "--parsable2";
+const NO_HEADER: &str = "--noheader";
+const USER_ENTITY: &str = "user";
+const COORDINATOR_ENTITY: &str = "coordinator";
+const WITH_COORDINATORS: &str = "withcoord";
+const NAMES: &str = "Names";
+const COORDINATORS: &str = "Coordinators";
+const QOS_ENTITY: &str = "qos";
+const NAME: &str = "Name";
+const PRIORITY: &str = "Priority";
+const MAX_TRES_PER_USER: &str = "MaxTRESPerUser";
+const PREEMPT: &str = "Preempt";
+const PREEMPT_MODE: &str = "PreemptMode";
 
-enum SlurmSubCommand {
-    Add { group: Group },
-    Delete,
-    Modify(HashMap<&'static str, Vec<String>>),
-    Show { parseable: bool },
+/// Parent, description and organization of a slurm account.
+/// Fields with `None` are left out of the command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountAttributes {
+    pub parent: Option<String>,
+    pub description: Option<String>,
+    pub organization: Option<String>,
 }
 
+impl AccountAttributes {
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_none() && self.description.is_none() && self.organization.is_none()
+    }
+
+    fn into_args(self) -> Vec<String> {
+        [
+            (DESCRIPTION, self.description),
+            (ORGANIZATION, self.organization),
+            (PARENT, self.parent),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+        .collect()
+    }
+}
+
+/// Priority, limits and preemption settings of a slurm QOS.
+/// Fields with `None` are left out of the command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QosAttributes {
+    pub priority: Option<u32>,
+    /// Limit of trackable resources per user, e.g. `cpu=64,gres/gpu=2`.
+    pub max_tres_per_user: Option<String>,
+    /// Maximum wall time per job, e.g. `2-00:00:00`.
+    pub max_wall: Option<String>,
+    /// QOS which can be preempted by jobs of this QOS.
+    pub preempt: Option<Vec<String>>,
+    /// How jobs of this QOS are preempted, e.g. `cancel`, `requeue` or `suspend`.
+    pub preempt_mode: Option<String>,
+}
+
+impl QosAttributes {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn into_args(self) -> Vec<String> {
+        [
+            (MAX_TRES_PER_USER, self.max_tres_per_user),
+            (MAX_WALL, self.max_wall),
+            (PREEMPT, self.preempt.map(|preempt| preempt.join(","))),
+            (PREEMPT_MODE, self.preempt_mode),
+            (PRIORITY, self.priority.map(|priority| priority.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, value)))
+        .collect()
+    }
+}
+
+/// Sub commands of sacctmgr with their arguments.
+/// Besides building sacctmgr commands, they are translated into requests for slurmrestd.
+#[derive(Debug, Clone)]
+pub(super) enum SlurmSubCommand {
+    Add {
+        accounts: Vec<String>,
+        default_account: Option<String>,
+        partitions: Vec<String>,
+        clusters: Vec<String>,
+    },
+    /// Without clusters the user is deleted on all clusters.
+    Delete {
+        clusters: Vec<String>,
+    },
+    /// Without clusters the associations of the user on all clusters are modified.
+    Modify {
+        to_set: HashMap<&'static str, Vec<String>>,
+        clusters: Vec<String>,
+    },
+    /// Without clusters the associations of all clusters are shown.
+    Show {
+        user: Option<String>,
+        clusters: Vec<String>,
+    },
+    ShowUser,
+    ShowQosNames,
+    AddAccount {
+        name: String,
+        attributes: AccountAttributes,
+    },
+    ModifyAccount {
+        name: String,
+        attributes: AccountAttributes,
+    },
+    DeleteAccount {
+        name: String,
+    },
+    ShowAccounts {
+        parseable: bool,
+    },
+    AddCoordinators {
+        account: String,
+        users: Vec<String>,
+    },
+    DeleteCoordinators {
+        account: String,
+        users: Vec<String>,
+    },
+    ShowCoordinators {
+        parseable: bool,
+    },
+    AddQos {
+        name: String,
+        attributes: QosAttributes,
+    },
+    ModifyQos {
+        name: String,
+        attributes: QosAttributes,
+    },
+    DeleteQos {
+        name: String,
+    },
+    ShowQos {
+        parseable: bool,
+    },
+}
+
+impl SlurmSubCommand {
+    /// True if the sub command adds an entity to slurm.
+    pub(super) fn adds(&self) -> bool {
+        matches!(
+            self,
+            Self::Add { .. }
+                | Self::AddAccount { .. }
+                | Self::AddCoordinators { .. }
+                | Self::AddQos { .. }
+        )
+    }
+}
+
+/// Condition like `Cluster=a,b` which restricts a sub command to the given clusters.
+/// Returns `None` without any cluster, so the sub command applies to all clusters.
+fn cluster_condition(clusters: &[String]) -> Option<String> {
+    (!clusters.is_empty()).then(|| format!("{}={}", CLUSTER, clusters.join(",")))
+}
+
+/// Arguments for sacctmgr of the given sub command.
+/// The parameter `username` is only used by sub commands for an user.
 fn from_username(value: SlurmSubCommand, username: String) -> Vec<String> {
     match value {
-        SlurmSubCommand::Add { group } => {
-            vec![
+        SlurmSubCommand::Add {
+            accounts,
+            default_account,
+            partitions,
+            clusters,
+        } => {
+            let mut command = vec![
                 SUB_COMMAND_ADD.into(),
                 USER.into(),
                 username,
-                format!("{}={}", ACCOUNT, group.to_string()),
-            ]
+                format!("{}={}", ACCOUNT, accounts.join(",")),
+            ];
+            if let Some(default_account) = default_account {
+                command.push(format!("{}={}", DEFAULT_ACCOUNT, default_account));
+            }
+            if !partitions.is_empty() {
+                command.push(format!("{}={}", PARTITION, partitions.join(",")));
+            }
+            command.extend(cluster_condition(&clusters));
+            command
+        }
+        SlurmSubCommand::Delete { clusters } => {
+            vec![SUB_COMMAND_DELETE.into(), USER.into(), username]
+                .into_iter()
+                .chain(cluster_condition(&clusters))
+                .collect()
         }
-        SlurmSubCommand::Delete => vec![SUB_COMMAND_DELETE.into(), USER.into(), username],
-        SlurmSubCommand::Modify(map) => {
-            let mut to_set: Vec<String> = map
+        SlurmSubCommand::Modify { to_set, clusters } => {
+            let mut to_set: Vec<String> = to_set
                 .into_iter()
                 .map(|(key, values)| format!("{}={}", key, values.join(",")))
                 .collect();
             to_set.sort();
-            vec![SUB_COMMAND_MODIFY.into(), USER.into(), username, SET.into()]
+            vec![SUB_COMMAND_MODIFY.into(), USER.into(), username]
                 .into_iter()
+                .chain(cluster_condition(&clusters))
+                .chain(iter::once(SET.into()))
                 .chain(to_set)
                 .collect()
         }
-        SlurmSubCommand::Show { parseable } => {
+        SlurmSubCommand::Show { user, clusters } => vec![
+            NO_HEADER.into(),
+            SLURM_PRASEABLE_2_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            ASSOCIATION.into(),
+        ]
+        .into_iter()
+        .chain(user.map(|user| format!("{}={}", USER, user)))
+        .chain(cluster_condition(&clusters))
+        .chain(iter::once(format!("format={}", FORMAT_FIELDS.join(","))))
+        .collect(),
+        SlurmSubCommand::ShowUser => vec![
+            NO_HEADER.into(),
+            SLURM_PRASEABLE_2_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            USER_ENTITY.into(),
+            username,
+        ],
+        SlurmSubCommand::ShowQosNames => vec![
+            NO_HEADER.into(),
+            SLURM_PRASEABLE_2_ARG.into(),
+            SUB_COMMAND_SHOW.into(),
+            QOS_ENTITY.into(),
+            format!("format={}", NAME),
+        ],
+        SlurmSubCommand::AddAccount { name, attributes } => {
+            vec![SUB_COMMAND_ADD.into(), ACCOUNT.into(), name]
+                .into_iter()
+                .chain(attributes.into_args())
+                .collect()
+        }
+        SlurmSubCommand::ModifyAccount { name, attributes } => {
+            vec![SUB_COMMAND_MODIFY.into(), ACCOUNT.into(), name, SET.into()]
+                .into_iter()
+                .chain(attributes.into_args())
+                .collect()
+        }
+        SlurmSubCommand::DeleteAccount { name } => {
+            vec![SUB_COMMAND_DELETE.into(), ACCOUNT.into(), name]
+        }
+        SlurmSubCommand::ShowAccounts { parseable } => {
             let mut command = if parseable {
                 vec![SLURM_PRASEABLE_ARG.to_owned()]
             } else {
@@ -55,17 +275,80 @@ fn from_username(value: SlurmSubCommand, username: String) -> Vec<String> {
             };
             command.extend_from_slice(&[
                 SUB_COMMAND_SHOW.into(),
-                ASSOCIATION.into(),
-                format!("format={}%30,{},{},{}%80", USER, ACCOUNT, DEFAULT_QOS, QOS),
+                ACCOUNT.into(),
+                format!(
+                    "format={}%30,{}%50,{}%30",
+                    ACCOUNT, DESCRIPTION, ORGANIZATION
+                ),
+            ]);
+            command
+        }
+        SlurmSubCommand::AddCoordinators { account, users } => vec![
+            SUB_COMMAND_ADD.into(),
+            COORDINATOR_ENTITY.into(),
+            format!("{}={}", ACCOUNT, account),
+            format!("{}={}", NAMES, users.join(",")),
+        ],
+        SlurmSubCommand::DeleteCoordinators { account, users } => vec![
+            SUB_COMMAND_DELETE.into(),
+            COORDINATOR_ENTITY.into(),
+            format!("{}={}", ACCOUNT, account),
+            format!("{}={}", NAMES, users.join(",")),
+        ],
+        SlurmSubCommand::ShowCoordinators { parseable } => {
+            let mut command = if parseable {
+                vec![SLURM_PRASEABLE_ARG.to_owned()]
+            } else {
+                Vec::new()
+            };
+            command.extend_from_slice(&[
+                SUB_COMMAND_SHOW.into(),
+                ACCOUNT.into(),
+                WITH_COORDINATORS.into(),
+                format!("format={}%30,{}%60", ACCOUNT, COORDINATORS),
+            ]);
+            command
+        }
+        SlurmSubCommand::AddQos { name, attributes } => {
+            vec![SUB_COMMAND_ADD.into(), QOS_ENTITY.into(), name]
+                .into_iter()
+                .chain(attributes.into_args())
+                .collect()
+        }
+        SlurmSubCommand::ModifyQos { name, attributes } => vec![
+            SUB_COMMAND_MODIFY.into(),
+            QOS_ENTITY.into(),
+            name,
+            SET.into(),
+        ]
+        .into_iter()
+        .chain(attributes.into_args())
+        .collect(),
+        SlurmSubCommand::DeleteQos { name } => {
+            vec![SUB_COMMAND_DELETE.into(), QOS_ENTITY.into(), name]
+        }
+        SlurmSubCommand::ShowQos { parseable } => {
+            let mut command = if parseable {
+                vec![SLURM_PRASEABLE_ARG.to_owned()]
+            } else {
+                Vec::new()
+            };
+            command.extend_from_slice(&[
+                SUB_COMMAND_SHOW.into(),
+                QOS_ENTITY.into(),
+                format!(
+                    "format={}%20,{},{}%30,{},{}%30,{}",
+                    NAME, PRIORITY, MAX_TRES_PER_USER, MAX_WALL, PREEMPT, PREEMPT_MODE
+                ),
             ]);
             command
         }
     }
 }
 
-/// Builder to construct slurm commands for execution as local process commands or as strings for
-/// ssh remote execution.
-/// One or more commands are added before the call of [`remote_commands`] for remote or [`local_commanded`] for local
+/// Builder to construct slurm commands for execution by a [`crate::executor::CommandExecutor`]
+/// on the local machine or over ssh on a remote machine.
+/// One or more commands are added before the call of [`CommandBuilder::commands`].
 pub struct CommandBuilder {
     sub_commands: Vec<SlurmSubCommand>,
     username: String,
@@ -74,20 +357,57 @@ pub struct CommandBuilder {
 }
 
 impl CommandBuilder {
-    pub fn new_delete(username: String) -> Self {
-        Self::new_inner(username, vec![SlurmSubCommand::Delete])
+    /// Adds a command to delete the user aka parameter `username` on the given clusters.
+    /// Without any cluster the user is deleted on all clusters.
+    pub fn new_delete(username: String, clusters: Vec<String>) -> Self {
+        Self::new_inner(username, vec![SlurmSubCommand::Delete { clusters }])
     }
 
-    pub fn new_show(parseable: bool) -> Self {
+    /// Adds a command which lists all associations on the given clusters in the format expected by
+    /// [`SlurmAssociation::parse_all`](super::SlurmAssociation::parse_all).
+    /// Without any cluster the associations of all clusters are listed.
+    pub fn new_show(clusters: Vec<String>) -> Self {
         Self::new_inner(
             Default::default(),
-            vec![SlurmSubCommand::Show { parseable }],
+            vec![SlurmSubCommand::Show {
+                user: None,
+                clusters,
+            }],
         )
     }
 
+    /// Same as [`CommandBuilder::new_show`] except only the associations of the user
+    /// aka parameter `username` are listed.
+    pub fn new_show_of_user(username: String, clusters: Vec<String>) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::Show {
+                user: Some(username),
+                clusters,
+            }],
+        )
+    }
+
+    /// Adds a command which lists only the name of the user aka parameter `username`.
+    /// The output is empty if the user does not exist.
+    pub fn new_show_user(username: String) -> Self {
+        Self::new_inner(username, vec![SlurmSubCommand::ShowUser])
+    }
+
+    /// Adds a command which lists the names of all QOS within slurm, one per line.
+    pub fn new_show_qos_names() -> Self {
+        Self::new_inner(Default::default(), vec![SlurmSubCommand::ShowQosNames])
+    }
+
     #[cfg(test)]
     pub fn new_modify(username: String, modifier: HashMap<&'static str, Vec<String>>) -> Self {
-        Self::new_inner(username, vec![SlurmSubCommand::Modify(modifier)])
+        Self::new_inner(
+            username,
+            vec![SlurmSubCommand::Modify {
+                to_set: modifier,
+                clusters: Vec::new(),
+            }],
+        )
     }
 
     /// Adds a command to modiy the default quality of service for user aka parameter `username`
@@ -96,15 +416,133 @@ impl CommandBuilder {
         default_qos: String,
         qos: Vec<String>,
     ) -> Self {
-        let command = Self::create_modify_command(default_qos, qos);
+        let command =
+            Self::create_modify_command(default_qos, qos, &Default::default(), Vec::new());
         Self::new_inner(username, vec![command])
     }
 
-    pub fn new_add(username: String, group: Group, default_qos: String, qos: Vec<String>) -> Self {
+    /// Adds a command to set the limits of all associations of user aka parameter `username`.
+    /// Partitions within `limits` are ignored, they can only be given while adding the user.
+    pub fn new_modify_limits(username: String, limits: &AssociationLimits) -> Self {
+        let to_set = HashMap::from_iter(limits.to_set());
+        Self::new_inner(
+            username,
+            vec![SlurmSubCommand::Modify {
+                to_set,
+                clusters: Vec::new(),
+            }],
+        )
+    }
+
+    /// Adds the user aka parameter `username` to all given accounts.
+    /// The default account is only set explicitly if `default_account` is given.
+    /// Otherwise slurm takes the first account as the default one.
+    /// The user is restricted to the partitions of `limits` if there are any.
+    /// The user is only added on the given clusters if there are any.
+    pub fn new_add(
+        username: String,
+        accounts: Vec<String>,
+        default_account: Option<String>,
+        default_qos: String,
+        qos: Vec<String>,
+        limits: AssociationLimits,
+        clusters: Vec<String>,
+    ) -> Self {
         // Note: The order of execution is important here!
         // Slurm expects the user to have QOS, before it can set the default QOS
-        let mod_command = Self::create_modify_command(default_qos, qos);
-        Self::new_inner(username, vec![SlurmSubCommand::Add { group }, mod_command])
+        let mod_command = Self::create_modify_command(default_qos, qos, &limits, clusters.clone());
+        Self::new_inner(
+            username,
+            vec![
+                SlurmSubCommand::Add {
+                    accounts,
+                    default_account,
+                    partitions: limits.partitions,
+                    clusters,
+                },
+                mod_command,
+            ],
+        )
+    }
+
+    pub fn new_add_account(name: String, attributes: AccountAttributes) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::AddAccount { name, attributes }],
+        )
+    }
+
+    pub fn new_modify_account(name: String, attributes: AccountAttributes) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ModifyAccount { name, attributes }],
+        )
+    }
+
+    pub fn new_delete_account(name: String) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::DeleteAccount { name }],
+        )
+    }
+
+    pub fn new_show_accounts(parseable: bool) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ShowAccounts { parseable }],
+        )
+    }
+
+    /// Adds a command which makes all given users coordinators of the account aka parameter `account`.
+    pub fn new_add_coordinators(account: String, users: Vec<String>) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::AddCoordinators { account, users }],
+        )
+    }
+
+    /// Adds a command which removes all given users as coordinators of the account aka parameter `account`.
+    pub fn new_delete_coordinators(account: String, users: Vec<String>) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::DeleteCoordinators { account, users }],
+        )
+    }
+
+    /// Adds a command which lists every account with its coordinators.
+    pub fn new_show_coordinators(parseable: bool) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ShowCoordinators { parseable }],
+        )
+    }
+
+    pub fn new_add_qos(name: String, attributes: QosAttributes) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::AddQos { name, attributes }],
+        )
+    }
+
+    pub fn new_modify_qos(name: String, attributes: QosAttributes) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ModifyQos { name, attributes }],
+        )
+    }
+
+    pub fn new_delete_qos(name: String) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::DeleteQos { name }],
+        )
+    }
+
+    pub fn new_show_qos(parseable: bool) -> Self {
+        Self::new_inner(
+            Default::default(),
+            vec![SlurmSubCommand::ShowQos { parseable }],
+        )
     }
 
     pub fn immediate(mut self, immediate: bool) -> Self {
@@ -117,26 +555,34 @@ impl CommandBuilder {
         self
     }
 
-    pub fn remote_commands(self) -> Vec<String> {
+    pub fn commands(self) -> Vec<ExecCommand> {
         let args = Self::construct_args(self.username, self.immediate, self.sub_commands);
         args.into_iter()
-            .map(|args| {
-                let mut command = Vec::with_capacity(args.len() + 1);
-                command.push(self.sacctmgr_path.to_owned());
-                command.extend(args);
-                command.join(" ")
-            })
+            .map(|args| ExecCommand::new(self.sacctmgr_path.as_str(), args))
             .collect()
     }
 
-    pub fn local_commands(self) -> Vec<Command> {
-        let args = Self::construct_args(self.username, self.immediate, self.sub_commands);
-        args.into_iter()
-            .map(|args| {
-                let mut command = Command::new(&self.sacctmgr_path);
-                command.args(args);
-                command
-            })
+    /// Returns the username together with every sub command and its equivalent sacctmgr command.
+    pub(super) fn into_parts(self) -> (String, Vec<(SlurmSubCommand, ExecCommand)>) {
+        let sub_commands = self.sub_commands.clone();
+        let username = self.username.clone();
+        let commands = self.commands();
+        (username, sub_commands.into_iter().zip(commands).collect())
+    }
+
+    #[cfg(test)]
+    pub fn remote_commands(self) -> Vec<String> {
+        self.commands()
+            .iter()
+            .map(ExecCommand::command_line)
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn local_commands(self) -> Vec<std::process::Command> {
+        self.commands()
+            .iter()
+            .map(ExecCommand::to_local_command)
             .collect()
     }
 
@@ -149,9 +595,18 @@ impl CommandBuilder {
         }
     }
 
-    fn create_modify_command(default_qos: String, qos: Vec<String>) -> SlurmSubCommand {
-        let map = HashMap::from_iter([(DEFAULT_QOS, vec![default_qos]), (QOS, qos)]);
-        SlurmSubCommand::Modify(map)
+    fn create_modify_command(
+        default_qos: String,
+        qos: Vec<String>,
+        limits: &AssociationLimits,
+        clusters: Vec<String>,
+    ) -> SlurmSubCommand {
+        let to_set = HashMap::from_iter(
+            [(DEFAULT_QOS, vec![default_qos]), (QOS, qos)]
+                .into_iter()
+                .chain(limits.to_set()),
+        );
+        SlurmSubCommand::Modify { to_set, clusters }
     }
 
     fn construct_args(
@@ -178,13 +633,17 @@ impl CommandBuilder {
 #[cfg(test)]
 mod testing {
     use super::*;
+    use crate::Group;
     #[test]
     fn produce_add_username_with_account_and_qos() {
         let input = CommandBuilder::new_add(
             "somebody".to_owned(),
-            Group::Staff,
+            vec![Group::Staff.to_string()],
+            None,
             "student".to_owned(),
             vec!["student".into(), "worker".into()],
+            Default::default(),
+            Vec::new(),
         );
 
         let actual = input.remote_commands();
@@ -194,9 +653,12 @@ mod testing {
     fn produce_immediate_add_username_with_account_and_qos() {
         let input = CommandBuilder::new_add(
             "somebody".to_owned(),
-            Group::Staff,
+            vec![Group::Staff.to_string()],
+            None,
             "student".to_owned(),
             vec!["student".into(), "worker".into()],
+            Default::default(),
+            Vec::new(),
         )
         .immediate(true);
 
@@ -206,7 +668,7 @@ mod testing {
 
     #[test]
     fn produce_delete_user_with_separate_path() {
-        let input = CommandBuilder::new_delete("somebody".to_owned())
+        let input = CommandBuilder::new_delete("somebody".to_owned(), Vec::new())
             .sacctmgr_path("some_path/sacctmgr".to_owned());
         let actual = input.remote_commands();
         insta::assert_yaml_snapshot!(actual);
@@ -214,24 +676,176 @@ mod testing {
 
     #[test]
     fn produce_delete_user_with_local_command() {
-        let input = CommandBuilder::new_delete("somebody".to_owned())
+        let input = CommandBuilder::new_delete("somebody".to_owned(), Vec::new())
             .sacctmgr_path("some_path/sacctmgr".to_owned());
         let actual = input.local_commands();
         insta::assert_debug_snapshot!(actual);
     }
 
     #[test]
-    fn list_user() {
-        let input = CommandBuilder::new_show(false).sacctmgr_path("some_path/sacctmgr".to_owned());
+    fn list_associations() {
+        let input =
+            CommandBuilder::new_show(Vec::new()).sacctmgr_path("some_path/sacctmgr".to_owned());
         let actual = input.remote_commands();
         insta::assert_debug_snapshot!(actual);
     }
 
     #[test]
-    fn list_user_parserable() {
-        let input = CommandBuilder::new_show(true).sacctmgr_path("some_path/sacctmgr".to_owned());
+    fn restrict_user_commands_to_clusters() {
+        let clusters = vec!["alpha".to_owned(), "beta".to_owned()];
+        let actual: Vec<String> = [
+            CommandBuilder::new_add(
+                "somebody".to_owned(),
+                vec![Group::Staff.to_string()],
+                None,
+                "basic".to_owned(),
+                vec!["basic".into()],
+                Default::default(),
+                clusters.clone(),
+            ),
+            CommandBuilder::new_delete("somebody".to_owned(), clusters.clone()),
+            CommandBuilder::new_show(clusters.clone()),
+            CommandBuilder::new_show_of_user("somebody".to_owned(), clusters),
+        ]
+        .into_iter()
+        .flat_map(CommandBuilder::remote_commands)
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn add_user_to_several_accounts_with_default_account() {
+        let input = CommandBuilder::new_add(
+            "somebody".to_owned(),
+            vec!["project_a".to_owned(), "project_b".to_owned()],
+            Some("project_b".to_owned()),
+            "basic".to_owned(),
+            vec!["basic".into()],
+            Default::default(),
+            Vec::new(),
+        )
+        .immediate(true);
+
         let actual = input.remote_commands();
-        insta::assert_debug_snapshot!(actual);
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn add_and_modify_user_with_limits_and_partitions() {
+        let limits = AssociationLimits {
+            grp_tres: Some("cpu=64,gres/gpu=2".to_owned()),
+            max_jobs: Some(10),
+            max_submit_jobs: Some(50),
+            max_wall: Some("2-00:00:00".to_owned()),
+            fairshare: Some(5),
+            partitions: vec!["cpu".to_owned(), "gpu".to_owned()],
+        };
+        let actual: Vec<String> = [
+            CommandBuilder::new_add(
+                "somebody".to_owned(),
+                vec![Group::Staff.to_string()],
+                None,
+                "basic".to_owned(),
+                vec!["basic".into()],
+                limits.clone(),
+                Vec::new(),
+            ),
+            CommandBuilder::new_modify_limits("somebody".to_owned(), &limits),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.immediate(true).remote_commands())
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn produce_account_commands() {
+        let attributes = AccountAttributes {
+            parent: Some("faculty_cs".to_owned()),
+            description: Some("Project X of the CS faculty".to_owned()),
+            organization: Some("cs".to_owned()),
+        };
+        let actual: Vec<String> = [
+            CommandBuilder::new_add_account("project_x".to_owned(), attributes.clone()),
+            CommandBuilder::new_modify_account(
+                "project_x".to_owned(),
+                AccountAttributes {
+                    parent: Some("faculty_math".to_owned()),
+                    ..Default::default()
+                },
+            ),
+            CommandBuilder::new_delete_account("project_x".to_owned()),
+            CommandBuilder::new_show_accounts(true),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.immediate(true).remote_commands())
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn produce_coordinator_commands() {
+        let users = vec!["prof_a".to_owned(), "prof_b".to_owned()];
+        let actual: Vec<String> = [
+            CommandBuilder::new_add_coordinators("project_x".to_owned(), users.clone()),
+            CommandBuilder::new_delete_coordinators("project_x".to_owned(), users),
+            CommandBuilder::new_show_coordinators(true),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.immediate(true).remote_commands())
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn produce_qos_commands() {
+        let attributes = QosAttributes {
+            priority: Some(100),
+            max_tres_per_user: Some("cpu=64,gres/gpu=2".to_owned()),
+            max_wall: Some("2-00:00:00".to_owned()),
+            preempt: Some(vec!["basic".to_owned(), "interactive".to_owned()]),
+            preempt_mode: Some("requeue".to_owned()),
+        };
+        let actual: Vec<String> = [
+            CommandBuilder::new_add_qos("gpu".to_owned(), attributes),
+            CommandBuilder::new_modify_qos(
+                "gpu".to_owned(),
+                QosAttributes {
+                    priority: Some(50),
+                    ..Default::default()
+                },
+            ),
+            CommandBuilder::new_delete_qos("gpu".to_owned()),
+            CommandBuilder::new_show_qos(false),
+        ]
+        .into_iter()
+        .flat_map(|builder| builder.immediate(true).remote_commands())
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn show_single_user() {
+        let input = CommandBuilder::new_show_user("somebody".to_owned())
+            .sacctmgr_path("some_path/sacctmgr".to_owned());
+        assert_eq!(
+            vec!["some_path/sacctmgr --noheader --parsable2 show user somebody"],
+            input.remote_commands()
+        );
+    }
+
+    #[test]
+    fn show_qos_names() {
+        let input = CommandBuilder::new_show_qos_names();
+        assert_eq!(
+            vec!["sacctmgr --noheader --parsable2 show qos format=Name"],
+            input.remote_commands()
+        );
     }
 
     #[test]
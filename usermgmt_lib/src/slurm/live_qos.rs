@@ -0,0 +1,157 @@
+use std::cell::RefCell;
+
+use anyhow::ensure;
+use log::{debug, warn};
+
+use crate::{config::MgmtConfig, executor::CommandExecutor, prelude::AppResult};
+
+use super::{command_builder::CommandBuilder, run_slurm_action};
+
+/// QOS within slurm, fetched only once for the lifetime of the cache.
+/// One operation owns one cache and passes it to every function validating QOS.
+#[derive(Debug, Default)]
+pub struct QosCache(RefCell<Option<Vec<String>>>);
+
+impl QosCache {
+    /// Returns the cached QOS or fetches them from slurm via the given executor.
+    /// After fetching, a warning is logged for every QOS in the configuration which slurm
+    /// does not know.
+    ///
+    /// # Errors
+    ///
+    /// - If running sacctmgr fails. See [`run_slurm_action`]
+    pub fn get_or_fetch<E>(&self, config: &MgmtConfig, executor: &E) -> AppResult<Vec<String>>
+    where
+        E: CommandExecutor,
+    {
+        if let Some(qos) = self.0.borrow().as_ref() {
+            debug!("Using QOS of slurm fetched earlier");
+            return Ok(qos.clone());
+        }
+
+        let output = run_slurm_action(CommandBuilder::new_show_qos_names(), config, executor)?;
+        let qos: Vec<String> = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToOwned::to_owned)
+            .collect();
+        debug!("Fetched QOS from slurm: {:?}", qos);
+        for (field, unknown) in unknown_configured_qos(config, &qos) {
+            warn!(
+                "QOS ({}) of field {} in the configuration does not exist in slurm",
+                unknown, field
+            );
+        }
+
+        *self.0.borrow_mut() = Some(qos.clone());
+        Ok(qos)
+    }
+
+    /// # Errors
+    ///
+    /// - If fetching the QOS from slurm fails. See [`QosCache::get_or_fetch`]
+    /// - If one of the given QOS does not exist in slurm.
+    pub fn ensure_known<E>(&self, qos: &[String], config: &MgmtConfig, executor: &E) -> AppResult
+    where
+        E: CommandExecutor,
+    {
+        let known = self.get_or_fetch(config, executor)?;
+        let unknown: Vec<&str> = qos
+            .iter()
+            .filter(|next| !known.contains(next))
+            .map(String::as_str)
+            .collect();
+        ensure!(
+            unknown.is_empty(),
+            "QOS ({}) do not exist in slurm. Existing QOS are: {}",
+            unknown.join(","),
+            known.join(",")
+        );
+        Ok(())
+    }
+}
+
+/// Pairs of a configuration field and a QOS within it which is not part of `known`.
+fn unknown_configured_qos<'a>(
+    config: &'a MgmtConfig,
+    known: &[String],
+) -> Vec<(&'static str, &'a str)> {
+    let configured = [
+        ("valid_qos", config.valid_qos.as_slice()),
+        ("student_qos", config.student_qos.as_slice()),
+        ("staff_qos", config.staff_qos.as_slice()),
+        (
+            "student_default_qos",
+            std::slice::from_ref(&config.student_default_qos),
+        ),
+        (
+            "staff_default_qos",
+            std::slice::from_ref(&config.staff_default_qos),
+        ),
+    ];
+    configured
+        .into_iter()
+        .flat_map(|(field, qos)| qos.iter().map(move |next| (field, next.as_str())))
+        .filter(|(_, qos)| !known.iter().any(|next| next == qos))
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::executor::{CommandOutput, RecordingExecutor};
+
+    const SHOW_QOS: &str = "/usr/local/bin/sacctmgr --noheader --parsable2 show qos";
+
+    #[test]
+    fn fetch_qos_only_once() {
+        let config = MgmtConfig::default();
+        let recorder = RecordingExecutor::new()
+            .respond_with(SHOW_QOS, CommandOutput::success("normal\nbasic\n\n"));
+        let cache = QosCache::default();
+
+        let first = cache.get_or_fetch(&config, &recorder).unwrap();
+        let second = cache.get_or_fetch(&config, &recorder).unwrap();
+
+        assert_eq!(vec!["normal", "basic"], first);
+        assert_eq!(first, second);
+        assert_eq!(1, recorder.recorded().len());
+    }
+
+    #[test]
+    fn error_for_qos_unknown_to_slurm() {
+        let config = MgmtConfig::default();
+        let recorder = RecordingExecutor::new()
+            .respond_with(SHOW_QOS, CommandOutput::success("normal\nbasic\n"));
+        let cache = QosCache::default();
+
+        assert!(cache
+            .ensure_known(&["basic".to_owned()], &config, &recorder)
+            .is_ok());
+        let actual = cache
+            .ensure_known(&["basic".to_owned(), "gpu".to_owned()], &config, &recorder)
+            .unwrap_err();
+        assert_eq!(
+            "QOS (gpu) do not exist in slurm. Existing QOS are: normal,basic",
+            actual.to_string()
+        );
+    }
+
+    #[test]
+    fn detect_configured_qos_unknown_to_slurm() {
+        let config = MgmtConfig::default();
+        let known = vec!["interactive".to_owned(), "basic".to_owned()];
+
+        let actual = unknown_configured_qos(&config, &known);
+
+        assert_eq!(
+            vec![
+                ("valid_qos", "advanced"),
+                ("staff_qos", "advanced"),
+                ("staff_default_qos", "advanced"),
+            ],
+            actual
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::executor::CommandOutput;
+
+/// Printed by sacctmgr if an association to add exists already.
+const NOTHING_NEW_ADDED: &str = "nothing new added";
+const ALREADY_EXISTS: &str = "already exists";
+/// Printed by sacctmgr if no association matched the one to delete.
+const NOTHING_DELETED: &str = "nothing deleted";
+/// Printed by sacctmgr if a QOS given for a user does not exist within slurm.
+const UNKNOWN_QOS: &[&str] = &["unknown qos", "bad qos", "invalid qos"];
+
+/// Outcome of a sacctmgr run, classified by its exit code and the known messages
+/// sacctmgr prints to stdout or stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SacctmgrOutcome {
+    /// Command succeeded. Contains the stdout of sacctmgr.
+    Done(String),
+    /// Entity to add exists already within slurm.
+    /// Only commands which add an entity have this outcome.
+    AlreadyExists,
+    /// Entity to delete does not exist within slurm.
+    NothingDeleted,
+    /// One of the given QOS is not known by slurm. Contains the message of sacctmgr.
+    UnknownQos(String),
+    /// Command failed for a reason not known. Contains the exit code and the message of sacctmgr.
+    Failed { exit_code: i32, message: String },
+}
+
+impl SacctmgrOutcome {
+    /// Parameter `adds` is true if the command adds an entity.
+    /// Other commands which fail with a message about an existing entity have failed for real.
+    pub fn from_output(output: CommandOutput, adds: bool) -> Self {
+        if output.is_success() {
+            return Self::Done(output.into_stdout());
+        }
+
+        let message = failure_message(&output);
+        let lowercase = message.to_lowercase();
+        if UNKNOWN_QOS.iter().any(|known| lowercase.contains(known)) {
+            Self::UnknownQos(message)
+        } else if adds
+            && (lowercase.contains(NOTHING_NEW_ADDED) || lowercase.contains(ALREADY_EXISTS))
+        {
+            Self::AlreadyExists
+        } else if lowercase.contains(NOTHING_DELETED) {
+            Self::NothingDeleted
+        } else {
+            Self::Failed {
+                exit_code: output.exit_code(),
+                message,
+            }
+        }
+    }
+}
+
+/// Sacctmgr reports some failures on stdout instead of stderr.
+/// Therefore both are considered, stderr first.
+fn failure_message(output: &CommandOutput) -> String {
+    [output.stderr().trim(), output.stdout().trim()]
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn classify_success_as_done() {
+        let actual = SacctmgrOutcome::from_output(CommandOutput::success("listing"), false);
+        assert_eq!(SacctmgrOutcome::Done("listing".to_owned()), actual);
+    }
+
+    #[test]
+    fn classify_known_messages() {
+        let output = |stdout: &str, stderr: &str| {
+            SacctmgrOutcome::from_output(
+                CommandOutput::new(1, stdout.to_owned(), stderr.to_owned()),
+                true,
+            )
+        };
+
+        assert_eq!(
+            SacctmgrOutcome::AlreadyExists,
+            output(" Nothing new added.\n", "")
+        );
+        assert_eq!(
+            SacctmgrOutcome::NothingDeleted,
+            output("", " Nothing deleted\n")
+        );
+        assert_eq!(
+            SacctmgrOutcome::UnknownQos(
+                "You gave a bad qos 'gpu'.  Use 'list qos' to get complete list.".to_owned()
+            ),
+            output(
+                "",
+                " You gave a bad qos 'gpu'.  Use 'list qos' to get complete list.\n"
+            )
+        );
+        assert_eq!(
+            SacctmgrOutcome::Failed {
+                exit_code: 1,
+                message: "sacctmgr: error: Problem talking to the database\nsome output".to_owned()
+            },
+            output(
+                "some output\n",
+                "sacctmgr: error: Problem talking to the database\n"
+            )
+        );
+    }
+
+    #[test]
+    fn classify_existing_entity_as_failure_unless_added() {
+        let output = CommandOutput::failure(1, " Account already exists\n");
+
+        assert_eq!(
+            SacctmgrOutcome::Failed {
+                exit_code: 1,
+                message: "Account already exists".to_owned()
+            },
+            SacctmgrOutcome::from_output(output, false)
+        );
+    }
+}
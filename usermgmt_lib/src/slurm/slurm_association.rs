@@ -0,0 +1,207 @@
+use anyhow::{ensure, Context};
+use serde::{Deserialize, Serialize};
+
+use super::association_limits::{
+    FAIRSHARE, GRP_TRES, MAX_JOBS, MAX_SUBMIT_JOBS, MAX_WALL, PARTITION,
+};
+use super::command_builder::{ACCOUNT, CLUSTER, DEFAULT_QOS, QOS, USER};
+use crate::prelude::AppResult;
+
+/// Fields of an association in the order in which sacctmgr is asked to print them.
+pub(super) const FORMAT_FIELDS: [&str; 11] = [
+    CLUSTER,
+    ACCOUNT,
+    USER,
+    PARTITION,
+    DEFAULT_QOS,
+    QOS,
+    GRP_TRES,
+    MAX_JOBS,
+    MAX_SUBMIT_JOBS,
+    MAX_WALL,
+    FAIRSHARE,
+];
+
+/// Character which splits cells in a row of sacctmgr with the option `--parsable2`.
+const SPLIT_BETWEEN_CELLS: char = '|';
+const SPLIT_BETWEEN_VALUES: char = ',';
+
+/// Association of slurm as listed by `sacctmgr show assoc`.
+///
+/// Slurm also lists associations of accounts alone. Those have no user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlurmAssociation {
+    pub cluster: String,
+    pub account: String,
+    pub user: Option<String>,
+    pub partition: Option<String>,
+    pub default_qos: Option<String>,
+    pub qos: Vec<String>,
+    /// Limit of trackable resources for all running jobs together, e.g. `cpu=64,gres/gpu=2`.
+    pub grp_tres: Option<String>,
+    pub max_jobs: Option<u32>,
+    pub max_submit_jobs: Option<u32>,
+    /// Maximum wall time per job, e.g. `2-00:00:00`.
+    pub max_wall: Option<String>,
+    /// Number of shares or `parent` if the shares of the parent account are used.
+    pub fairshare: Option<String>,
+}
+
+impl SlurmAssociation {
+    /// Titles of the columns for the cells returned by [`SlurmAssociation::to_row`].
+    pub const HEADERS: [&'static str; 11] = [
+        "Cluster",
+        "Account",
+        "User",
+        "Partition",
+        "Def QOS",
+        "QOS",
+        "GrpTRES",
+        "MaxJobs",
+        "MaxSubmit",
+        "MaxWall",
+        "Share",
+    ];
+
+    /// Parses the output of sacctmgr with the options `--noheader --parsable2`
+    /// and the fields of [`FORMAT_FIELDS`]. Empty lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// - If a line does not have exactly one cell per field.
+    /// - If the cell of a numeric limit is not a number.
+    pub fn parse_all(input: &str) -> AppResult<Vec<Self>> {
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                Self::parse_line(line)
+                    .with_context(|| format!("Could not parse association from line ({})", line))
+            })
+            .collect()
+    }
+
+    fn parse_line(line: &str) -> AppResult<Self> {
+        let cells: Vec<&str> = line.split(SPLIT_BETWEEN_CELLS).map(str::trim).collect();
+        ensure!(
+            cells.len() == FORMAT_FIELDS.len(),
+            "Expected {} cells but found {}",
+            FORMAT_FIELDS.len(),
+            cells.len()
+        );
+        let text = |index: usize| Some(cells[index].to_owned()).filter(|cell| !cell.is_empty());
+        let number = |index: usize| {
+            text(index)
+                .map(|cell| {
+                    cell.parse::<u32>().with_context(|| {
+                        format!("{} ({}) is not a number", FORMAT_FIELDS[index], cell)
+                    })
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            cluster: cells[0].to_owned(),
+            account: cells[1].to_owned(),
+            user: text(2),
+            partition: text(3),
+            default_qos: text(4),
+            qos: cells[5]
+                .split(SPLIT_BETWEEN_VALUES)
+                .map(str::trim)
+                .filter(|qos| !qos.is_empty())
+                .map(ToOwned::to_owned)
+                .collect(),
+            grp_tres: text(6),
+            max_jobs: number(7)?,
+            max_submit_jobs: number(8)?,
+            max_wall: text(9),
+            fairshare: text(10),
+        })
+    }
+
+    /// True if the association belongs to an user and not only to an account.
+    pub fn is_user_association(&self) -> bool {
+        self.user.is_some()
+    }
+
+    /// Cells in the order of [`SlurmAssociation::HEADERS`].
+    /// Missing values are empty cells.
+    pub fn to_row(&self) -> Vec<String> {
+        let or_empty = |value: &Option<String>| value.clone().unwrap_or_default();
+        let number = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+        vec![
+            self.cluster.clone(),
+            self.account.clone(),
+            or_empty(&self.user),
+            or_empty(&self.partition),
+            or_empty(&self.default_qos),
+            self.qos.join(","),
+            or_empty(&self.grp_tres),
+            number(self.max_jobs),
+            number(self.max_submit_jobs),
+            or_empty(&self.max_wall),
+            or_empty(&self.fairshare),
+        ]
+    }
+
+    /// Line like sacctmgr prints it with the options `--noheader --parsable2`.
+    /// Inverse of [`SlurmAssociation::parse_all`] for a single association.
+    pub fn to_parsable_line(&self) -> String {
+        self.to_row().join(&SPLIT_BETWEEN_CELLS.to_string())
+    }
+}
+
+/// Returns a pretty ASCII table of the given associations with a title row.
+pub fn associations_to_pretty_table(associations: &[SlurmAssociation]) -> String {
+    use prettytable::{Cell, Row, Table};
+
+    let mut table = Table::new();
+    table.set_titles(Row::new(
+        SlurmAssociation::HEADERS
+            .iter()
+            .map(|title| Cell::new(title))
+            .collect(),
+    ));
+    for association in associations {
+        table.add_row(Row::new(
+            association
+                .to_row()
+                .iter()
+                .map(|cell| Cell::new(cell))
+                .collect(),
+        ));
+    }
+    table.to_string()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn parse_user_and_account_only_associations() {
+        let input = "cluster|root|||||||||1\n\
+                    cluster|root|root|||normal|||||1\n\
+                    \n\
+                    cluster|staff|dev_user|gpu|basic|basic,interactive|cpu=64,gres/gpu=2|10||2-00:00:00|parent\n";
+
+        let actual = SlurmAssociation::parse_all(input).unwrap();
+
+        insta::assert_debug_snapshot!(actual);
+        assert!(!actual[0].is_user_association());
+        assert!(actual[2].is_user_association());
+        assert_eq!(
+            "cluster|staff|dev_user|gpu|basic|basic,interactive|cpu=64,gres/gpu=2|10||2-00:00:00|parent",
+            actual[2].to_parsable_line()
+        );
+    }
+
+    #[test]
+    fn error_for_malformed_lines() {
+        assert!(SlurmAssociation::parse_all("cluster|root|\n").is_err());
+        let actual =
+            SlurmAssociation::parse_all("cluster|staff|dev_user|||||many|||\n").unwrap_err();
+        assert!(format!("{:#}", actual).contains("MaxJobs (many) is not a number"));
+    }
+}
@@ -0,0 +1,704 @@
+//! Backend for slurm which talks to the accounting endpoints of slurmrestd over HTTP
+//! instead of running sacctmgr.
+//!
+//! Every sub command of sacctmgr is translated into one or more requests.
+//! The responses are turned into a [`CommandOutput`] which looks like the output of sacctmgr.
+//! This way the outcome is handled the same way for both backends.
+
+mod conversion;
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, ensure, Context};
+use log::debug;
+use serde_json::{json, Value};
+
+use self::conversion::{
+    get_path, minutes_to_wall, number, read_number, render_table, set_path, to_text, tres_to_json,
+    tres_to_text, wall_to_minutes,
+};
+use super::association_limits::{FAIRSHARE, GRP_TRES, MAX_JOBS, MAX_SUBMIT_JOBS, MAX_WALL};
+use super::command_builder::{SlurmSubCommand, DEFAULT_QOS, QOS};
+use super::{AccountAttributes, QosAttributes, SlurmAssociation};
+use crate::{config::MgmtConfig, executor::CommandOutput, prelude::AppResult};
+
+/// Environment variable which contains the JWT if no token is given in the configuration.
+pub const SLURM_JWT_ENV: &str = "SLURM_JWT";
+const HEADER_USER_NAME: &str = "X-SLURM-USER-NAME";
+const HEADER_USER_TOKEN: &str = "X-SLURM-USER-TOKEN";
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Message of sacctmgr if no entity matched the one to delete.
+/// See [`super::SacctmgrOutcome::NothingDeleted`].
+const NOTHING_DELETED: &str = " Nothing deleted";
+/// Message of sacctmgr if the account to add exists already.
+/// See [`super::SacctmgrOutcome::AlreadyExists`].
+const ACCOUNT_EXISTS: &str = " Account already exists";
+
+const ACCOUNT_HEADERS: [&str; 3] = ["Account", "Descr", "Org"];
+const COORDINATOR_HEADERS: [&str; 2] = ["Account", "Coord Names"];
+const QOS_HEADERS: [&str; 6] = [
+    "Name",
+    "Priority",
+    "MaxTRESPU",
+    "MaxWall",
+    "Preempt",
+    "PreemptMode",
+];
+
+/// Client for the accounting endpoints (slurmdb) of slurmrestd.
+/// Authenticates every request via the headers `X-SLURM-USER-NAME` and `X-SLURM-USER-TOKEN`.
+#[derive(Debug)]
+pub struct SlurmRestClient {
+    agent: ureq::Agent,
+    base_url: String,
+    user: String,
+    token: String,
+}
+
+/// Status code and JSON body of a response of slurmrestd.
+#[derive(Debug)]
+struct RestResponse {
+    status: u16,
+    body: Value,
+}
+
+impl RestResponse {
+    /// Slurmrestd reports some errors within the body of a successful response.
+    fn is_success(&self) -> bool {
+        self.status < 300 && self.errors().is_empty()
+    }
+
+    fn errors(&self) -> Vec<String> {
+        get_path(&self.body, &["errors"])
+            .as_array()
+            .map(|errors| {
+                errors
+                    .iter()
+                    .map(|error| {
+                        let description = to_text(get_path(error, &["description"]));
+                        let error = to_text(get_path(error, &["error"]));
+                        match (error.is_empty(), description.is_empty()) {
+                            (false, false) => format!("{}: {}", error, description),
+                            (false, true) => error,
+                            _ => description,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Output like sacctmgr would give for a failed command.
+    fn into_failure(self) -> CommandOutput {
+        let mut message = self.errors().join("\n");
+        if message.is_empty() {
+            message = match &self.body {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+        }
+        CommandOutput::failure(i32::from(self.status), &message)
+    }
+}
+
+/// Returns early with the failure of the response if the response is not successful.
+macro_rules! ensure_success {
+    ($response:expr) => {{
+        let response = $response;
+        if !response.is_success() {
+            return Ok(response.into_failure());
+        }
+        response
+    }};
+}
+
+impl SlurmRestClient {
+    /// # Errors
+    ///
+    /// - If no JWT is given in the configuration and the environment variable `SLURM_JWT` is not set.
+    pub fn from_config(config: &MgmtConfig) -> AppResult<Self> {
+        let token = match &config.slurmrestd_token {
+            Some(token) => token.clone(),
+            None => std::env::var(SLURM_JWT_ENV).with_context(|| {
+                format!(
+                    "No JWT for slurmrestd given. Set the field slurmrestd_token in the configuration or the environment variable {}",
+                    SLURM_JWT_ENV
+                )
+            })?,
+        };
+        let user = config
+            .slurmrestd_user
+            .clone()
+            .unwrap_or_else(|| config.default_ssh_user.clone());
+        Ok(Self::new(
+            &config.slurmrestd_url,
+            &config.slurmrestd_api_version,
+            user,
+            token,
+        ))
+    }
+
+    /// Parameter `url` is the address of slurmrestd like `http://localhost:6820`
+    /// and `api_version` the version of the accounting endpoints like `v0.0.40`.
+    pub fn new(url: &str, api_version: &str, user: String, token: String) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            base_url: format!("{}/slurmdb/{}", url.trim_end_matches('/'), api_version),
+            user,
+            token,
+        }
+    }
+
+    /// Checks that slurmrestd is reachable and accepts the JWT by fetching the names of all QOS.
+    /// Returns the number of QOS.
+    ///
+    /// # Errors
+    ///
+    /// - If slurmrestd could not be reached or did not answer with JSON.
+    /// - If slurmrestd refused the request, for example because of an invalid JWT.
+    pub fn check_access(&self) -> AppResult<usize> {
+        let output = self.show_qos_names()?;
+        ensure!(
+            output.is_success(),
+            "Request GET /qos failed with status {}: {}",
+            output.exit_code(),
+            output.stderr().trim()
+        );
+        Ok(output.stdout().lines().count())
+    }
+
+    /// Performs the sub command of sacctmgr via requests to slurmrestd.
+    /// The parameter `username` is only used by sub commands for an user.
+    ///
+    /// # Errors
+    ///
+    /// - If slurmrestd could not be reached or did not answer with JSON.
+    /// - If a value of the sub command could not be translated for slurmrestd.
+    pub(super) fn run(
+        &self,
+        username: &str,
+        sub_command: SlurmSubCommand,
+    ) -> AppResult<CommandOutput> {
+        match sub_command {
+            SlurmSubCommand::Add {
+                accounts,
+                default_account,
+                partitions,
+                clusters,
+            } => self.add_user(username, accounts, default_account, partitions, clusters),
+            SlurmSubCommand::Delete { clusters } if clusters.is_empty() => {
+                self.delete(&format!("/user/{}", encode(username)))
+            }
+            SlurmSubCommand::Delete { clusters } => self.delete(&format!(
+                "/associations?user={}&cluster={}",
+                encode(username),
+                encode(&clusters.join(","))
+            )),
+            SlurmSubCommand::Modify { to_set, clusters } => {
+                self.modify_associations(username, to_set, clusters)
+            }
+            SlurmSubCommand::Show { user, clusters } => self.show_associations(user, clusters),
+            SlurmSubCommand::ShowUser => self.show_user(username),
+            SlurmSubCommand::ShowQosNames => self.show_qos_names(),
+            SlurmSubCommand::AddAccount { name, attributes } => self.add_account(&name, attributes),
+            SlurmSubCommand::ModifyAccount { name, attributes } => {
+                self.modify_account(&name, attributes)
+            }
+            SlurmSubCommand::DeleteAccount { name } => {
+                self.delete(&format!("/account/{}", encode(&name)))
+            }
+            SlurmSubCommand::ShowAccounts { parseable } => self.show_accounts(parseable),
+            SlurmSubCommand::AddCoordinators { account, users } => {
+                self.add_coordinators(&account, users)
+            }
+            SlurmSubCommand::DeleteCoordinators { .. } => {
+                bail!("Removing coordinators is not supported by slurmrestd")
+            }
+            SlurmSubCommand::ShowCoordinators { parseable } => self.show_coordinators(parseable),
+            SlurmSubCommand::AddQos { name, attributes }
+            | SlurmSubCommand::ModifyQos { name, attributes } => self.upsert_qos(&name, attributes),
+            SlurmSubCommand::DeleteQos { name } => self.delete(&format!("/qos/{}", encode(&name))),
+            SlurmSubCommand::ShowQos { parseable } => self.show_qos(parseable),
+        }
+    }
+
+    fn add_user(
+        &self,
+        username: &str,
+        accounts: Vec<String>,
+        default_account: Option<String>,
+        partitions: Vec<String>,
+        clusters: Vec<String>,
+    ) -> AppResult<CommandOutput> {
+        let default_account = default_account
+            .or_else(|| accounts.first().cloned())
+            .unwrap_or_default();
+        let user =
+            json!({ "users": [{ "name": username, "default": { "account": default_account } }] });
+        ensure_success!(self.call("POST", "/users", Some(user))?);
+
+        let partitions: Vec<Option<&String>> = if partitions.is_empty() {
+            vec![None]
+        } else {
+            partitions.iter().map(Some).collect()
+        };
+        let clusters: Vec<Option<&String>> = if clusters.is_empty() {
+            vec![None]
+        } else {
+            clusters.iter().map(Some).collect()
+        };
+        let associations: Vec<Value> = clusters
+            .iter()
+            .flat_map(|cluster| accounts.iter().map(move |account| (cluster, account)))
+            .flat_map(|(cluster, account)| {
+                partitions.iter().map(move |partition| {
+                    let mut association = json!({ "user": username, "account": account });
+                    if let Some(cluster) = cluster {
+                        set_path(&mut association, &["cluster"], json!(cluster));
+                    }
+                    if let Some(partition) = partition {
+                        set_path(&mut association, &["partition"], json!(partition));
+                    }
+                    association
+                })
+            })
+            .collect();
+        let body = json!({ "associations": associations });
+        ensure_success!(self.call("POST", "/associations", Some(body))?);
+        Ok(CommandOutput::success(""))
+    }
+
+    /// Sets the values on every association of the user on the given clusters
+    /// or on all clusters if none are given.
+    fn modify_associations(
+        &self,
+        username: &str,
+        to_set: HashMap<&'static str, Vec<String>>,
+        clusters: Vec<String>,
+    ) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.call(
+            "GET",
+            &format!(
+                "/associations?user={}{}",
+                encode(username),
+                cluster_query(&clusters)
+            ),
+            None
+        )?);
+        let mut associations = match response.body.get("associations") {
+            Some(Value::Array(associations)) if !associations.is_empty() => associations.clone(),
+            _ => {
+                return Ok(CommandOutput::failure(
+                    1,
+                    &format!("User {} has no associations to modify", username),
+                ))
+            }
+        };
+
+        for association in associations.iter_mut() {
+            for (key, values) in &to_set {
+                let joined = values.join(",");
+                match *key {
+                    DEFAULT_QOS => set_path(association, &["default", "qos"], json!(joined)),
+                    QOS => set_path(association, &["qos"], json!(values)),
+                    GRP_TRES => set_path(
+                        association,
+                        &["max", "tres", "total"],
+                        tres_to_json(&joined)?,
+                    ),
+                    MAX_JOBS => set_path(
+                        association,
+                        &["max", "jobs", "per", "count"],
+                        number(parse_number(&joined)?),
+                    ),
+                    MAX_SUBMIT_JOBS => set_path(
+                        association,
+                        &["max", "jobs", "per", "submitted"],
+                        number(parse_number(&joined)?),
+                    ),
+                    MAX_WALL => set_path(
+                        association,
+                        &["max", "jobs", "per", "wall_clock"],
+                        number(wall_to_minutes(&joined)?),
+                    ),
+                    FAIRSHARE => {
+                        set_path(association, &["shares_raw"], json!(parse_number(&joined)?))
+                    }
+                    unknown => bail!(
+                        "Setting ({}) of an user is not supported by slurmrestd backend",
+                        unknown
+                    ),
+                }
+            }
+        }
+
+        let body = json!({ "associations": associations });
+        ensure_success!(self.call("POST", "/associations", Some(body))?);
+        Ok(CommandOutput::success(""))
+    }
+
+    /// Output like `sacctmgr --noheader --parsable2 show assoc` with the fields expected by
+    /// [`SlurmAssociation::parse_all`].
+    fn show_associations(
+        &self,
+        user: Option<String>,
+        clusters: Vec<String>,
+    ) -> AppResult<CommandOutput> {
+        let mut conditions = Vec::new();
+        if let Some(user) = user {
+            conditions.push(format!("user={}", encode(&user)));
+        }
+        if !clusters.is_empty() {
+            conditions.push(format!("cluster={}", encode(&clusters.join(","))));
+        }
+        let path = if conditions.is_empty() {
+            "/associations".to_owned()
+        } else {
+            format!("/associations?{}", conditions.join("&"))
+        };
+        let response = ensure_success!(self.call("GET", &path, None)?);
+        let lines: String = list(&response.body, "associations")
+            .iter()
+            .map(|association| {
+                let text = |path: &[&str]| {
+                    Some(to_text(get_path(association, path))).filter(|value| !value.is_empty())
+                };
+                let number = |path: &[&str]| {
+                    read_number(get_path(association, path))
+                        .and_then(|value| u32::try_from(value).ok())
+                };
+                let grp_tres = tres_to_text(get_path(association, &["max", "tres", "total"]));
+                SlurmAssociation {
+                    cluster: to_text(get_path(association, &["cluster"])),
+                    account: to_text(get_path(association, &["account"])),
+                    user: text(&["user"]),
+                    partition: text(&["partition"]),
+                    default_qos: text(&["default", "qos"]),
+                    qos: list(association, "qos").iter().map(to_text).collect(),
+                    grp_tres: Some(grp_tres).filter(|value| !value.is_empty()),
+                    max_jobs: number(&["max", "jobs", "per", "count"]),
+                    max_submit_jobs: number(&["max", "jobs", "per", "submitted"]),
+                    max_wall: read_number(get_path(
+                        association,
+                        &["max", "jobs", "per", "wall_clock"],
+                    ))
+                    .map(minutes_to_wall),
+                    fairshare: text(&["shares_raw"]),
+                }
+                .to_parsable_line()
+                    + "\n"
+            })
+            .collect();
+        Ok(CommandOutput::success(&lines))
+    }
+
+    /// Output like `sacctmgr --noheader --parsable2 show user <username>`.
+    /// It is empty if the user does not exist.
+    fn show_user(&self, username: &str) -> AppResult<CommandOutput> {
+        let response = self.call("GET", &format!("/user/{}", encode(username)), None)?;
+        if response.status == 404 {
+            return Ok(CommandOutput::success(""));
+        }
+        let response = ensure_success!(response);
+        let lines: String = list(&response.body, "users")
+            .iter()
+            .map(|user| {
+                let admin_level = to_text(get_path(user, &["administrator_level"]));
+                format!(
+                    "{}|{}|{}\n",
+                    to_text(get_path(user, &["name"])),
+                    to_text(get_path(user, &["default", "account"])),
+                    if admin_level.is_empty() {
+                        "None".to_owned()
+                    } else {
+                        admin_level
+                    }
+                )
+            })
+            .collect();
+        Ok(CommandOutput::success(&lines))
+    }
+
+    fn show_qos_names(&self) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.call("GET", "/qos", None)?);
+        let names: String = list(&response.body, "qos")
+            .iter()
+            .map(|qos| format!("{}\n", to_text(get_path(qos, &["name"]))))
+            .collect();
+        Ok(CommandOutput::success(&names))
+    }
+
+    /// Slurmrestd changes an existing account instead of refusing to add it.
+    /// Therefore an existing account is reported like sacctmgr does,
+    /// see [`super::SacctmgrOutcome::AlreadyExists`].
+    fn add_account(&self, name: &str, attributes: AccountAttributes) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.get_account(name)?);
+        if !list(&response.body, "accounts").is_empty() {
+            return Ok(CommandOutput::failure(1, ACCOUNT_EXISTS));
+        }
+        self.post_account(name, attributes, true)
+    }
+
+    fn modify_account(
+        &self,
+        name: &str,
+        attributes: AccountAttributes,
+    ) -> AppResult<CommandOutput> {
+        let only_parent = attributes.description.is_none() && attributes.organization.is_none();
+        self.post_account(name, attributes, !only_parent)
+    }
+
+    /// Posts the description and organization of the account if `with_account` is true.
+    /// The parent is set via the association of the account.
+    fn post_account(
+        &self,
+        name: &str,
+        attributes: AccountAttributes,
+        with_account: bool,
+    ) -> AppResult<CommandOutput> {
+        let AccountAttributes {
+            parent,
+            description,
+            organization,
+        } = attributes;
+        if with_account {
+            let mut account = json!({ "name": name });
+            if let Some(description) = description {
+                set_path(&mut account, &["description"], json!(description));
+            }
+            if let Some(organization) = organization {
+                set_path(&mut account, &["organization"], json!(organization));
+            }
+            let body = json!({ "accounts": [account] });
+            ensure_success!(self.call("POST", "/accounts", Some(body))?);
+        }
+        if let Some(parent) = parent {
+            let body = json!({ "associations": [{ "account": name, "parent_account": parent }] });
+            ensure_success!(self.call("POST", "/associations", Some(body))?);
+        }
+        Ok(CommandOutput::success(""))
+    }
+
+    /// Response with the account of the given name.
+    /// Its list of accounts is empty if the account does not exist.
+    fn get_account(&self, name: &str) -> AppResult<RestResponse> {
+        let response = self.call("GET", &format!("/account/{}", encode(name)), None)?;
+        if response.status == 404 {
+            return Ok(RestResponse {
+                status: 200,
+                body: json!({ "accounts": [] }),
+            });
+        }
+        Ok(response)
+    }
+
+    fn show_accounts(&self, parseable: bool) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.call("GET", "/accounts", None)?);
+        let rows: Vec<Vec<String>> = list(&response.body, "accounts")
+            .iter()
+            .map(|account| {
+                ["name", "description", "organization"]
+                    .into_iter()
+                    .map(|key| to_text(get_path(account, &[key])))
+                    .collect()
+            })
+            .collect();
+        Ok(CommandOutput::success(&render_table(
+            &ACCOUNT_HEADERS,
+            &rows,
+            parseable,
+        )))
+    }
+
+    /// Coordinators given within an account are added to the existing ones by slurmrestd.
+    /// The account must exist since slurmrestd would create it otherwise.
+    fn add_coordinators(&self, account: &str, users: Vec<String>) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.get_account(account)?);
+        if list(&response.body, "accounts").is_empty() {
+            return Ok(CommandOutput::failure(
+                1,
+                &format!(" Account {} does not exist", account),
+            ));
+        }
+        let coordinators: Vec<Value> = users.iter().map(|user| json!({ "name": user })).collect();
+        let body = json!({ "accounts": [{ "name": account, "coordinators": coordinators }] });
+        ensure_success!(self.call("POST", "/accounts", Some(body))?);
+        Ok(CommandOutput::success(""))
+    }
+
+    fn show_coordinators(&self, parseable: bool) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.call("GET", "/accounts", None)?);
+        let rows: Vec<Vec<String>> = list(&response.body, "accounts")
+            .iter()
+            .map(|account| {
+                let coordinators: Vec<String> = list(account, "coordinators")
+                    .iter()
+                    .map(|coordinator| to_text(get_path(coordinator, &["name"])))
+                    .collect();
+                vec![
+                    to_text(get_path(account, &["name"])),
+                    coordinators.join(","),
+                ]
+            })
+            .collect();
+        Ok(CommandOutput::success(&render_table(
+            &COORDINATOR_HEADERS,
+            &rows,
+            parseable,
+        )))
+    }
+
+    fn upsert_qos(&self, name: &str, attributes: QosAttributes) -> AppResult<CommandOutput> {
+        let mut qos = json!({ "name": name });
+        if let Some(priority) = attributes.priority {
+            set_path(&mut qos, &["priority"], number(u64::from(priority)));
+        }
+        if let Some(max_tres_per_user) = attributes.max_tres_per_user {
+            set_path(
+                &mut qos,
+                &["limits", "max", "tres", "per", "user"],
+                tres_to_json(&max_tres_per_user)?,
+            );
+        }
+        if let Some(max_wall) = attributes.max_wall {
+            set_path(
+                &mut qos,
+                &["limits", "max", "wall_clock", "per", "job"],
+                number(wall_to_minutes(&max_wall)?),
+            );
+        }
+        if let Some(preempt) = attributes.preempt {
+            set_path(&mut qos, &["preempt", "list"], json!(preempt));
+        }
+        if let Some(preempt_mode) = attributes.preempt_mode {
+            set_path(&mut qos, &["preempt", "mode"], json!([preempt_mode]));
+        }
+        let body = json!({ "qos": [qos] });
+        ensure_success!(self.call("POST", "/qos", Some(body))?);
+        Ok(CommandOutput::success(""))
+    }
+
+    fn show_qos(&self, parseable: bool) -> AppResult<CommandOutput> {
+        let response = ensure_success!(self.call("GET", "/qos", None)?);
+        let rows: Vec<Vec<String>> = list(&response.body, "qos")
+            .iter()
+            .map(|qos| {
+                vec![
+                    to_text(get_path(qos, &["name"])),
+                    to_text(get_path(qos, &["priority"])),
+                    tres_to_text(get_path(qos, &["limits", "max", "tres", "per", "user"])),
+                    read_number(get_path(
+                        qos,
+                        &["limits", "max", "wall_clock", "per", "job"],
+                    ))
+                    .map(minutes_to_wall)
+                    .unwrap_or_default(),
+                    to_text(get_path(qos, &["preempt", "list"])),
+                    to_text(get_path(qos, &["preempt", "mode"])),
+                ]
+            })
+            .collect();
+        Ok(CommandOutput::success(&render_table(
+            &QOS_HEADERS,
+            &rows,
+            parseable,
+        )))
+    }
+
+    /// Deletes the entity at the path.
+    /// Nothing removed is reported like sacctmgr does, see [`super::SacctmgrOutcome::NothingDeleted`].
+    fn delete(&self, path: &str) -> AppResult<CommandOutput> {
+        let response = self.call("DELETE", path, None)?;
+        if response.status == 404 {
+            return Ok(CommandOutput::failure(1, NOTHING_DELETED));
+        }
+        let response = ensure_success!(response);
+        let removed = [
+            "removed_users",
+            "removed_accounts",
+            "removed_qos",
+            "removed_associations",
+        ]
+        .into_iter()
+        .filter_map(|key| response.body.get(key))
+        .filter_map(Value::as_array)
+        .map(Vec::len)
+        .sum::<usize>();
+        if removed == 0 {
+            return Ok(CommandOutput::failure(1, NOTHING_DELETED));
+        }
+        Ok(CommandOutput::success(""))
+    }
+
+    fn call(&self, method: &str, path: &str, body: Option<Value>) -> AppResult<RestResponse> {
+        let url = format!("{}{}", self.base_url, path);
+        debug!("Request to slurmrestd: {} {}", method, url);
+        let request = self
+            .agent
+            .request(method, &url)
+            .set(HEADER_USER_NAME, &self.user)
+            .set(HEADER_USER_TOKEN, &self.token);
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => response,
+            Err(error) => {
+                return Err(error).with_context(|| {
+                    format!("Could not reach slurmrestd for request {} {}", method, url)
+                })
+            }
+        };
+        let status = response.status();
+        let text = response
+            .into_string()
+            .with_context(|| format!("Could not read response of slurmrestd for {}", url))?;
+        let body = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        };
+        Ok(RestResponse { status, body })
+    }
+}
+
+/// Additional query parameter which restricts associations to the given clusters.
+/// Empty without any cluster.
+fn cluster_query(clusters: &[String]) -> String {
+    if clusters.is_empty() {
+        String::new()
+    } else {
+        format!("&cluster={}", encode(&clusters.join(",")))
+    }
+}
+
+/// Entries of the list under the given key of a response body.
+fn list<'a>(body: &'a Value, key: &str) -> &'a [Value] {
+    body.get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn parse_number(text: &str) -> AppResult<u64> {
+    text.parse()
+        .with_context(|| format!("Value ({}) is not a number", text))
+}
+
+/// Percent encoding for a single segment of an URL path or a query value.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            other => format!("%{:02X}", other),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing;
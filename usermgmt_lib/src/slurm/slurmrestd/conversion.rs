@@ -0,0 +1,288 @@
+//! Conversion between the text values of sacctmgr and the JSON values of slurmrestd.
+
+use anyhow::{bail, Context};
+use serde_json::{json, Map, Value};
+
+use crate::prelude::AppResult;
+
+const MINUTES_PER_HOUR: u64 = 60;
+const MINUTES_PER_DAY: u64 = 24 * MINUTES_PER_HOUR;
+
+/// Number in the format of slurmrestd which can also be unset or infinite.
+pub fn number(value: u64) -> Value {
+    json!({ "set": true, "infinite": false, "number": value })
+}
+
+/// Reads a number given as plain integer or in the format of [`number`].
+/// Returns `None` for unset or infinite numbers.
+pub fn read_number(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::Object(object) => {
+            let is_set = object.get("set").and_then(Value::as_bool).unwrap_or(true);
+            let is_infinite = object
+                .get("infinite")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if is_set && !is_infinite {
+                object.get("number").and_then(Value::as_u64)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Turns trackable resources like `cpu=64,gres/gpu=2` into a list of TRES objects.
+///
+/// # Errors
+///
+/// - If a resource has no count or the count is not a number.
+pub fn tres_to_json(text: &str) -> AppResult<Value> {
+    let mut tres = Vec::new();
+    for resource in text
+        .split(',')
+        .map(str::trim)
+        .filter(|next| !next.is_empty())
+    {
+        let (kind, count) = resource
+            .split_once('=')
+            .with_context(|| format!("Resource ({}) has no count like cpu=4", resource))?;
+        let count: u64 = count
+            .parse()
+            .with_context(|| format!("Count of resource ({}) is not a number", resource))?;
+        let mut object = Map::new();
+        match kind.split_once('/') {
+            Some((kind, name)) => {
+                object.insert("type".to_owned(), kind.into());
+                object.insert("name".to_owned(), name.into());
+            }
+            None => {
+                object.insert("type".to_owned(), kind.into());
+            }
+        }
+        object.insert("count".to_owned(), count.into());
+        tres.push(Value::Object(object));
+    }
+    Ok(Value::Array(tres))
+}
+
+/// Inverse of [`tres_to_json`]. Returns an empty text for missing or empty TRES.
+pub fn tres_to_text(value: &Value) -> String {
+    value
+        .as_array()
+        .map(|tres| {
+            tres.iter()
+                .filter_map(|resource| {
+                    let kind = resource.get("type")?.as_str()?;
+                    let count = resource.get("count").and_then(read_number)?;
+                    let name = resource
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .filter(|name| !name.is_empty());
+                    Some(match name {
+                        Some(name) => format!("{}/{}={}", kind, name, count),
+                        None => format!("{}={}", kind, count),
+                    })
+                })
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .unwrap_or_default()
+}
+
+/// Turns a time limit of slurm into minutes.
+/// Accepted are the formats of slurm: `minutes`, `minutes:seconds`, `hours:minutes:seconds`,
+/// `days-hours`, `days-hours:minutes` and `days-hours:minutes:seconds`.
+/// Seconds are rounded up to the next minute like slurm does.
+///
+/// # Errors
+///
+/// - If the text is not in one of the formats above.
+pub fn wall_to_minutes(text: &str) -> AppResult<u64> {
+    let parse = |part: &str| {
+        part.parse::<u64>()
+            .with_context(|| format!("Time limit ({}) is not valid", text))
+    };
+    let round_up_seconds = |seconds: u64| u64::from(seconds > 0);
+
+    let (days, rest) = match text.split_once('-') {
+        Some((days, rest)) => (Some(parse(days)?), rest),
+        None => (None, text),
+    };
+    let parts = rest
+        .split(':')
+        .map(parse)
+        .collect::<AppResult<Vec<u64>>>()?;
+    let minutes = match (days, parts.as_slice()) {
+        (None, [minutes]) => *minutes,
+        (None, [minutes, seconds]) => minutes + round_up_seconds(*seconds),
+        (None, [hours, minutes, seconds]) => {
+            hours * MINUTES_PER_HOUR + minutes + round_up_seconds(*seconds)
+        }
+        (Some(days), [hours]) => days * MINUTES_PER_DAY + hours * MINUTES_PER_HOUR,
+        (Some(days), [hours, minutes]) => {
+            days * MINUTES_PER_DAY + hours * MINUTES_PER_HOUR + minutes
+        }
+        (Some(days), [hours, minutes, seconds]) => {
+            days * MINUTES_PER_DAY + hours * MINUTES_PER_HOUR + minutes + round_up_seconds(*seconds)
+        }
+        _ => bail!("Time limit ({}) is not valid", text),
+    };
+    Ok(minutes)
+}
+
+/// Time limit in minutes as sacctmgr shows it, e.g. `2-00:00:00` or `01:30:00`.
+pub fn minutes_to_wall(minutes: u64) -> String {
+    let (days, rest) = (minutes / MINUTES_PER_DAY, minutes % MINUTES_PER_DAY);
+    let (hours, minutes) = (rest / MINUTES_PER_HOUR, rest % MINUTES_PER_HOUR);
+    if days > 0 {
+        format!("{}-{:02}:{:02}:00", days, hours, minutes)
+    } else {
+        format!("{:02}:{:02}:00", hours, minutes)
+    }
+}
+
+/// Sets the value at the path of keys and creates missing objects on the way.
+pub fn set_path(target: &mut Value, path: &[&str], value: Value) {
+    let mut current = target;
+    for key in path {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        current = current
+            .as_object_mut()
+            .expect("Value was turned into an object above")
+            .entry(key.to_string())
+            .or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+/// Value at the path of keys or `Value::Null` if one of the keys is missing.
+pub fn get_path<'a>(source: &'a Value, path: &[&str]) -> &'a Value {
+    path.iter()
+        .try_fold(source, |current, key| current.get(key))
+        .unwrap_or(&Value::Null)
+}
+
+/// Text of a value as sacctmgr would show it.
+/// Lists are joined by commas, missing values become an empty text.
+pub fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(to_text)
+            .filter(|next| !next.is_empty())
+            .collect::<Vec<String>>()
+            .join(","),
+        other => read_number(other)
+            .map(|number| number.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Renders rows like sacctmgr does.
+/// With `parseable` cells are followed by `|` like with the option `--parsable`.
+/// Otherwise cells are padded to the width of their column.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>], parseable: bool) -> String {
+    let header_row: Vec<String> = headers.iter().map(ToString::to_string).collect();
+    let all_rows = std::iter::once(&header_row).chain(rows);
+    if parseable {
+        return all_rows
+            .map(|row| format!("{}|\n", row.join("|")))
+            .collect();
+    }
+
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            std::iter::once(&header_row)
+                .chain(rows)
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    all_rows
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            format!("{}\n", cells.join(" "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn convert_tres_back_and_forth() {
+        let json = tres_to_json("cpu=64,gres/gpu=2").unwrap();
+        assert_eq!(
+            json!([
+                { "type": "cpu", "count": 64 },
+                { "type": "gres", "name": "gpu", "count": 2 },
+            ]),
+            json
+        );
+        assert_eq!("cpu=64,gres/gpu=2", tres_to_text(&json));
+        assert!(tres_to_json("cpu").is_err());
+    }
+
+    #[test]
+    fn convert_wall_time_back_and_forth() {
+        let actual: Vec<u64> = ["90", "90:30", "01:30:00", "2-00", "2-01:30", "2-00:00:00"]
+            .into_iter()
+            .map(|text| wall_to_minutes(text).unwrap())
+            .collect();
+        assert_eq!(vec![90, 91, 90, 2880, 2970, 2880], actual);
+        assert_eq!("2-00:00:00", minutes_to_wall(2880));
+        assert_eq!("01:30:00", minutes_to_wall(90));
+        assert!(wall_to_minutes("1:2:3:4").is_err());
+    }
+
+    #[test]
+    fn read_plain_and_structured_numbers() {
+        assert_eq!(Some(5), read_number(&json!(5)));
+        assert_eq!(Some(5), read_number(&number(5)));
+        assert_eq!(
+            None,
+            read_number(&json!({ "set": false, "infinite": false, "number": 0 }))
+        );
+        assert_eq!(
+            None,
+            read_number(&json!({ "set": true, "infinite": true, "number": 0 }))
+        );
+    }
+
+    #[test]
+    fn set_and_get_nested_values() {
+        let mut target = json!({ "user": "somebody" });
+        set_path(&mut target, &["max", "jobs", "per", "count"], number(10));
+        assert_eq!(
+            Some(10),
+            read_number(get_path(&target, &["max", "jobs", "per", "count"]))
+        );
+        assert_eq!(&Value::Null, get_path(&target, &["max", "tres"]));
+    }
+
+    #[test]
+    fn render_parseable_and_aligned_tables() {
+        let rows = vec![vec!["somebody".to_owned(), "staff".to_owned()]];
+        assert_eq!(
+            "User|Account|\nsomebody|staff|\n",
+            render_table(&["User", "Account"], &rows, true)
+        );
+        assert_eq!(
+            "    User Account\nsomebody   staff\n",
+            render_table(&["User", "Account"], &rows, false)
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde::Serialize;
+
+use super::*;
+use crate::executor::RecordingExecutor;
+use crate::slurm::{self, CommandBuilder};
+
+/// Request received by the [`StandIn`].
+#[derive(Debug, Clone, Serialize)]
+struct ReceivedRequest {
+    method: String,
+    path: String,
+    user_name: Option<String>,
+    token: Option<String>,
+    body: Option<Value>,
+}
+
+/// Local HTTP server which mimics slurmrestd.
+/// It answers every request with the first scripted response whose method and path prefix match.
+/// Requests without a scripted response are answered with 200 and an empty JSON object.
+struct StandIn {
+    url: String,
+    received: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl StandIn {
+    fn start(responses: Vec<(&'static str, &'static str, u16, Value)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recording = Arc::clone(&received);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let request = read_request(&mut stream);
+                let (status, body) = responses
+                    .iter()
+                    .find(|(method, path, _, _)| {
+                        request.method == *method && request.path.starts_with(path)
+                    })
+                    .map(|(_, _, status, body)| (*status, body.to_string()))
+                    .unwrap_or((200, "{}".to_owned()));
+                recording.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stand-In\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        Self { url, received }
+    }
+
+    fn config(&self) -> MgmtConfig {
+        MgmtConfig {
+            use_slurmrestd: true,
+            slurmrestd_url: self.url.clone(),
+            slurmrestd_user: Some("admin".to_owned()),
+            slurmrestd_token: Some("secret.jwt".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn received(&self) -> Vec<ReceivedRequest> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &mut std::net::TcpStream) -> ReceivedRequest {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (
+        parts.next().unwrap().to_owned(),
+        parts.next().unwrap().to_owned(),
+    );
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap();
+        headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+    }
+
+    let length: usize = headers
+        .get("content-length")
+        .map(|length| length.parse().unwrap())
+        .unwrap_or_default();
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    ReceivedRequest {
+        method,
+        path,
+        user_name: headers.get(&HEADER_USER_NAME.to_lowercase()).cloned(),
+        token: headers.get(&HEADER_USER_TOKEN.to_lowercase()).cloned(),
+        body: (length > 0).then(|| serde_json::from_slice(&body).unwrap()),
+    }
+}
+
+fn association() -> Value {
+    json!({
+        "cluster": "cluster",
+        "user": "somebody",
+        "account": "staff",
+        "partition": "gpu",
+        "default": { "qos": "basic" },
+        "qos": ["basic", "interactive"],
+        "max": {
+            "tres": { "total": [{ "type": "cpu", "count": 64 }] },
+            "jobs": { "per": {
+                "count": { "set": true, "infinite": false, "number": 10 },
+                "submitted": { "set": false, "infinite": false, "number": 0 },
+                "wall_clock": { "set": true, "infinite": false, "number": 2880 },
+            } },
+        },
+        "shares_raw": 1,
+    })
+}
+
+#[test]
+fn add_user_with_limits_via_slurmrestd() {
+    // Associations created in the first step are returned for the step which sets QOS and limits
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/associations?user=somebody",
+        200,
+        json!({ "associations": [
+            { "user": "somebody", "account": "staff", "partition": "gpu" },
+            { "user": "somebody", "account": "project_a", "partition": "gpu" },
+        ] }),
+    )]);
+    let action = CommandBuilder::new_add(
+        "somebody".to_owned(),
+        vec!["staff".to_owned(), "project_a".to_owned()],
+        Some("project_a".to_owned()),
+        "basic".to_owned(),
+        vec!["basic".to_owned(), "interactive".to_owned()],
+        slurm::AssociationLimits {
+            max_jobs: Some(10),
+            partitions: vec!["gpu".to_owned()],
+            ..Default::default()
+        },
+        Vec::new(),
+    );
+    let recorder = RecordingExecutor::new();
+
+    slurm::run_slurm_action(action, &stand_in.config(), &recorder).unwrap();
+
+    assert!(recorder.recorded().is_empty());
+    insta::assert_yaml_snapshot!(stand_in.received());
+}
+
+#[test]
+fn list_associations_like_sacctmgr() {
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/associations",
+        200,
+        json!({ "associations": [association()] }),
+    )]);
+
+    let actual = slurm::list_associations(&stand_in.config(), &RecordingExecutor::new()).unwrap();
+
+    insta::assert_yaml_snapshot!(actual);
+}
+
+#[test]
+fn detect_existing_and_missing_user() {
+    let stand_in = StandIn::start(vec![
+        (
+            "GET",
+            "/slurmdb/v0.0.40/user/somebody",
+            200,
+            json!({ "users": [{ "name": "somebody", "default": { "account": "staff" } }] }),
+        ),
+        (
+            "GET",
+            "/slurmdb/v0.0.40/user/nobody",
+            404,
+            json!({ "errors": [{ "error": "Nothing found" }] }),
+        ),
+    ]);
+    let (config, recorder) = (stand_in.config(), RecordingExecutor::new());
+
+    assert!(slurm::user_exists("somebody", &config, &recorder).unwrap());
+    assert!(!slurm::user_exists("nobody", &config, &recorder).unwrap());
+    let received = stand_in.received();
+    assert_eq!(Some("admin"), received[0].user_name.as_deref());
+    assert_eq!(Some("secret.jwt"), received[0].token.as_deref());
+}
+
+#[test]
+fn delete_missing_user_without_error_via_slurmrestd() {
+    let stand_in = StandIn::start(vec![(
+        "DELETE",
+        "/slurmdb/v0.0.40/user/somebody",
+        200,
+        json!({ "removed_users": [] }),
+    )]);
+
+    let actual = slurm::delete_slurm_user(
+        "somebody",
+        &[],
+        &stand_in.config(),
+        &RecordingExecutor::new(),
+    );
+
+    assert!(actual.is_ok());
+}
+
+#[test]
+fn delete_user_on_clusters_via_slurmrestd() {
+    let stand_in = StandIn::start(vec![(
+        "DELETE",
+        "/slurmdb/v0.0.40/associations?user=somebody&cluster=alpha",
+        200,
+        json!({ "removed_associations": ["alpha|staff|somebody|"] }),
+    )]);
+
+    let actual = slurm::delete_slurm_user(
+        "somebody",
+        &["alpha".to_owned()],
+        &stand_in.config(),
+        &RecordingExecutor::new(),
+    );
+
+    assert!(actual.is_ok());
+    assert_eq!(
+        "/slurmdb/v0.0.40/associations?user=somebody&cluster=alpha",
+        stand_in.received()[0].path
+    );
+}
+
+#[test]
+fn report_errors_of_slurmrestd() {
+    let stand_in = StandIn::start(vec![(
+        "POST",
+        "/slurmdb/v0.0.40/qos",
+        500,
+        json!({ "errors": [{ "error": "Unable to add QOS", "description": "Database unreachable" }] }),
+    )]);
+
+    let actual = slurm::add_slurm_qos(
+        "gpu",
+        Default::default(),
+        &stand_in.config(),
+        &RecordingExecutor::new(),
+    )
+    .unwrap_err();
+
+    let message = format!("{:#}", actual);
+    assert!(
+        message.contains("with error code 500: Unable to add QOS: Database unreachable"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn add_account_with_only_parent_via_slurmrestd() {
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/account/project_a",
+        404,
+        json!({ "errors": [{ "error": "Nothing found" }] }),
+    )]);
+    let attributes = slurm::AccountAttributes {
+        parent: Some("staff".to_owned()),
+        ..Default::default()
+    };
+
+    slurm::add_slurm_account(
+        "project_a",
+        attributes,
+        &stand_in.config(),
+        &RecordingExecutor::new(),
+    )
+    .unwrap();
+
+    let received: Vec<(String, String, Option<Value>)> = stand_in
+        .received()
+        .into_iter()
+        .map(|request| (request.method, request.path, request.body))
+        .collect();
+    assert_eq!(
+        vec![
+            (
+                "GET".to_owned(),
+                "/slurmdb/v0.0.40/account/project_a".to_owned(),
+                None
+            ),
+            (
+                "POST".to_owned(),
+                "/slurmdb/v0.0.40/accounts".to_owned(),
+                Some(json!({ "accounts": [{ "name": "project_a" }] }))
+            ),
+            (
+                "POST".to_owned(),
+                "/slurmdb/v0.0.40/associations".to_owned(),
+                Some(
+                    json!({ "associations": [{ "account": "project_a", "parent_account": "staff" }] })
+                )
+            ),
+        ],
+        received
+    );
+}
+
+#[test]
+fn keep_existing_account_on_add_via_slurmrestd() {
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/account/staff",
+        200,
+        json!({ "accounts": [{ "name": "staff", "description": "old" }] }),
+    )]);
+    let attributes = slurm::AccountAttributes {
+        description: Some("new".to_owned()),
+        ..Default::default()
+    };
+    let sub_command = SlurmSubCommand::AddAccount {
+        name: "staff".to_owned(),
+        attributes,
+    };
+
+    let client = SlurmRestClient::from_config(&stand_in.config()).unwrap();
+    let actual = client.run("", sub_command).unwrap();
+
+    assert_eq!(
+        slurm::SacctmgrOutcome::AlreadyExists,
+        slurm::SacctmgrOutcome::from_output(actual, true)
+    );
+    assert!(stand_in
+        .received()
+        .iter()
+        .all(|request| request.method == "GET"));
+}
+
+#[test]
+fn add_coordinators_only_to_existing_account_via_slurmrestd() {
+    let stand_in = StandIn::start(vec![
+        (
+            "GET",
+            "/slurmdb/v0.0.40/account/staff",
+            200,
+            json!({ "accounts": [{ "name": "staff" }] }),
+        ),
+        (
+            "GET",
+            "/slurmdb/v0.0.40/account/missing",
+            404,
+            json!({ "errors": [{ "error": "Nothing found" }] }),
+        ),
+    ]);
+    let (config, recorder) = (stand_in.config(), RecordingExecutor::new());
+    let users = ["somebody".to_owned()];
+
+    slurm::add_slurm_coordinators("staff", &users, &config, &recorder).unwrap();
+    assert!(slurm::add_slurm_coordinators("missing", &users, &config, &recorder).is_err());
+
+    let posted: Vec<Option<Value>> = stand_in
+        .received()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.body)
+        .collect();
+    assert_eq!(
+        vec![Some(
+            json!({ "accounts": [{ "name": "staff", "coordinators": [{ "name": "somebody" }] }] })
+        )],
+        posted
+    );
+}
+
+#[test]
+fn error_without_jwt() {
+    std::env::remove_var(SLURM_JWT_ENV);
+    let config = MgmtConfig {
+        use_slurmrestd: true,
+        ..Default::default()
+    };
+
+    assert!(SlurmRestClient::from_config(&config).is_err());
+}
+
+#[test]
+fn check_access_with_jwt() {
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/qos",
+        200,
+        json!({ "qos": [{ "name": "normal" }, { "name": "gpu" }] }),
+    )]);
+    let client = SlurmRestClient::from_config(&stand_in.config()).unwrap();
+
+    assert_eq!(2, client.check_access().unwrap());
+    let received = stand_in.received();
+    assert_eq!(Some("secret.jwt".to_owned()), received[0].token);
+}
+
+#[test]
+fn refused_access_with_invalid_jwt() {
+    let stand_in = StandIn::start(vec![(
+        "GET",
+        "/slurmdb/v0.0.40/qos",
+        401,
+        json!({ "errors": [{ "error": "Authentication failure" }] }),
+    )]);
+    let client = SlurmRestClient::from_config(&stand_in.config()).unwrap();
+
+    let actual = client.check_access().unwrap_err();
+
+    assert_eq!(
+        "Request GET /qos failed with status 401: Authentication failure",
+        actual.to_string()
+    );
+}
@@ -0,0 +1,319 @@
+use super::*;
+use crate::executor::{CommandOutput, RecordingExecutor};
+use crate::util::{ResolvedGid, ValidGroupOfQos, ValidQos};
+use crate::{Entity, Group};
+
+fn new_entity(config: &MgmtConfig) -> NewEntity {
+    let group = ResolvedGid::new(Group::Staff, config);
+    NewEntity {
+        username: "somebody".try_into().unwrap(),
+        firstname: "Some".try_into().unwrap(),
+        lastname: "Body".try_into().unwrap(),
+        mail: None,
+        default_qos: ValidQos::default_qos_from_conf(group.id(), config),
+        qos: ValidGroupOfQos::from_group(group.id(), config).unwrap(),
+        publickey: None,
+        accounts: vec![group.id().to_string()],
+        default_account: None,
+        limits: Default::default(),
+        clusters: config.clusters_of_group(group.id()),
+        coordinator: false,
+        group,
+    }
+}
+
+#[test]
+fn add_user_flow() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new();
+
+    add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    )
+    .unwrap();
+
+    insta::assert_yaml_snapshot!(recorder.recorded());
+}
+
+#[test]
+fn add_user_as_coordinator_of_default_account() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new();
+    let mut entity = new_entity(&config);
+    entity.accounts = vec!["project_a".to_owned(), "project_b".to_owned()];
+    entity.default_account = Some("project_b".to_owned());
+    entity.coordinator = true;
+
+    add_slurm_user(&entity, &config, &recorder, &QosCache::default()).unwrap();
+
+    assert_eq!(
+        Some(
+            &"/usr/local/bin/sacctmgr add coordinator Account=project_b Names=somebody --immediate"
+                .to_owned()
+        ),
+        recorder.recorded_lines().last()
+    );
+}
+
+#[test]
+fn delete_user_flow() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new();
+
+    delete_slurm_user("somebody", &[], &config, &recorder).unwrap();
+
+    insta::assert_yaml_snapshot!(recorder.recorded());
+}
+
+#[test]
+fn add_and_delete_user_on_configured_clusters() {
+    let config = MgmtConfig {
+        clusters: vec!["alpha".to_owned(), "beta".to_owned()],
+        staff_clusters: vec!["beta".to_owned()],
+        ..Default::default()
+    };
+    let recorder = RecordingExecutor::new();
+
+    add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    )
+    .unwrap();
+    delete_slurm_user("somebody", &["alpha".to_owned()], &config, &recorder).unwrap();
+    let unknown = delete_slurm_user("somebody", &["gamma".to_owned()], &config, &recorder);
+
+    insta::assert_yaml_snapshot!(recorder.recorded_lines());
+    assert_eq!(
+        "Clusters (gamma) are not part of the configured clusters (alpha,beta)",
+        unknown.unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn modify_user_flow() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new();
+    let fields = crate::cli::CommonUserFields {
+        qos: vec!["interactive".to_owned(), "basic".to_owned()],
+        default_qos: Some("basic".try_into().unwrap()),
+        ..Default::default()
+    };
+    let changes = ChangesToUser::try_new(
+        Entity::new_inner(
+            "somebody".try_into().unwrap(),
+            None,
+            None,
+            fields,
+            &config,
+            |_| panic!(),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    modify_slurm_user(&changes, &config, &recorder, &QosCache::default()).unwrap();
+
+    insta::assert_yaml_snapshot!(recorder.recorded_lines());
+}
+
+#[test]
+fn stop_add_user_flow_after_failed_command() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new()
+        .respond_with("/usr/local/bin/sacctmgr add", CommandOutput::failure(1, ""));
+
+    let actual = add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    );
+
+    assert!(actual.is_err());
+    assert_eq!(1, recorder.recorded().len());
+}
+
+#[test]
+fn list_associations_parsed_from_executor() {
+    const LISTED: &str = "cluster|root|||||||||1\ncluster|root|dev_user|||normal|||||1\n";
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr --noheader --parsable2 show assoc",
+        CommandOutput::success(LISTED),
+    );
+
+    let actual = list_associations(&config, &recorder).unwrap();
+
+    assert_eq!(2, actual.len());
+    assert_eq!(None, actual[0].user);
+    assert_eq!(Some("dev_user"), actual[1].user.as_deref());
+    assert_eq!(vec!["normal"], actual[1].qos);
+}
+
+#[test]
+fn choose_executor_by_config() {
+    let recorder = RecordingExecutor::new();
+    let remote = executor_from_config(
+        &MgmtConfig {
+            run_slurm_remote: true,
+            ..Default::default()
+        },
+        &recorder,
+    );
+    assert!(matches!(remote, LocalOrRemoteExecutor::Remote(_)));
+
+    let local = executor_from_config(
+        &MgmtConfig {
+            run_slurm_remote: false,
+            ..Default::default()
+        },
+        &recorder,
+    );
+    assert!(matches!(local, LocalOrRemoteExecutor::Local(_)));
+}
+
+#[test]
+fn add_existing_user_again_without_error() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr add",
+        CommandOutput::new(1, " Nothing new added.\n".to_owned(), String::new()),
+    );
+
+    add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    )
+    .unwrap();
+
+    assert_eq!(2, recorder.recorded().len());
+}
+
+#[test]
+fn delete_missing_user_without_error() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr delete",
+        CommandOutput::failure(1, " Nothing deleted\n"),
+    );
+
+    delete_slurm_user("somebody", &[], &config, &recorder).unwrap();
+}
+
+#[test]
+fn report_failed_modify_mentioning_existing_entity() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr modify",
+        CommandOutput::failure(1, " Account faculty_cs already exists as parent\n"),
+    );
+    let attributes = AccountAttributes {
+        parent: Some("faculty_cs".to_owned()),
+        ..Default::default()
+    };
+
+    let actual = modify_slurm_account("faculty_cs", attributes, &config, &recorder);
+
+    assert!(actual.is_err());
+}
+
+#[test]
+fn report_stderr_of_failed_command() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr modify",
+        CommandOutput::failure(
+            1,
+            " You gave a bad qos 'gpu'.  Use 'list qos' to get complete list.\n",
+        ),
+    );
+
+    let error = add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    )
+    .unwrap_err();
+
+    insta::assert_snapshot!(format!("{:#}", error));
+}
+
+#[test]
+fn detect_existing_user() {
+    let config = MgmtConfig::default();
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr --noheader --parsable2 show user somebody",
+        CommandOutput::success("somebody|normal|None|\n"),
+    );
+
+    assert!(user_exists("somebody", &config, &recorder).unwrap());
+    assert!(!user_exists("nobody", &config, &recorder).unwrap());
+}
+
+#[test]
+fn refuse_adding_user_with_qos_unknown_to_slurm() {
+    let config = MgmtConfig {
+        validate_qos_against_slurm: true,
+        ..Default::default()
+    };
+    let recorder = RecordingExecutor::new().respond_with(
+        "/usr/local/bin/sacctmgr --noheader --parsable2 show qos",
+        CommandOutput::success("interactive\nbasic\n"),
+    );
+
+    let actual = add_slurm_user(
+        &new_entity(&config),
+        &config,
+        &recorder,
+        &QosCache::default(),
+    );
+
+    assert!(actual.is_err());
+    assert_eq!(
+        vec!["/usr/local/bin/sacctmgr --noheader --parsable2 show qos format=Name --immediate"],
+        recorder.recorded_lines()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn fetch_qos_only_once_via_local_executor() {
+    use crate::executor::LocalExecutor;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("usermgmt_live_qos_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let runs = dir.join("runs");
+    let sacctmgr = dir.join("sacctmgr");
+    std::fs::write(
+        &sacctmgr,
+        format!(
+            "#!/bin/sh\necho \"$*\" >> '{}'\necho basic\n",
+            runs.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&sacctmgr, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let config = MgmtConfig {
+        sacctmgr_path: sacctmgr.display().to_string(),
+        ..Default::default()
+    };
+    let qos_cache = QosCache::default();
+
+    let first = fetch_qos(&config, &LocalExecutor, &qos_cache);
+    let second = fetch_qos(&config, &LocalExecutor, &qos_cache);
+    let recorded_runs = std::fs::read_to_string(&runs);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(vec!["basic"], first.unwrap());
+    assert_eq!(vec!["basic"], second.unwrap());
+    assert_eq!(1, recorded_runs.unwrap().lines().count());
+}
@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use crate::{config::MgmtConfig, prelude::AppResult};
+use crate::{config::MgmtConfig, executor::CommandOutput, prelude::AppResult};
 use log::debug;
 mod ssh_connection;
 
@@ -41,19 +41,20 @@ pub enum EntitiesAndSshAgent {
 /// # Errors
 ///
 /// - If the execution of remote command fails. See [`SshConnection::exec`].
-pub fn run_remote_command<C>(sess: &SshConnection<C>, cmd: &str) -> AppResult<(i32, String)>
+pub fn run_remote_command<C>(sess: &SshConnection<C>, cmd: &str) -> AppResult<CommandOutput>
 where
     C: SshCredentials,
 {
     debug!("Running command {}", cmd);
 
-    let (s, exit_status) = sess.exec(cmd)?;
+    let output = sess.exec(cmd)?;
 
-    debug!("command exit status: {}", exit_status);
-    if exit_status != 0 {
-        debug!("command output: {}", s);
+    debug!("command exit status: {}", output.exit_code());
+    if !output.is_success() {
+        debug!("command output: {}", output.stdout());
+        debug!("command error output: {}", output.stderr());
     }
-    Ok((exit_status, s))
+    Ok(output)
 }
 
 /// Tries get all identities, pub keys, from the active ssh agent.
@@ -61,7 +62,7 @@ where
 /// # Errors
 ///
 /// - If agent could not retrieve identities because no agent found, connection could not be
-/// established and so on
+///   established and so on
 pub fn get_agent_with_all_entities(session: &mut Session) -> AppResult<EntitiesAndSshAgent> {
     let mut agent = session.agent()?;
     agent.connect()?;
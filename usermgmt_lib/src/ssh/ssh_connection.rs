@@ -1,7 +1,8 @@
 use crate::prelude::*;
 
-use std::io::Read;
+use std::io::{self, Read};
 use std::net::{SocketAddr, TcpStream};
+use std::thread;
 use std::time::Duration;
 
 use once_cell::unsync::OnceCell;
@@ -10,11 +11,15 @@ use log::{info, warn};
 use ssh2::Session;
 
 use crate::config::MgmtConfig;
+use crate::executor::{CommandExecutor, CommandOutput, ExecCommand};
 use crate::prelude::AppResult;
 use crate::ssh::{self, EntitiesAndSshAgent, SshPublicKeySuggestion};
 
 use super::SshCredentials;
 
+/// Pause between reads of the output of a command while nothing new arrived.
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 pub struct SshConnection<'a, T> {
     endpoint: &'a str,
     port: u32,
@@ -50,6 +55,7 @@ where
     }
 
     /// Tries to execute a given command on a remote machine over ssh
+    /// Returns the exit code, stdout and stderr of the executed command.
     ///
     /// # Error
     ///
@@ -58,7 +64,7 @@ where
     /// - If remote command could not be executed.
     /// - If output or exit code of executed remote command could not be retrieved.
     ///
-    pub fn exec(&self, cmd: &str) -> AppResult<(String, i32)> {
+    pub fn exec(&self, cmd: &str) -> AppResult<CommandOutput> {
         let session = self
             .session
             .get_or_try_init(|| -> AppResult<Session> { self.establish_connection() })?;
@@ -71,15 +77,20 @@ where
             .exec(cmd)
             .context("Execution of command on remote machine over ssh has failed.")?;
 
-        let mut output = String::new();
+        // Reading stdout to its end before stderr blocks forever
+        // if the command waits for stderr to be read while stdout is still open.
+        session.set_blocking(false);
+        let output = read_interleaved(channel.stream(0), channel.stderr(), || channel.eof());
+        session.set_blocking(true);
+        let (stdout, stderr) = output?;
         channel
-            .read_to_string(&mut output)
-            .context("Could not read output of executed command over ssh channel")?;
+            .wait_close()
+            .context("Could not close ssh channel of executed command")?;
         let exit_status = channel
             .exit_status()
             .context("Could not retrieve exit code of executed command over ssh")?;
 
-        Ok((output, exit_status))
+        Ok(CommandOutput::new(exit_status, stdout, stderr))
     }
 
     pub fn establish_connection(&self) -> AppResult<Session> {
@@ -214,6 +225,20 @@ where
     }
 }
 
+impl<'a, T> CommandExecutor for SshConnection<'a, T>
+where
+    T: SshCredentials,
+{
+    /// Runs the command line of `command` on the remote machine.
+    ///
+    /// # Errors
+    ///
+    /// - If the execution of the remote command fails. See [`ssh::run_remote_command`].
+    fn run(&self, command: &ExecCommand) -> AppResult<CommandOutput> {
+        ssh::run_remote_command(self, &command.command_line())
+    }
+}
+
 /// Tries to authenticate an user via an active ssh agent.
 /// If more than one key is registered in the ssh agent, user is asked which one to use via prompt in the
 /// terminal.
@@ -223,7 +248,7 @@ where
 /// - If no ssh agent is accessible.
 /// - If no key is registered within ssh agent
 /// - If the selection from user is not within the available range of ssh keys registered within
-/// ssh agent .
+///   ssh agent .
 fn try_authenticate_via_ssh_agent(
     session: &mut Session,
     credentials: &impl SshCredentials,
@@ -258,3 +283,93 @@ fn try_authenticate_via_ssh_agent(
     agent.userauth(username, &chosen_key)?;
     Ok(())
 }
+
+/// Reads the non-blocking sources `stdout` and `stderr` alternately
+/// until both are drained and `is_eof` returns true.
+fn read_interleaved(
+    mut stdout: impl Read,
+    mut stderr: impl Read,
+    is_eof: impl Fn() -> bool,
+) -> AppResult<(String, String)> {
+    let (mut stdout_bytes, mut stderr_bytes) = (Vec::new(), Vec::new());
+    loop {
+        let read_stdout = read_available(&mut stdout, &mut stdout_bytes)
+            .context("Could not read output of executed command over ssh channel")?;
+        let read_stderr = read_available(&mut stderr, &mut stderr_bytes)
+            .context("Could not read error output of executed command over ssh channel")?;
+        if read_stdout + read_stderr == 0 {
+            if is_eof() {
+                break;
+            }
+            thread::sleep(READ_POLL_INTERVAL);
+        }
+    }
+    let stdout = String::from_utf8(stdout_bytes)
+        .context("Output of executed command over ssh is no valid UTF-8")?;
+    let stderr = String::from_utf8(stderr_bytes)
+        .context("Error output of executed command over ssh is no valid UTF-8")?;
+    Ok((stdout, stderr))
+}
+
+/// Appends everything which can be read without blocking to `target`.
+/// Returns the number of bytes read.
+fn read_available(source: &mut impl Read, target: &mut Vec<u8>) -> io::Result<usize> {
+    let mut buffer = [0; 4096];
+    let mut total = 0;
+    loop {
+        match source.read(&mut buffer) {
+            Ok(0) => return Ok(total),
+            Ok(read) => {
+                target.extend_from_slice(&buffer[..read]);
+                total += read;
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(total),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+    use super::*;
+
+    /// Source which returns its chunks one per read.
+    /// Every `None` is a read which would block.
+    struct Chunks(Rc<RefCell<VecDeque<Option<&'static str>>>>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            match self.0.borrow_mut().pop_front() {
+                Some(Some(chunk)) => {
+                    buffer[..chunk.len()].copy_from_slice(chunk.as_bytes());
+                    Ok(chunk.len())
+                }
+                Some(None) => Err(io::ErrorKind::WouldBlock.into()),
+                None => Ok(0),
+            }
+        }
+    }
+
+    fn chunks(chunks: Vec<Option<&'static str>>) -> Rc<RefCell<VecDeque<Option<&'static str>>>> {
+        Rc::new(RefCell::new(chunks.into()))
+    }
+
+    #[test]
+    fn read_stderr_while_stdout_is_not_finished() {
+        let stdout = chunks(vec![Some("first "), None, None, Some("second")]);
+        let stderr = chunks(vec![Some("warning"), None, Some(" again")]);
+        let (left_stdout, left_stderr) = (Rc::clone(&stdout), Rc::clone(&stderr));
+
+        let actual = read_interleaved(Chunks(stdout), Chunks(stderr), || {
+            left_stdout.borrow().is_empty() && left_stderr.borrow().is_empty()
+        })
+        .unwrap();
+
+        assert_eq!(
+            ("first second".to_owned(), "warning again".to_owned()),
+            actual
+        );
+    }
+}
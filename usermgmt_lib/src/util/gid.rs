@@ -22,6 +22,15 @@ impl ResolvedGid {
         };
         Self { gid, id: group }
     }
+
+    /// Finds the group whose group id in the configuration is the given one.
+    /// Returns `None` if no group has this group id.
+    pub fn from_gid(gid: i32, config: &MgmtConfig) -> Option<Self> {
+        [Group::Staff, Group::Student, Group::Faculty]
+            .into_iter()
+            .map(|group| Self::new(group, config))
+            .find(|resolved| resolved.gid == gid)
+    }
 }
 
 #[cfg(test)]
@@ -42,4 +51,21 @@ mod testing {
         assert_eq!(staff_gid, ResolvedGid::new(Group::Staff, &config).gid());
         assert_eq!(faculty_gid, ResolvedGid::new(Group::Faculty, &config).gid());
     }
+
+    #[test]
+    fn find_group_by_gid() {
+        let staff_gid = 42;
+        let config = MgmtConfig {
+            staff_gid,
+            student_gid: 200,
+            faculty_gid: 3001,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(Group::Staff),
+            ResolvedGid::from_gid(staff_gid, &config).map(|resolved| resolved.id())
+        );
+        assert_eq!(None, ResolvedGid::from_gid(7, &config));
+    }
 }
@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+
+use anyhow::{ensure, Context};
+use log::debug;
+use regex::Regex;
+
+use crate::{config::MgmtConfig, prelude::AppResult};
+
+use super::ensure_valid_username;
+
+/// File with the accounts of the local machine.
+const PASSWD_PATH: &str = "/etc/passwd";
+/// Accounts with an UID below this value are considered system accounts.
+const FIRST_NON_SYSTEM_UID: u32 = 1_000;
+
+/// Rules from the configuration which every username must follow
+/// in addition to the basic rules of [`ensure_valid_username`].
+#[derive(Debug, Default)]
+pub struct UsernamePolicy {
+    regex: Option<Regex>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    reserved: HashSet<String>,
+    lowercase_only: bool,
+}
+
+impl UsernamePolicy {
+    /// # Errors
+    ///
+    /// - If the field `username_regex` of parameter `config` is not a valid regular expression.
+    /// - If system accounts are to be reserved and the file `/etc/passwd` could not be read.
+    pub fn from_config(config: &MgmtConfig) -> AppResult<Self> {
+        let system_accounts = if config.username_reserve_system_accounts {
+            let passwd = std::fs::read_to_string(PASSWD_PATH)
+                .with_context(|| format!("Unable to read system accounts from {}", PASSWD_PATH))?;
+            system_accounts(&passwd)
+        } else {
+            Vec::new()
+        };
+        Self::new(config, system_accounts)
+    }
+
+    /// Same as [`UsernamePolicy::from_config`] except that the system accounts to reserve
+    /// are given by the parameter `system_accounts`.
+    pub fn new(config: &MgmtConfig, system_accounts: Vec<String>) -> AppResult<Self> {
+        let regex = config
+            .username_regex
+            .as_deref()
+            .map(|pattern| {
+                // Anchored, so the whole username has to match and not only a part of it
+                Regex::new(&format!("^(?:{})$", pattern))
+                    .with_context(|| format!("Invalid regex ({}) for usernames", pattern))
+            })
+            .transpose()?;
+        let reserved = config
+            .username_reserved
+            .iter()
+            .cloned()
+            .chain(system_accounts)
+            .collect();
+
+        Ok(Self {
+            regex,
+            min_length: config.username_min_length,
+            max_length: config.username_max_length,
+            reserved,
+            lowercase_only: config.username_lowercase_only,
+        })
+    }
+
+    /// Maximum length of a username if configured.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// # Errors
+    ///
+    /// - If the username is not valid. See [`ensure_valid_username`]
+    /// - If the username violates one of the rules of this policy.
+    pub fn check(&self, username: &str) -> AppResult {
+        ensure_valid_username(username)?;
+
+        let length = username.chars().count();
+        if let Some(min_length) = self.min_length {
+            ensure!(
+                length >= min_length,
+                "Username ({}) must have at least {} characters",
+                username,
+                min_length
+            );
+        }
+        if let Some(max_length) = self.max_length {
+            ensure!(
+                length <= max_length,
+                "Username ({}) must have at most {} characters",
+                username,
+                max_length
+            );
+        }
+        ensure!(
+            !self.lowercase_only || !username.chars().any(|next| next.is_uppercase()),
+            "Username ({}) must not contain uppercase letters",
+            username
+        );
+        ensure!(
+            !self.reserved.contains(username),
+            "Username ({}) is reserved",
+            username
+        );
+        if let Some(regex) = &self.regex {
+            ensure!(
+                regex.is_match(username),
+                "Username ({}) does not match the configured pattern ({})",
+                username,
+                regex
+            );
+        }
+
+        debug!("Username ({}) follows the username policy", username);
+        Ok(())
+    }
+}
+
+/// Names of accounts with an UID below 1000 within the content of a passwd file.
+fn system_accounts(passwd: &str) -> Vec<String> {
+    passwd
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid: u32 = fields.nth(1)?.parse().ok()?;
+            (uid < FIRST_NON_SYSTEM_UID).then(|| name.to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    fn policy(config: MgmtConfig) -> UsernamePolicy {
+        UsernamePolicy::new(&config, vec!["daemon".to_owned()]).unwrap()
+    }
+
+    #[test]
+    fn accept_username_following_all_rules() {
+        let actual = policy(MgmtConfig {
+            username_regex: Some("[a-z]+[0-9]*".to_owned()),
+            username_min_length: Some(3),
+            username_max_length: Some(8),
+            username_lowercase_only: true,
+            ..Default::default()
+        });
+
+        assert!(actual.check("doejo").is_ok());
+        assert!(actual.check("doejo12").is_ok());
+    }
+
+    #[test]
+    fn reject_username_breaking_rules() {
+        let actual = policy(MgmtConfig {
+            username_regex: Some("[a-z]+[0-9]*".to_owned()),
+            username_min_length: Some(3),
+            username_max_length: Some(8),
+            username_lowercase_only: true,
+            ..Default::default()
+        });
+
+        let errors: Vec<String> = [
+            "do",
+            "doejohnny",
+            "DoeJo",
+            "root",
+            "daemon",
+            "12doe",
+            "doe_jo",
+            "doe;jo",
+        ]
+        .into_iter()
+        .map(|username| actual.check(username).unwrap_err().to_string())
+        .collect();
+        insta::assert_yaml_snapshot!(errors);
+    }
+
+    #[test]
+    fn error_for_invalid_regex() {
+        let actual = UsernamePolicy::new(
+            &MgmtConfig {
+                username_regex: Some("[a-z".to_owned()),
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn take_only_system_accounts_from_passwd() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n\
+                      daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin\n\
+                      nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin\n\
+                      somebody:x:1000:1000:Some Body:/home/somebody:/bin/bash\n\
+                      broken line\n";
+        assert_eq!(vec!["root", "daemon"], system_accounts(passwd));
+    }
+}
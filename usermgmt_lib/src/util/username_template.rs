@@ -0,0 +1,262 @@
+use anyhow::{bail, Context};
+use log::{debug, info};
+
+use crate::prelude::AppResult;
+
+const FIRSTNAME: &str = "firstname";
+const LASTNAME: &str = "lastname";
+/// Highest numeric suffix tried before the generation of a username is given up.
+const MAX_SUFFIX: u32 = 99;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Firstname(Option<usize>),
+    Lastname(Option<usize>),
+}
+
+/// Template to derive a username from the first and last name of a user.
+///
+/// Placeholders are `{firstname}` and `{lastname}`.
+/// A placeholder can be limited to the first n characters via `{lastname:6}`.
+/// Every other text is taken literally.
+/// Example: `{lastname:6}{firstname:2}` turns "Jürgen Müller" into "muelleju".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsernameTemplate(Vec<TemplatePart>);
+
+impl UsernameTemplate {
+    /// # Errors
+    ///
+    /// - If a placeholder is not closed or unknown.
+    /// - If the limit of a placeholder is not a number.
+    /// - If the template has no placeholder at all.
+    pub fn new(template: &str) -> AppResult<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(TemplatePart::Literal(rest[..start].to_owned()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .with_context(|| format!("Placeholder not closed in template ({})", template))?
+                + start;
+            parts.push(Self::parse_placeholder(&rest[start + 1..end], template)?);
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Literal(rest.to_owned()));
+        }
+
+        if !parts
+            .iter()
+            .any(|part| !matches!(part, TemplatePart::Literal(_)))
+        {
+            bail!(
+                "Template ({}) needs at least one placeholder {{{}}} or {{{}}}",
+                template,
+                FIRSTNAME,
+                LASTNAME
+            );
+        }
+        Ok(Self(parts))
+    }
+
+    /// Username from the template without any suffix.
+    /// Umlauts and other accented letters are transliterated, the result is lowercase.
+    /// Characters other than ASCII letters and digits are dropped from the names.
+    pub fn render(&self, firstname: &str, lastname: &str) -> String {
+        self.0
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(text) => text.clone(),
+                TemplatePart::Firstname(limit) => truncate(&transliterate(firstname), *limit),
+                TemplatePart::Lastname(limit) => truncate(&transliterate(lastname), *limit),
+            })
+            .collect()
+    }
+
+    /// Renders the username and appends the numeric suffixes 1, 2, 3 and so on
+    /// until the parameter `is_available` accepts the username.
+    /// With parameter `max_length` the rendered username is shortened,
+    /// so that it fits together with its suffix into this length.
+    ///
+    /// # Errors
+    ///
+    /// - If the parameter `is_available` returns an error.
+    /// - If no available username was found up to the suffix 99.
+    pub fn generate(
+        &self,
+        firstname: &str,
+        lastname: &str,
+        max_length: Option<usize>,
+        mut is_available: impl FnMut(&str) -> AppResult<bool>,
+    ) -> AppResult<String> {
+        let base = self.render(firstname, lastname);
+        let with_suffix = |suffix: String| {
+            let limit = max_length.map(|max_length| max_length.saturating_sub(suffix.len()));
+            format!("{}{}", truncate(&base, limit), suffix)
+        };
+        let candidates = std::iter::once(String::new())
+            .chain((1..=MAX_SUFFIX).map(|suffix| suffix.to_string()))
+            .map(with_suffix);
+        for candidate in candidates {
+            if is_available(&candidate)? {
+                info!("Generated username {}", candidate);
+                return Ok(candidate);
+            }
+            debug!("Generated username {} is not available", candidate);
+        }
+        bail!(
+            "No available username found for {} {} up to {}",
+            firstname,
+            lastname,
+            with_suffix(MAX_SUFFIX.to_string())
+        )
+    }
+
+    fn parse_placeholder(placeholder: &str, template: &str) -> AppResult<TemplatePart> {
+        let (name, limit) = match placeholder.split_once(':') {
+            Some((name, limit)) => {
+                let limit = limit.trim().parse::<usize>().with_context(|| {
+                    format!(
+                        "Limit ({}) of placeholder in template ({}) is not a number",
+                        limit, template
+                    )
+                })?;
+                (name.trim(), Some(limit))
+            }
+            None => (placeholder.trim(), None),
+        };
+        match name {
+            FIRSTNAME => Ok(TemplatePart::Firstname(limit)),
+            LASTNAME => Ok(TemplatePart::Lastname(limit)),
+            unknown => bail!(
+                "Unknown placeholder ({}) in template ({}). Only {} and {} are allowed",
+                unknown,
+                template,
+                FIRSTNAME,
+                LASTNAME
+            ),
+        }
+    }
+}
+
+/// Lowercase ASCII version of a name.
+/// German umlauts become two letters like "ü" to "ue".
+/// Other accented letters lose their accent. Every other character is dropped.
+fn transliterate(name: &str) -> String {
+    let mut ascii = String::with_capacity(name.len());
+    for character in name.chars().flat_map(char::to_lowercase) {
+        let replacement = match character {
+            'ä' | 'æ' => "ae",
+            'ö' | 'œ' => "oe",
+            'ü' => "ue",
+            'ß' => "ss",
+            'à' | 'á' | 'â' | 'ã' | 'å' => "a",
+            'ç' | 'č' | 'ć' => "c",
+            'è' | 'é' | 'ê' | 'ë' | 'ě' => "e",
+            'ì' | 'í' | 'î' | 'ï' => "i",
+            'ñ' | 'ń' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ø' => "o",
+            'ř' => "r",
+            'š' | 'ś' => "s",
+            'ù' | 'ú' | 'û' | 'ů' => "u",
+            'ý' | 'ÿ' => "y",
+            'ž' | 'ź' | 'ż' => "z",
+            'ł' => "l",
+            other if other.is_ascii_alphanumeric() => {
+                ascii.push(other);
+                continue;
+            }
+            _ => continue,
+        };
+        ascii.push_str(replacement);
+    }
+    ascii
+}
+
+fn truncate(text: &str, limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) => text.chars().take(limit).collect(),
+        None => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::{config::MgmtConfig, util::UsernamePolicy};
+
+    #[test]
+    fn render_names_with_umlauts_and_limits() {
+        let template = UsernameTemplate::new("{lastname:6}{firstname:2}").unwrap();
+
+        let actual: Vec<String> = [
+            ("Jürgen", "Müller"),
+            ("Ann", "Li"),
+            ("José", "Núñez-García"),
+            ("Ægir", "Strauß"),
+            ("Mary Ann", "O'Neil"),
+        ]
+        .into_iter()
+        .map(|(firstname, lastname)| template.render(firstname, lastname))
+        .collect();
+
+        insta::assert_yaml_snapshot!(actual);
+    }
+
+    #[test]
+    fn keep_literal_text() {
+        let template = UsernameTemplate::new("x_{firstname:1}.{lastname}").unwrap();
+        assert_eq!("x_j.doe", template.render("John", "Doe"));
+    }
+
+    #[test]
+    fn append_suffix_if_name_taken() {
+        let template = UsernameTemplate::new("{lastname:6}{firstname:2}").unwrap();
+        let taken = ["muelleju", "muelleju1"];
+
+        let actual = template
+            .generate("Jürgen", "Müller", None, |candidate| {
+                Ok(!taken.contains(&candidate))
+            })
+            .unwrap();
+
+        assert_eq!("muelleju2", actual);
+    }
+
+    #[test]
+    fn shorten_name_to_fit_suffix_into_max_length() {
+        let config = MgmtConfig {
+            username_max_length: Some(8),
+            ..Default::default()
+        };
+        let policy = UsernamePolicy::new(&config, Vec::new()).unwrap();
+        let template = UsernameTemplate::new("{lastname:6}{firstname:2}").unwrap();
+        let taken = ["muelleju", "muellej1"];
+
+        let actual = template
+            .generate("Jürgen", "Müller", policy.max_length(), |candidate| {
+                Ok(policy.check(candidate).is_ok() && !taken.contains(&candidate))
+            })
+            .unwrap();
+
+        assert_eq!("muellej2", actual);
+    }
+
+    #[test]
+    fn error_if_no_name_available() {
+        let template = UsernameTemplate::new("{lastname}").unwrap();
+        assert!(template
+            .generate("John", "Doe", None, |_| Ok(false))
+            .is_err());
+    }
+
+    #[test]
+    fn error_for_invalid_templates() {
+        for invalid in ["{lastname", "{middlename}", "{lastname:six}", "static", ""] {
+            assert!(UsernameTemplate::new(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
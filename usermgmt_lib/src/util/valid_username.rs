@@ -0,0 +1,74 @@
+use anyhow::ensure;
+
+use crate::prelude::AppResult;
+
+/// Maximum length of a username accepted by common Linux tools like `useradd`.
+const MAX_USERNAME_LENGTH: usize = 32;
+
+/// Ensures that the username of a new user is a portable Unix username.
+/// A username is passed as an argument to commands on remote machines and is part of LDAP entries.
+/// Restricting it to a small set of characters keeps it from being interpreted in any other way.
+///
+/// # Errors
+///
+/// - If the username is empty or longer than 32 characters.
+/// - If the username does not start with an ASCII letter or an underscore.
+/// - If the username contains characters other than ASCII letters, digits, '_', '-' and '.'.
+pub fn ensure_valid_username(username: &str) -> AppResult {
+    ensure!(
+        !username.is_empty() && username.len() <= MAX_USERNAME_LENGTH,
+        "Username ({}) must have between 1 and {} characters",
+        username,
+        MAX_USERNAME_LENGTH
+    );
+    ensure!(
+        username
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_'),
+        "Username ({}) must start with a letter or an underscore",
+        username
+    );
+    ensure!(
+        username
+            .chars()
+            .all(|next| next.is_ascii_alphanumeric() || "_-.".contains(next)),
+        "Username ({}) must only contain letters, digits, '_', '-' or '.'",
+        username
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+
+    #[test]
+    fn accept_portable_usernames() {
+        for valid in ["somebody", "Some_User", "_svc", "doe.j-2", "a"] {
+            assert!(ensure_valid_username(valid).is_ok(), "{}", valid);
+        }
+    }
+
+    #[test]
+    fn reject_hostile_usernames() {
+        for invalid in [
+            "",
+            "some body",
+            "x;reboot",
+            "$(reboot)",
+            "`reboot`",
+            "a|b",
+            "it's",
+            "-rf",
+            "1user",
+            "uid=admin,dc=example",
+            "admin)(uid=*",
+            "müller",
+            "a\nb",
+            "averyveryveryveryveryverylongname",
+        ] {
+            assert!(ensure_valid_username(invalid).is_err(), "{}", invalid);
+        }
+    }
+}